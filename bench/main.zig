@@ -1,6 +1,10 @@
 const std = @import("std");
 const zbench = @import("zbench");
-const parser = @import("parser");
+const strace = @import("strace");
+const parser = strace.parser;
+const Database = strace.database.Database;
+const file_processor = strace.file_processor;
+const worker_pool = strace.worker_pool;
 
 // ============================================================================
 // PARSER BENCHMARKS - Understanding the 3-way dispatch cost
@@ -196,6 +200,85 @@ fn benchmarkReadAndParse10000(allocator: std.mem.Allocator) void {
     std.mem.doNotOptimizeAway(count);
 }
 
+// ============================================================================
+// END-TO-END BENCHMARKS - Read, parse and append into DuckDB
+// ============================================================================
+
+// Written at startup and removed on exit, so the benchmark needs no external traces
+const E2E_FIXTURE = "zig-cache/bench-e2e-10000.trace";
+const E2E_LINES = 10_000;
+
+// Cycles through the samples above in roughly the realistic mix (12% errors)
+fn writeFixture(path: []const u8, line_count: usize) !void {
+    const samples = [_][]const u8{
+        REGULAR_SIMPLE,        REGULAR_SIMPLE, REGULAR_SIMPLE,     REGULAR_NESTED_PARENS,
+        REGULAR_NESTED_PARENS, REGULAR_LONG,   REGULAR_LONG,       REGULAR_WITH_ERROR,
+    };
+
+    try std.fs.cwd().makePath(std.fs.path.dirname(path) orelse ".");
+    const file = try std.fs.cwd().createFile(path, .{});
+    defer file.close();
+
+    var buffer: [16384]u8 = undefined;
+    var writer = file.writer(&buffer);
+    for (0..line_count) |i| {
+        try writer.interface.writeAll(samples[i % samples.len]);
+        try writer.interface.writeAll("\n");
+    }
+    try writer.interface.flush();
+}
+
+// Benchmark: processFile into a fresh in-memory database (the appender path the CLI uses)
+// Opening the database is included in each run, but is small next to 10K appends
+const ProcessFileBenchmark = struct {
+    path: []const u8,
+
+    pub fn run(self: ProcessFileBenchmark, allocator: std.mem.Allocator) void {
+        var db = Database.init(":memory:") catch unreachable;
+        defer db.deinit();
+
+        db.beginAppend() catch unreachable;
+        const stats = file_processor.processFile(allocator, &db, self.path) catch unreachable;
+        db.endAppend() catch unreachable;
+        std.mem.doNotOptimizeAway(stats);
+    }
+};
+
+// ============================================================================
+// PARALLEL SCALING - The worker pool at 1, 2, 4 and one worker per CPU
+// ============================================================================
+
+// Several files so every worker count has whole files to pull; large enough that the
+// pool's 100ms progress polling is small next to a run
+const SCALING_FILES = 8;
+const SCALING_LINES_PER_FILE = 50_000;
+const SCALING_LINES = SCALING_FILES * SCALING_LINES_PER_FILE;
+
+// Benchmark: processFilesParallel with a fixed worker count into a fresh in-memory database
+// Each worker appends through its own connection to the shared instance, as in the CLI
+const ParallelBenchmark = struct {
+    files: []const []const u8,
+    workers: usize,
+
+    pub fn run(self: ParallelBenchmark, allocator: std.mem.Allocator) void {
+        var db = Database.init(":memory:") catch unreachable;
+        defer db.deinit();
+
+        const stats = worker_pool.processFilesParallel(allocator, &db, self.files, self.workers) catch unreachable;
+        std.mem.doNotOptimizeAway(stats);
+    }
+};
+
+// Lines per second from the mean of a result's timings
+fn printThroughput(writer: *std.Io.Writer, name: []const u8, lines: usize, timings_ns: []const u64) !void {
+    if (timings_ns.len == 0) return;
+    var total_ns: u128 = 0;
+    for (timings_ns) |ns| total_ns += ns;
+    const mean_ns = @as(f64, @floatFromInt(total_ns)) / @as(f64, @floatFromInt(timings_ns.len));
+    const lines_per_sec = @as(f64, @floatFromInt(lines)) / (mean_ns / std.time.ns_per_s);
+    try writer.print("  {s}: {d:.0} lines/s ({d} lines, mean {d:.3}ms, including database inserts)\n", .{ name, lines_per_sec, lines, mean_ns / std.time.ns_per_ms });
+}
+
 // ============================================================================
 // MAIN
 // ============================================================================
@@ -205,6 +288,9 @@ pub fn main() !void {
     var stdout = std.fs.File.stdout().writerStreaming(&.{});
     const writer = &stdout.interface;
 
+    // No progress bar from the worker pool between result rows
+    strace.log.level = .quiet;
+
     var bench = zbench.Benchmark.init(allocator, .{});
     defer bench.deinit();
 
@@ -232,6 +318,36 @@ pub fn main() !void {
     try bench.add("FileIO: Read+Parse 1K", benchmarkReadAndParse1000, .{});
     try bench.add("FileIO: Read+Parse 10K", benchmarkReadAndParse10000, .{});
 
+    // End-to-end: includes DuckDB appends
+    try writeFixture(E2E_FIXTURE, E2E_LINES);
+    defer std.fs.cwd().deleteFile(E2E_FIXTURE) catch {};
+    const e2e_name = "E2E: processFile 10K (in-memory DB)";
+    try bench.addParam(e2e_name, &ProcessFileBenchmark{ .path = E2E_FIXTURE }, .{});
+
+    // Parallel scaling over the same files at each worker count
+    var scaling_paths: [SCALING_FILES][]const u8 = undefined;
+    var path_buffers: [SCALING_FILES][64]u8 = undefined;
+    for (&scaling_paths, &path_buffers, 0..) |*path, *buffer, i| {
+        path.* = try std.fmt.bufPrint(buffer, "zig-cache/bench-scaling-{d}.trace", .{i});
+        try writeFixture(path.*, SCALING_LINES_PER_FILE);
+    }
+    defer for (scaling_paths) |path| std.fs.cwd().deleteFile(path) catch {};
+
+    const cpu_count = try std.Thread.getCpuCount();
+    var worker_counts = std.ArrayListUnmanaged(usize){};
+    defer worker_counts.deinit(allocator);
+    for ([_]usize{ 1, 2, 4, cpu_count }) |workers| {
+        if (std.mem.indexOfScalar(usize, worker_counts.items, workers) == null) try worker_counts.append(allocator, workers);
+    }
+
+    var scaling_names: [4][64]u8 = undefined;
+    var scaling_benchmarks: [4]ParallelBenchmark = undefined;
+    for (worker_counts.items, 0..) |workers, i| {
+        scaling_benchmarks[i] = .{ .files = &scaling_paths, .workers = workers };
+        const name = try std.fmt.bufPrint(&scaling_names[i], "Scaling: {d} worker(s), {d} files", .{ workers, SCALING_FILES });
+        try bench.addParam(name, &scaling_benchmarks[i], .{});
+    }
+
     try writer.writeAll("\n");
     try zbench.prettyPrintHeader(writer);
 
@@ -239,7 +355,7 @@ pub fn main() !void {
     const progress = std.Progress.start(.{});
     defer progress.end();
 
-    const suite_node = progress.start("Benchmarks", 16);
+    const suite_node = progress.start("Benchmarks", 17 + worker_counts.items.len);
     defer suite_node.end();
 
     var iter = try bench.iterator();
@@ -273,6 +389,12 @@ pub fn main() !void {
             completed_benchmarks += 1;
             suite_node.setCompletedItems(completed_benchmarks);
             try r.prettyPrint(allocator, writer, tty_config);
+            // zbench reports time per run; the end-to-end runs also get lines/s
+            if (std.mem.eql(u8, r.name, e2e_name)) {
+                try printThroughput(writer, r.name, E2E_LINES, r.readings.timings_ns);
+            } else if (std.mem.startsWith(u8, r.name, "Scaling: ")) {
+                try printThroughput(writer, r.name, SCALING_LINES, r.readings.timings_ns);
+            }
         },
     };
 
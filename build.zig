@@ -4,6 +4,11 @@ pub fn build(b: *std.Build) void {
     const target = b.standardTargetOptions(.{});
     const optimize = b.standardOptimizeOption(.{});
 
+    // The version in build.zig.zon, recorded in runs.tool_version
+    const options = b.addOptions();
+    options.addOption([]const u8, "version", @import("build.zig.zon").version);
+    const build_options = options.createModule();
+
     const exe = b.addExecutable(.{
         .name = "strace-to-duckdb",
         .root_module = b.createModule(.{
@@ -14,6 +19,8 @@ pub fn build(b: *std.Build) void {
         }),
     });
 
+    exe.root_module.addImport("build_options", build_options);
+
     // Link DuckDB library
     exe.linkSystemLibrary("duckdb");
 
@@ -47,9 +54,10 @@ pub fn build(b: *std.Build) void {
     // Add zBench module
     bench.root_module.addImport("zbench", zbench_dep.module("zbench"));
 
-    // Add parser only - other modules cause conflicts due to shared types.zig
-    bench.root_module.addAnonymousImport("parser", .{
-        .root_source_file = b.path("src/parser.zig"),
+    // One module for all sources - separate parser/database imports would each pull in types.zig
+    bench.root_module.addAnonymousImport("strace", .{
+        .root_source_file = b.path("src/lib.zig"),
+        .imports = &.{.{ .name = "build_options", .module = build_options }},
     });
 
     // Link DuckDB and libc for benchmarks
@@ -77,9 +85,21 @@ pub fn build(b: *std.Build) void {
     const modules = [_][]const u8{
         "src/types.zig",
         "src/utils.zig",
+        "src/errno.zig",
+        "src/errors.zig",
+        "src/inputs.zig",
+        "src/log.zig",
+        "src/proc.zig",
+        "src/rejects.zig",
+        "src/report.zig",
         "src/parser.zig",
+        "src/explain.zig",
+        "src/decoders.zig",
+        "src/pairing.zig",
+        "src/threads.zig",
         "src/progress.zig",
         "src/database.zig",
+        "src/sink.zig",
         "src/file_processor.zig",
         "src/worker_pool.zig",
     };
@@ -94,6 +114,8 @@ pub fn build(b: *std.Build) void {
             }),
         });
 
+        module_test.root_module.addImport("build_options", build_options);
+
         // Link DuckDB for modules that need it
         module_test.linkSystemLibrary("duckdb");
 
@@ -1,12 +1,369 @@
 const std = @import("std");
+const build_options = @import("build_options");
 const types = @import("types.zig");
+const errors = @import("errors.zig");
 const Syscall = types.Syscall;
+const FileStats = types.FileStats;
 
 // Import DuckDB C API
 const c = @cImport({
     @cInclude("duckdb.h");
 });
 
+/// Every error a Database method returns itself; methods that allocate add std.mem.Allocator.Error
+pub const DatabaseError = errors.DatabaseError;
+
+/// Column definition for the syscalls table
+pub const Column = struct {
+    name: []const u8,
+    sql_type: []const u8,
+};
+
+/// Columns of the syscalls table, in appender order
+/// Optional columns are always created (NULL when their option is off),
+/// so the schema does not depend on command-line options
+pub const syscall_columns = [_]Column{
+    .{ .name = "trace_file", .sql_type = "VARCHAR" },
+    .{ .name = "pid", .sql_type = "INTEGER" },
+    .{ .name = "timestamp", .sql_type = "VARCHAR" },
+    .{ .name = "syscall", .sql_type = "VARCHAR" },
+    .{ .name = "args", .sql_type = "TEXT" },
+    .{ .name = "return_value", .sql_type = "BIGINT" },
+    .{ .name = "error_code", .sql_type = "VARCHAR" },
+    .{ .name = "error_message", .sql_type = "VARCHAR" },
+    .{ .name = "duration", .sql_type = "DOUBLE" },
+    .{ .name = "unfinished", .sql_type = "BOOLEAN DEFAULT FALSE" },
+    .{ .name = "resumed", .sql_type = "BOOLEAN DEFAULT FALSE" },
+    .{ .name = "target_pid", .sql_type = "BIGINT" },
+    .{ .name = "signal", .sql_type = "VARCHAR" },
+    .{ .name = "return_value_raw", .sql_type = "VARCHAR" },
+    .{ .name = "run_id", .sql_type = "VARCHAR" },
+    .{ .name = "oldfd", .sql_type = "INTEGER" },
+    .{ .name = "newfd", .sql_type = "INTEGER" },
+    .{ .name = "peer_addr", .sql_type = "VARCHAR" },
+    .{ .name = "peer_port", .sql_type = "INTEGER" },
+    .{ .name = "clock_value_ns", .sql_type = "BIGINT" },
+    .{ .name = "clock_id", .sql_type = "VARCHAR" },
+    .{ .name = "command", .sql_type = "VARCHAR" },
+    .{ .name = "hour", .sql_type = "INTEGER" },
+    .{ .name = "bind_addr", .sql_type = "VARCHAR" },
+    .{ .name = "bind_port", .sql_type = "INTEGER" },
+    .{ .name = "backlog", .sql_type = "INTEGER" },
+    .{ .name = "args_raw", .sql_type = "TEXT" },
+    .{ .name = "return_annotation", .sql_type = "VARCHAR" },
+    .{ .name = "kind", .sql_type = "VARCHAR DEFAULT 'syscall'" },
+    .{ .name = "core_dumped", .sql_type = "BOOLEAN DEFAULT FALSE" },
+    .{ .name = "reassembled", .sql_type = "BOOLEAN DEFAULT FALSE" },
+    .{ .name = "time_of_day", .sql_type = "TIME" },
+    .{ .name = "wall_time", .sql_type = "TIMESTAMP" },
+    .{ .name = "env_count", .sql_type = "INTEGER" },
+    .{ .name = "raw_line", .sql_type = "VARCHAR" },
+    .{ .name = "line_number", .sql_type = "INTEGER" },
+    .{ .name = "seq", .sql_type = "BIGINT" },
+    .{ .name = "errno", .sql_type = "INTEGER" },
+    .{ .name = "fd", .sql_type = "INTEGER" },
+    .{ .name = "truncated", .sql_type = "BOOLEAN DEFAULT FALSE" },
+    .{ .name = "return_unknown", .sql_type = "BOOLEAN DEFAULT FALSE" },
+    .{ .name = "return_addr", .sql_type = "UBIGINT" },
+    .{ .name = "tid", .sql_type = "INTEGER" },
+    .{ .name = "rel_time", .sql_type = "DOUBLE" },
+    .{ .name = "arg_count", .sql_type = "INTEGER" },
+    .{ .name = "args_json", .sql_type = "VARCHAR" },
+    .{ .name = "mmap_prot", .sql_type = "VARCHAR" },
+    .{ .name = "mmap_flags", .sql_type = "VARCHAR" },
+    .{ .name = "mode", .sql_type = "INTEGER" },
+    .{ .name = "sa_family", .sql_type = "VARCHAR" },
+    .{ .name = "sock_addr", .sql_type = "VARCHAR" },
+    .{ .name = "sock_port", .sql_type = "INTEGER" },
+    .{ .name = "args_truncated", .sql_type = "BOOLEAN DEFAULT FALSE" },
+    .{ .name = "duration_ns", .sql_type = "BIGINT" },
+};
+
+/// Bump whenever syscall_columns changes
+/// Version 1 was the original 11-column schema (no schema_info table)
+pub const schema_version: i64 = 35;
+
+/// Column list for CREATE TABLE, generated from syscall_columns
+const column_definitions = blk: {
+    var defs: []const u8 = "";
+    for (syscall_columns, 0..) |col, i| {
+        const separator = if (i + 1 < syscall_columns.len) ",\n" else "\n";
+        defs = defs ++ "    " ++ col.name ++ " " ++ col.sql_type ++ separator;
+    }
+    break :blk defs;
+};
+
+/// Columns compared by removeDuplicateRows: all but run_id and seq, which differ between re-ingests
+const dedupe_columns = blk: {
+    var list: []const u8 = "";
+    for (syscall_columns) |col| {
+        if (std.mem.eql(u8, col.name, "run_id") or std.mem.eql(u8, col.name, "seq")) continue;
+        list = list ++ (if (list.len > 0) ", " else "") ++ col.name;
+    }
+    break :blk list;
+};
+
+/// Table rows go to unless --table names another
+pub const default_table = "syscalls";
+
+/// Longest name accepted by isValidTableName, so generated statements stay within their buffers
+pub const max_table_name_len = 63;
+
+/// Tables the schema creates alongside the rows table; --table can't reuse their names
+const reserved_tables = [_][]const u8{ "schema_info", "trace_files", "runs", "file_hashes", "syscall_aggregates", "process_tree" };
+
+/// Whether name may be used as the rows table (--table)
+/// Only plain identifiers ([A-Za-z_][A-Za-z0-9_]*) are allowed, since the name is spliced
+/// into SQL (in double quotes, so keywords such as order work); DuckDB compares identifiers
+/// case-insensitively, quoted or not, and so does the reserved check
+pub fn isValidTableName(name: []const u8) bool {
+    if (name.len == 0 or name.len > max_table_name_len) return false;
+    if (std.ascii.isDigit(name[0])) return false;
+    for (name) |ch| {
+        if (!std.ascii.isAlphanumeric(ch) and ch != '_') return false;
+    }
+    for (reserved_tables) |reserved| {
+        if (std.ascii.eqlIgnoreCase(name, reserved)) return false;
+    }
+    return true;
+}
+
+/// Free a list returned by Database.routedTables
+pub fn freeTableNames(allocator: std.mem.Allocator, names: []const [:0]u8) void {
+    for (names) |name| allocator.free(name);
+    allocator.free(names);
+}
+
+/// Differences between an existing database and the schema this build produces
+pub const SchemaCheck = struct {
+    allocator: std.mem.Allocator,
+    /// Version recorded in schema_info (null for unversioned databases)
+    version: ?i64,
+    /// Expected columns the database lacks
+    missing: []const []const u8,
+    /// Database columns this build would not produce
+    extra: []const []const u8,
+
+    /// True when the column sets match exactly
+    pub fn passed(self: SchemaCheck) bool {
+        return self.missing.len == 0 and self.extra.len == 0;
+    }
+
+    pub fn deinit(self: *SchemaCheck) void {
+        for (self.extra) |name| self.allocator.free(name);
+        self.allocator.free(self.extra);
+        self.allocator.free(self.missing);
+    }
+};
+
+/// Next row of an insertIter iterator, whether or not its next() can fail
+fn nextRow(iter: anytype) !?Syscall {
+    const row = iter.next();
+    return if (comptime @typeInfo(@TypeOf(row)) == .error_union) try row else row;
+}
+
+/// Iterator over a slice, for appendBatch
+const SliceRows = struct {
+    items: []const Syscall,
+    index: usize = 0,
+
+    fn next(self: *SliceRows) ?Syscall {
+        if (self.index >= self.items.len) return null;
+        defer self.index += 1;
+        return self.items[self.index];
+    }
+};
+
+/// Append a nullable VARCHAR column value
+fn appendOptionalVarchar(appender: c.duckdb_appender, value: ?[]const u8) !void {
+    if (value) |v| {
+        if (c.duckdb_append_varchar_length(appender, @ptrCast(v.ptr), @intCast(v.len)) == c.DuckDBError) {
+            return error.AppendFailed;
+        }
+    } else {
+        if (c.duckdb_append_null(appender) == c.DuckDBError) {
+            return error.AppendFailed;
+        }
+    }
+}
+
+/// Append a nullable BIGINT column value
+fn appendOptionalInt64(appender: c.duckdb_appender, value: ?i64) !void {
+    if (value) |v| {
+        if (c.duckdb_append_int64(appender, v) == c.DuckDBError) {
+            return error.AppendFailed;
+        }
+    } else {
+        if (c.duckdb_append_null(appender) == c.DuckDBError) {
+            return error.AppendFailed;
+        }
+    }
+}
+
+/// Append a nullable DOUBLE column value
+fn appendOptionalDouble(appender: c.duckdb_appender, value: ?f64) !void {
+    if (value) |v| {
+        if (c.duckdb_append_double(appender, v) == c.DuckDBError) {
+            return error.AppendFailed;
+        }
+    } else {
+        if (c.duckdb_append_null(appender) == c.DuckDBError) {
+            return error.AppendFailed;
+        }
+    }
+}
+
+/// Append a nullable UBIGINT column value
+fn appendOptionalUInt64(appender: c.duckdb_appender, value: ?u64) !void {
+    if (value) |v| {
+        if (c.duckdb_append_uint64(appender, v) == c.DuckDBError) {
+            return error.AppendFailed;
+        }
+    } else {
+        if (c.duckdb_append_null(appender) == c.DuckDBError) {
+            return error.AppendFailed;
+        }
+    }
+}
+
+/// Write text as a quoted SQL string literal; single quotes are doubled
+fn writeSqlString(writer: *std.Io.Writer, text: []const u8) !void {
+    try writer.writeByte('\'');
+    for (text) |ch| {
+        if (ch == '\'') {
+            try writer.writeAll("''");
+        } else {
+            try writer.writeByte(ch);
+        }
+    }
+    try writer.writeByte('\'');
+}
+
+fn containsName(names: []const []u8, name: []const u8) bool {
+    for (names) |n| {
+        if (std.mem.eql(u8, n, name)) return true;
+    }
+    return false;
+}
+
+fn isSchemaColumn(name: []const u8) bool {
+    for (syscall_columns) |col| {
+        if (std.mem.eql(u8, col.name, name)) return true;
+    }
+    return false;
+}
+
+/// Append a nullable TIME column value given in microseconds since midnight
+fn appendOptionalTime(appender: c.duckdb_appender, micros: ?i64) !void {
+    if (micros) |v| {
+        if (c.duckdb_append_time(appender, .{ .micros = v }) == c.DuckDBError) {
+            return error.AppendFailed;
+        }
+    } else {
+        if (c.duckdb_append_null(appender) == c.DuckDBError) {
+            return error.AppendFailed;
+        }
+    }
+}
+
+/// Append a nullable TIMESTAMP column value given in microseconds since the epoch
+fn appendOptionalTimestamp(appender: c.duckdb_appender, micros: ?i64) !void {
+    if (micros) |v| {
+        if (c.duckdb_append_timestamp(appender, .{ .micros = v }) == c.DuckDBError) {
+            return error.AppendFailed;
+        }
+    } else {
+        if (c.duckdb_append_null(appender) == c.DuckDBError) {
+            return error.AppendFailed;
+        }
+    }
+}
+
+/// Append a nullable INTEGER column value
+fn appendOptionalInt32(appender: c.duckdb_appender, value: ?i32) !void {
+    if (value) |v| {
+        if (c.duckdb_append_int32(appender, v) == c.DuckDBError) {
+            return error.AppendFailed;
+        }
+    } else {
+        if (c.duckdb_append_null(appender) == c.DuckDBError) {
+            return error.AppendFailed;
+        }
+    }
+}
+
+/// Copy one VARCHAR cell out of a query result (NULL becomes an empty string)
+fn dupeVarchar(allocator: std.mem.Allocator, result: *c.duckdb_result, column: usize, row: usize) ![]u8 {
+    const value = c.duckdb_value_varchar(result, @intCast(column), @intCast(row));
+    if (value == null) return allocator.dupe(u8, "");
+    defer c.duckdb_free(value);
+    return allocator.dupe(u8, std.mem.span(value));
+}
+
+/// One row of the syscall_aggregates table
+pub const SyscallAggregate = struct {
+    syscall: []const u8,
+    count: i64,
+    error_count: i64,
+    total_duration: f64,
+};
+
+/// One row of the runs table (run_id is the connection's)
+pub const RunInfo = struct {
+    started_at_us: i64,
+    finished_at_us: i64,
+    tool_version: []const u8,
+    file_count: usize,
+    total_lines: usize,
+};
+
+/// One row of syscallCounts; the name is owned by the caller (see freeSyscallRows)
+pub const SyscallCount = struct {
+    syscall: []u8,
+    count: i64,
+};
+
+/// One row of timeBySyscall; the name is owned by the caller (see freeSyscallRows)
+pub const SyscallTime = struct {
+    syscall: []u8,
+    total_duration: f64,
+    /// Rows with a duration; rows without one are left out
+    count: i64,
+};
+
+/// Free a slice returned by one of the per-syscall query helpers
+pub fn freeSyscallRows(allocator: std.mem.Allocator, rows: anytype) void {
+    for (rows) |row| allocator.free(row.syscall);
+    allocator.free(rows);
+}
+
+/// Result of comparing the rows in the database against the processor's counters
+pub const RowCountCheck = struct {
+    expected: i64,
+    actual: i64,
+
+    /// True when every parsed line became exactly one row
+    pub fn passed(self: RowCountCheck) bool {
+        return self.expected == self.actual;
+    }
+
+    /// Rows in the database minus rows expected (negative means rows were dropped)
+    pub fn delta(self: RowCountCheck) i64 {
+        return self.actual - self.expected;
+    }
+};
+
+/// DuckDB resource settings applied right after the database is opened
+/// Null leaves DuckDB's own default in place
+pub const Settings = struct {
+    /// Table the schema is created for and rows are appended to; must pass isValidTableName
+    table: [:0]const u8 = default_table,
+    /// memory_limit value, e.g. "4GB" (validated loosely by the caller)
+    memory_limit: ?[]const u8 = null,
+    /// Threads DuckDB may use for its own work, independent of our worker count
+    threads: ?usize = null,
+};
+
 /// Database handle for strace data
 pub const Database = struct {
     db: c.duckdb_database,
@@ -14,11 +371,26 @@ pub const Database = struct {
     path: []const u8,
     appender: ?c.duckdb_appender,
     owns_db: bool, // Does this struct own the database instance?
+    run_id: ?[]const u8 = null, // Tag stored on every appended row
+    flush_every: ?usize = null, // Flush the appender after this many rows (null = only at the end)
+    rows_since_flush: usize = 0,
+    table: [:0]const u8 = default_table, // Rows table used by the appender and every query helper (--table)
 
     /// Initialize database and create schema
     /// Path can be a file path or ":memory:" for in-memory database
-    pub fn init(path: []const u8) !Database {
-        var database = try Database.open(path);
+    pub fn init(path: []const u8) DatabaseError!Database {
+        return initWithSettings(path, .{});
+    }
+
+    /// Like init, but applies DuckDB resource settings before the schema is created
+    pub fn initWithSettings(path: []const u8, settings: Settings) DatabaseError!Database {
+        if (!isValidTableName(settings.table)) return error.InvalidTableName;
+
+        var database = try Database.open(path, false);
+        errdefer database.deinit();
+        database.table = settings.table;
+
+        try database.applySettings(settings);
 
         // Create schema
         try database.createSchema();
@@ -34,7 +406,7 @@ pub const Database = struct {
 
     /// Create a new connection to an existing database instance
     /// Used by worker threads - does NOT own the database instance
-    pub fn connectToInstance(db_instance: c.duckdb_database) !Database {
+    pub fn connectToInstance(db_instance: c.duckdb_database) DatabaseError!Database {
         var conn: c.duckdb_connection = undefined;
 
         if (c.duckdb_connect(db_instance, &conn) == c.DuckDBError) {
@@ -51,8 +423,45 @@ pub const Database = struct {
         };
     }
 
+    /// Open an existing database without creating or altering its schema
+    /// Queries use default_table; set table afterwards to inspect another one
+    pub fn openExisting(path: []const u8) DatabaseError!Database {
+        std.fs.cwd().access(path, .{}) catch return error.DatabaseNotFound;
+        return Database.open(path, false);
+    }
+
+    /// Open an existing database read-only for queries (--query-only)
+    /// Nothing is created: DuckDB rejects every write, and a file without the rows
+    /// table is an error rather than an empty result
+    pub fn openReadOnly(path: []const u8, table: [:0]const u8) DatabaseError!Database {
+        if (!isValidTableName(table)) return error.InvalidTableName;
+        std.fs.cwd().access(path, .{}) catch return error.DatabaseNotFound;
+        var database = try Database.open(path, true);
+        errdefer database.deinit();
+        database.table = table;
+
+        if (!try database.tableExists()) {
+            return error.SyscallsTableMissing;
+        }
+        return database;
+    }
+
+    /// Whether the rows table (see table) exists in this database
+    pub fn tableExists(self: *Database) DatabaseError!bool {
+        var sql_buffer: [256]u8 = undefined;
+        const query = try self.tableSql(&sql_buffer, "SELECT COUNT(*) FROM information_schema.tables WHERE lower(table_name) = lower('{[table]s}')");
+        return try self.queryInt64(query.ptr) > 0;
+    }
+
+    /// Format a statement that names the rows table as "{[table]s}"
+    /// The name passed isValidTableName, so it holds no quote to escape; the quotes let SQL
+    /// keywords (order, group) name the table
+    fn tableSql(self: *const Database, buffer: []u8, comptime format: []const u8) DatabaseError![:0]const u8 {
+        return std.fmt.bufPrintZ(buffer, format, .{ .table = self.table }) catch error.QueryFailed;
+    }
+
     /// Internal: Open database connection
-    fn open(path: []const u8) !Database {
+    fn open(path: []const u8, read_only: bool) !Database {
         var db: c.duckdb_database = undefined;
         var conn: c.duckdb_connection = undefined;
 
@@ -62,7 +471,20 @@ pub const Database = struct {
         else
             @as([*c]const u8, @ptrCast(path.ptr));
 
-        if (c.duckdb_open(path_cstr, &db) == c.DuckDBError) {
+        // No config means DuckDB's defaults (read-write)
+        var config: c.duckdb_config = null;
+        defer if (config != null) c.duckdb_destroy_config(&config);
+        if (read_only) {
+            if (c.duckdb_create_config(&config) == c.DuckDBError or
+                c.duckdb_set_config(config, "access_mode", "READ_ONLY") == c.DuckDBError)
+            {
+                return error.DatabaseOpenFailed;
+            }
+        }
+
+        var open_error: [*c]u8 = null;
+        defer if (open_error != null) c.duckdb_free(open_error);
+        if (c.duckdb_open_ext(path_cstr, &db, config, &open_error) == c.DuckDBError) {
             return error.DatabaseOpenFailed;
         }
         errdefer c.duckdb_close(&db);
@@ -82,6 +504,30 @@ pub const Database = struct {
         };
     }
 
+    /// Run the PRAGMAs for each configured setting
+    fn applySettings(self: *Database, settings: Settings) !void {
+        var sql_buffer: [256]u8 = undefined;
+        if (settings.memory_limit) |limit| {
+            const sql = std.fmt.bufPrintZ(&sql_buffer, "PRAGMA memory_limit='{s}'", .{limit}) catch return error.InvalidDatabaseSetting;
+            try self.executeSetting(sql);
+        }
+        if (settings.threads) |threads| {
+            const sql = std.fmt.bufPrintZ(&sql_buffer, "PRAGMA threads={d}", .{threads}) catch return error.InvalidDatabaseSetting;
+            try self.executeSetting(sql);
+        }
+    }
+
+    /// Execute a settings statement, reporting DuckDB's own error message on failure
+    fn executeSetting(self: *Database, sql: [:0]const u8) !void {
+        var result: c.duckdb_result = undefined;
+        defer c.duckdb_destroy_result(&result);
+        if (c.duckdb_query(self.conn, sql.ptr, &result) == c.DuckDBError) {
+            const message = c.duckdb_result_error(&result);
+            std.debug.print("Error: DuckDB rejected {s}: {s}\n", .{ sql, if (message != null) std.mem.span(message) else "unknown error" });
+            return error.InvalidDatabaseSetting;
+        }
+    }
+
     /// Close database and clean up resources
     pub fn deinit(self: *Database) void {
         // Clean up appender if it exists
@@ -98,52 +544,110 @@ pub const Database = struct {
 
     /// Create database schema (tables and indexes)
     fn createSchema(self: *Database) !void {
-        // Create syscalls table
-        const create_table =
-            \\CREATE TABLE IF NOT EXISTS syscalls (
+        try self.createRowsTable(self.table);
+
+        // Record the schema version once, when the database is first created
+        var sql_buffer: [64]u8 = undefined;
+        self.execute("CREATE TABLE IF NOT EXISTS schema_info (version INTEGER)") catch return error.SchemaCreationFailed;
+        if (try self.queryInt64("SELECT COUNT(*) FROM schema_info") == 0) {
+            const insert_version = std.fmt.bufPrintZ(
+                &sql_buffer,
+                "INSERT INTO schema_info VALUES ({d})",
+                .{schema_version},
+            ) catch return error.SchemaCreationFailed;
+            self.execute(insert_version.ptr) catch return error.SchemaCreationFailed;
+        }
+
+        // One row per processed trace file, written by recordFileStats
+        self.execute(
+            \\CREATE TABLE IF NOT EXISTS trace_files (
             \\    trace_file VARCHAR,
-            \\    pid INTEGER,
-            \\    timestamp VARCHAR,
-            \\    syscall VARCHAR,
-            \\    args TEXT,
-            \\    return_value BIGINT,
-            \\    error_code VARCHAR,
-            \\    error_message VARCHAR,
-            \\    duration DOUBLE,
-            \\    unfinished BOOLEAN DEFAULT FALSE,
-            \\    resumed BOOLEAN DEFAULT FALSE
+            \\    total_lines BIGINT,
+            \\    parsed_lines BIGINT,
+            \\    failed_lines BIGINT,
+            \\    duration_sec DOUBLE
+            \\)
+        ) catch return error.SchemaCreationFailed;
+
+        // One row per invocation, written by recordRun; run_id matches syscalls.run_id
+        self.execute(
+            \\CREATE TABLE IF NOT EXISTS runs (
+            \\    run_id VARCHAR,
+            \\    started_at TIMESTAMP,
+            \\    finished_at TIMESTAMP,
+            \\    tool_version VARCHAR,
+            \\    file_count BIGINT,
+            \\    total_lines BIGINT
             \\)
-        ;
+        ) catch return error.SchemaCreationFailed;
+
+        // Content hash of each ingested file, consulted by --incremental
+        self.execute("CREATE TABLE IF NOT EXISTS file_hashes (trace_file VARCHAR, hash VARCHAR)") catch return error.SchemaCreationFailed;
+    }
+
+    /// Create another rows table next to table, e.g. the target of a --route
+    /// Uses IF NOT EXISTS, so naming a table the database already has is safe
+    pub fn addTable(self: *Database, table: [:0]const u8) DatabaseError!void {
+        if (!isValidTableName(table)) return error.InvalidTableName;
+        try self.createRowsTable(table);
+    }
+
+    /// Create one rows table and its indexes; table must pass isValidTableName
+    fn createRowsTable(self: *Database, table: [:0]const u8) DatabaseError!void {
+        var sql_buffer: [4096]u8 = undefined;
+        const create_table = std.fmt.bufPrintZ(
+            &sql_buffer,
+            "CREATE TABLE IF NOT EXISTS \"{s}\" (\n{s})",
+            .{ table, column_definitions },
+        ) catch return error.SchemaCreationFailed;
 
-        if (c.duckdb_query(self.conn, create_table, null) == c.DuckDBError) {
+        if (c.duckdb_query(self.conn, create_table.ptr, null) == c.DuckDBError) {
             return error.SchemaCreationFailed;
         }
 
         // Create indexes for common queries
+        // Index names are per database, so tables other than the default prefix theirs
+        // (idx_<table>_syscall); the default table keeps the original names
+        var prefix_buffer: [max_table_name_len + 1]u8 = undefined;
+        const index_prefix = if (std.mem.eql(u8, table, default_table))
+            ""
+        else
+            std.fmt.bufPrint(&prefix_buffer, "{s}_", .{table}) catch return error.IndexCreationFailed;
         const indexes = [_][]const u8{
-            "CREATE INDEX IF NOT EXISTS idx_syscall ON syscalls(syscall)",
-            "CREATE INDEX IF NOT EXISTS idx_pid ON syscalls(pid)",
-            "CREATE INDEX IF NOT EXISTS idx_error ON syscalls(error_code)",
-            "CREATE INDEX IF NOT EXISTS idx_trace_file ON syscalls(trace_file)",
+            "CREATE INDEX IF NOT EXISTS idx_{[index_prefix]s}syscall ON \"{[table]s}\"(syscall)",
+            "CREATE INDEX IF NOT EXISTS idx_{[index_prefix]s}pid ON \"{[table]s}\"(pid)",
+            "CREATE INDEX IF NOT EXISTS idx_{[index_prefix]s}error ON \"{[table]s}\"(error_code)",
+            "CREATE INDEX IF NOT EXISTS idx_{[index_prefix]s}trace_file ON \"{[table]s}\"(trace_file)",
         };
 
-        for (indexes) |index_sql| {
-            if (c.duckdb_query(self.conn, @ptrCast(index_sql.ptr), null) == c.DuckDBError) {
+        inline for (indexes) |index_format| {
+            const index_sql = std.fmt.bufPrintZ(&sql_buffer, index_format, .{ .table = table, .index_prefix = index_prefix }) catch return error.IndexCreationFailed;
+            if (c.duckdb_query(self.conn, index_sql.ptr, null) == c.DuckDBError) {
                 return error.IndexCreationFailed;
             }
         }
     }
 
+    /// Point the appender at another rows table (--route), flushing the rows appended so far
+    /// Does nothing when table is already the current one; the table must exist (see addTable)
+    pub fn useTable(self: *Database, table: [:0]const u8) DatabaseError!void {
+        if (std.mem.eql(u8, self.table, table)) return;
+        const appending = self.appender != null;
+        try self.endAppend();
+        self.table = table;
+        if (appending) try self.beginAppend();
+    }
+
     /// Begin bulk appending syscalls using DuckDB's appender API
     /// This is much faster than individual inserts for large batches
-    pub fn beginAppend(self: *Database) !void {
+    pub fn beginAppend(self: *Database) DatabaseError!void {
         // Destroy existing appender if any
         if (self.appender != null) {
             _ = c.duckdb_appender_destroy(&self.appender.?);
         }
 
         var appender: c.duckdb_appender = undefined;
-        if (c.duckdb_appender_create(self.conn, null, "syscalls", &appender) == c.DuckDBError) {
+        if (c.duckdb_appender_create(self.conn, null, self.table.ptr, &appender) == c.DuckDBError) {
             return error.AppenderCreateFailed;
         }
 
@@ -157,10 +661,10 @@ pub const Database = struct {
         trace_file: []const u8,
         pid: i32,
         syscall: Syscall,
-    ) !void {
+    ) DatabaseError!void {
         const appender = self.appender orelse return error.AppenderNotInitialized;
 
-        // Append each column in order
+        // Append each column in order (must match syscall_columns)
         // Column 1: trace_file (VARCHAR)
         if (c.duckdb_append_varchar_length(appender, @ptrCast(trace_file.ptr), @intCast(trace_file.len)) == c.DuckDBError) {
             return error.AppendFailed;
@@ -240,15 +744,160 @@ pub const Database = struct {
             return error.AppendFailed;
         }
 
+        // Column 12: target_pid (BIGINT, nullable)
+        try appendOptionalInt64(appender, syscall.target_pid);
+
+        // Column 13: signal (VARCHAR, nullable)
+        try appendOptionalVarchar(appender, syscall.signal);
+
+        // Column 14: return_value_raw (VARCHAR, nullable)
+        try appendOptionalVarchar(appender, syscall.return_value_raw);
+
+        // Column 15: run_id (VARCHAR, nullable) - same for every row of this connection
+        try appendOptionalVarchar(appender, self.run_id);
+
+        // Column 16-17: oldfd, newfd (INTEGER, nullable)
+        try appendOptionalInt32(appender, syscall.oldfd);
+        try appendOptionalInt32(appender, syscall.newfd);
+
+        // Column 18-19: peer_addr (VARCHAR), peer_port (INTEGER)
+        try appendOptionalVarchar(appender, syscall.peer_addr);
+        try appendOptionalInt32(appender, if (syscall.peer_port) |port| @as(i32, port) else null);
+
+        // Column 20-21: clock_value_ns (BIGINT), clock_id (VARCHAR)
+        try appendOptionalInt64(appender, syscall.clock_value_ns);
+        try appendOptionalVarchar(appender, syscall.clock_id);
+
+        // Column 22: command (VARCHAR)
+        try appendOptionalVarchar(appender, syscall.command);
+
+        // Column 23: hour (INTEGER)
+        try appendOptionalInt32(appender, if (syscall.hour) |hour| @as(i32, hour) else null);
+
+        // Column 24-26: bind_addr (VARCHAR), bind_port (INTEGER), backlog (INTEGER)
+        try appendOptionalVarchar(appender, syscall.bind_addr);
+        try appendOptionalInt32(appender, if (syscall.bind_port) |port| @as(i32, port) else null);
+        try appendOptionalInt32(appender, syscall.backlog);
+
+        // Column 27: args_raw (TEXT)
+        try appendOptionalVarchar(appender, syscall.args_raw);
+
+        // Column 28: return_annotation (VARCHAR)
+        try appendOptionalVarchar(appender, syscall.return_annotation);
+
+        // Column 29: kind (VARCHAR) - syscall, signal, exit or killed
+        try appendOptionalVarchar(appender, @tagName(syscall.kind));
+
+        // Column 30: core_dumped (BOOLEAN)
+        if (c.duckdb_append_bool(appender, syscall.core_dumped) == c.DuckDBError) {
+            return error.AppendFailed;
+        }
+
+        // Column 31: reassembled (BOOLEAN)
+        if (c.duckdb_append_bool(appender, syscall.reassembled) == c.DuckDBError) {
+            return error.AppendFailed;
+        }
+
+        // Column 32: time_of_day (TIME) - typed copy of the timestamp string
+        try appendOptionalTime(appender, syscall.time_of_day_us);
+
+        // Column 33: wall_time (TIMESTAMP) - only with --date
+        try appendOptionalTimestamp(appender, syscall.wall_time_us);
+
+        // Column 34: env_count (INTEGER) - execve/execveat only
+        try appendOptionalInt32(appender, syscall.env_count);
+
+        // Column 35: raw_line (VARCHAR) - only with --keep-raw
+        try appendOptionalVarchar(appender, syscall.raw_line);
+
+        // Column 36: line_number (INTEGER) - 1-based, counts every source line
+        try appendOptionalInt32(appender, syscall.line_number);
+
+        // Column 37: seq (BIGINT) - ORDER BY seq replays a run in file order
+        try appendOptionalInt64(appender, syscall.seq);
+
+        // Column 38: errno (INTEGER) - numeric error_code
+        try appendOptionalInt32(appender, syscall.errno);
+
+        // Column 39: fd (INTEGER) - only with --extract-fd
+        try appendOptionalInt32(appender, syscall.fd);
+
+        // Column 40: truncated (BOOLEAN)
+        if (c.duckdb_append_bool(appender, syscall.truncated) == c.DuckDBError) {
+            return error.AppendFailed;
+        }
+
+        // Column 41: return_unknown (BOOLEAN)
+        if (c.duckdb_append_bool(appender, syscall.return_unknown) == c.DuckDBError) {
+            return error.AppendFailed;
+        }
+
+        // Column 42: return_addr (UBIGINT, nullable) - hex returns only
+        try appendOptionalUInt64(appender, syscall.return_addr);
+
+        // Column 43: tid (INTEGER, nullable)
+        try appendOptionalInt32(appender, syscall.tid);
+
+        // Column 44: rel_time (DOUBLE, nullable) - strace -r only
+        try appendOptionalDouble(appender, syscall.rel_time);
+
+        // Column 45: arg_count (INTEGER, nullable)
+        try appendOptionalInt32(appender, syscall.arg_count);
+
+        // Column 46: args_json (VARCHAR, nullable) - only with --args-json
+        try appendOptionalVarchar(appender, syscall.args_json);
+
+        // Column 47: mmap_prot (VARCHAR, nullable) - only with --decode-mmap
+        try appendOptionalVarchar(appender, syscall.mmap_prot);
+
+        // Column 48: mmap_flags (VARCHAR, nullable) - only with --decode-mmap
+        try appendOptionalVarchar(appender, syscall.mmap_flags);
+
+        // Column 49: mode (INTEGER, nullable) - only with --decode-mode
+        try appendOptionalInt32(appender, syscall.mode);
+
+        // Column 50: sa_family (VARCHAR, nullable) - only with --decode-sockaddr
+        try appendOptionalVarchar(appender, syscall.sa_family);
+
+        // Column 51: sock_addr (VARCHAR, nullable) - only with --decode-sockaddr
+        try appendOptionalVarchar(appender, syscall.sock_addr);
+
+        // Column 52: sock_port (INTEGER, nullable) - only with --decode-sockaddr
+        try appendOptionalInt32(appender, if (syscall.sock_port) |port| @as(i32, port) else null);
+
+        // Column 53: args_truncated (BOOLEAN) - set by --max-args-len
+        if (c.duckdb_append_bool(appender, syscall.args_truncated) == c.DuckDBError) {
+            return error.AppendFailed;
+        }
+
+        // Column 54: duration_ns (BIGINT, nullable) - only with --duration-ns
+        try appendOptionalInt64(appender, syscall.duration_ns);
+
         // End the row
         if (c.duckdb_appender_end_row(appender) == c.DuckDBError) {
             return error.AppendFailed;
         }
+
+        // Periodic flushes bound the appender's buffer on very large loads
+        if (self.flush_every) |limit| {
+            self.rows_since_flush += 1;
+            if (self.rows_since_flush >= limit) try self.flushAppend();
+        }
+    }
+
+    /// Flush buffered appender rows without ending the append session
+    pub fn flushAppend(self: *Database) DatabaseError!void {
+        if (self.appender) |appender| {
+            if (c.duckdb_appender_flush(appender) == c.DuckDBError) {
+                return error.AppenderFlushFailed;
+            }
+        }
+        self.rows_since_flush = 0;
     }
 
     /// End appending and destroy the appender
     /// This also flushes any remaining rows
-    pub fn endAppend(self: *Database) !void {
+    pub fn endAppend(self: *Database) DatabaseError!void {
         if (self.appender != null) {
             // Flush before destroying
             if (c.duckdb_appender_flush(self.appender.?) == c.DuckDBError) {
@@ -261,14 +910,51 @@ pub const Database = struct {
 
             self.appender = null;
         }
+        self.rows_since_flush = 0;
+    }
+
+    /// Append every Syscall an iterator yields, e.g. rows parsed from another trace format
+    /// iter is a pointer to anything whose next() returns ?Syscall or an error union of one,
+    /// such as file_processor.TraceIterator. An open append session is reused and flushed;
+    /// otherwise one is opened and ended here. Rows before a failed next() are kept
+    /// pid is stored for rows without a pid of their own (a `[pid N]` prefix or TraceIterator's row PID)
+    /// Returns the number of rows appended
+    pub fn insertIter(self: *Database, trace_file: []const u8, pid: i32, iter: anytype) !usize {
+        const owns_session = self.appender == null;
+        if (owns_session) try self.beginAppend();
+        errdefer if (owns_session) self.endAppend() catch {};
+
+        var count: usize = 0;
+        while (try nextRow(iter)) |syscall| {
+            try self.appendSyscall(trace_file, syscall.pid orelse pid, syscall);
+            count += 1;
+        }
+
+        if (owns_session) try self.endAppend() else try self.flushAppend();
+        return count;
+    }
+
+    /// Append a slice of syscalls through the appender (see insertIter)
+    pub fn appendBatch(self: *Database, trace_file: []const u8, pid: i32, syscalls: []const Syscall) DatabaseError!usize {
+        var rows = SliceRows{ .items = syscalls };
+        return self.insertIter(trace_file, pid, &rows);
+    }
+
+    /// Rows in table, which need not be the current one (routed tables, see useTable)
+    pub fn countRowsIn(self: *Database, table: [:0]const u8) DatabaseError!i64 {
+        if (!isValidTableName(table)) return error.InvalidTableName;
+        var sql_buffer: [128]u8 = undefined;
+        const query = std.fmt.bufPrintZ(&sql_buffer, "SELECT COUNT(*) FROM \"{s}\"", .{table}) catch return error.QueryFailed;
+        return self.queryInt64(query.ptr);
     }
 
     /// Get count of total syscalls in database
-    pub fn getSyscallCount(self: *Database) !i64 {
+    pub fn getSyscallCount(self: *Database) DatabaseError!i64 {
         var result: c.duckdb_result = undefined;
-        const query = "SELECT COUNT(*) FROM syscalls";
+        var sql_buffer: [256]u8 = undefined;
+        const query = try self.tableSql(&sql_buffer, "SELECT COUNT(*) FROM \"{[table]s}\"");
 
-        if (c.duckdb_query(self.conn, query, &result) == c.DuckDBError) {
+        if (c.duckdb_query(self.conn, query.ptr, &result) == c.DuckDBError) {
             return error.QueryFailed;
         }
         defer c.duckdb_destroy_result(&result);
@@ -279,11 +965,12 @@ pub const Database = struct {
     }
 
     /// Get count of unique syscalls
-    pub fn getUniqueSyscallCount(self: *Database) !i64 {
+    pub fn getUniqueSyscallCount(self: *Database) DatabaseError!i64 {
         var result: c.duckdb_result = undefined;
-        const query = "SELECT COUNT(DISTINCT syscall) FROM syscalls";
+        var sql_buffer: [256]u8 = undefined;
+        const query = try self.tableSql(&sql_buffer, "SELECT COUNT(DISTINCT syscall) FROM \"{[table]s}\"");
 
-        if (c.duckdb_query(self.conn, query, &result) == c.DuckDBError) {
+        if (c.duckdb_query(self.conn, query.ptr, &result) == c.DuckDBError) {
             return error.QueryFailed;
         }
         defer c.duckdb_destroy_result(&result);
@@ -292,29 +979,477 @@ pub const Database = struct {
     }
 
     /// Get count of unique PIDs
-    pub fn getUniquePidCount(self: *Database) !i64 {
+    pub fn getUniquePidCount(self: *Database) DatabaseError!i64 {
+        var result: c.duckdb_result = undefined;
+        var sql_buffer: [256]u8 = undefined;
+        const query = try self.tableSql(&sql_buffer, "SELECT COUNT(DISTINCT pid) FROM \"{[table]s}\"");
+
+        if (c.duckdb_query(self.conn, query.ptr, &result) == c.DuckDBError) {
+            return error.QueryFailed;
+        }
+        defer c.duckdb_destroy_result(&result);
+
+        return c.duckdb_value_int64(&result, 0, 0);
+    }
+
+    /// Get count of failed syscalls (those with error codes)
+    pub fn getFailedSyscallCount(self: *Database) DatabaseError!i64 {
+        var result: c.duckdb_result = undefined;
+        var sql_buffer: [256]u8 = undefined;
+        const query = try self.tableSql(&sql_buffer, "SELECT COUNT(*) FROM \"{[table]s}\" WHERE error_code IS NOT NULL");
+
+        if (c.duckdb_query(self.conn, query.ptr, &result) == c.DuckDBError) {
+            return error.QueryFailed;
+        }
+        defer c.duckdb_destroy_result(&result);
+
+        return c.duckdb_value_int64(&result, 0, 0);
+    }
+
+    /// Row count per syscall name, most frequent first (ties by name)
+    /// Caller frees the result with freeSyscallRows
+    pub fn syscallCounts(self: *Database, allocator: std.mem.Allocator) (DatabaseError || std.mem.Allocator.Error)![]SyscallCount {
+        var result: c.duckdb_result = undefined;
+        var sql_buffer: [256]u8 = undefined;
+        const query = try self.tableSql(&sql_buffer, "SELECT syscall, COUNT(*) FROM \"{[table]s}\" GROUP BY syscall ORDER BY 2 DESC, 1");
+
+        if (c.duckdb_query(self.conn, query.ptr, &result) == c.DuckDBError) {
+            return error.QueryFailed;
+        }
+        defer c.duckdb_destroy_result(&result);
+
+        var rows = std.ArrayListUnmanaged(SyscallCount){};
+        errdefer {
+            for (rows.items) |row| allocator.free(row.syscall);
+            rows.deinit(allocator);
+        }
+
+        const row_count: usize = @intCast(c.duckdb_row_count(&result));
+        try rows.ensureTotalCapacity(allocator, row_count);
+        for (0..row_count) |row| {
+            const name = try dupeVarchar(allocator, &result, 0, row);
+            rows.appendAssumeCapacity(.{ .syscall = name, .count = c.duckdb_value_int64(&result, 1, @intCast(row)) });
+        }
+        return rows.toOwnedSlice(allocator);
+    }
+
+    /// Total duration and timed row count per syscall name, most time first
+    /// Rows without a duration (no -T, unfinished calls) are ignored
+    /// Caller frees the result with freeSyscallRows
+    pub fn timeBySyscall(self: *Database, allocator: std.mem.Allocator) (DatabaseError || std.mem.Allocator.Error)![]SyscallTime {
+        var result: c.duckdb_result = undefined;
+        var sql_buffer: [256]u8 = undefined;
+        const query = try self.tableSql(&sql_buffer,
+            \\SELECT syscall, SUM(duration), COUNT(*) FROM "{[table]s}"
+            \\WHERE duration IS NOT NULL
+            \\GROUP BY syscall ORDER BY 2 DESC, 1
+        );
+
+        if (c.duckdb_query(self.conn, query.ptr, &result) == c.DuckDBError) {
+            return error.QueryFailed;
+        }
+        defer c.duckdb_destroy_result(&result);
+
+        var rows = std.ArrayListUnmanaged(SyscallTime){};
+        errdefer {
+            for (rows.items) |row| allocator.free(row.syscall);
+            rows.deinit(allocator);
+        }
+
+        const row_count: usize = @intCast(c.duckdb_row_count(&result));
+        try rows.ensureTotalCapacity(allocator, row_count);
+        for (0..row_count) |row| {
+            const name = try dupeVarchar(allocator, &result, 0, row);
+            rows.appendAssumeCapacity(.{
+                .syscall = name,
+                .total_duration = c.duckdb_value_double(&result, 1, @intCast(row)),
+                .count = c.duckdb_value_int64(&result, 2, @intCast(row)),
+            });
+        }
+        return rows.toOwnedSlice(allocator);
+    }
+
+    /// Execute a statement that returns no rows (DDL, PRAGMA, COPY)
+    /// Never use this for inserts - use the appender API instead
+    pub fn execute(self: *Database, sql: [*:0]const u8) DatabaseError!void {
+        if (c.duckdb_query(self.conn, sql, null) == c.DuckDBError) {
+            return error.QueryFailed;
+        }
+    }
+
+    /// Run a query that returns a single integer (first row, first column)
+    pub fn queryInt64(self: *Database, query: [*:0]const u8) DatabaseError!i64 {
         var result: c.duckdb_result = undefined;
-        const query = "SELECT COUNT(DISTINCT pid) FROM syscalls";
 
         if (c.duckdb_query(self.conn, query, &result) == c.DuckDBError) {
             return error.QueryFailed;
         }
         defer c.duckdb_destroy_result(&result);
 
-        return c.duckdb_value_int64(&result, 0, 0);
+        return c.duckdb_value_int64(&result, 0, 0);
+    }
+
+    /// Compare the rows table against the columns this build would create
+    /// Read-only: nothing is created or inserted
+    pub fn checkSchema(self: *Database, allocator: std.mem.Allocator) (DatabaseError || std.mem.Allocator.Error)!SchemaCheck {
+        var result: c.duckdb_result = undefined;
+        var sql_buffer: [256]u8 = undefined;
+        const query = try self.tableSql(&sql_buffer,
+            \\SELECT column_name FROM information_schema.columns
+            \\WHERE lower(table_name) = lower('{[table]s}') ORDER BY ordinal_position
+        );
+
+        if (c.duckdb_query(self.conn, query.ptr, &result) == c.DuckDBError) {
+            return error.QueryFailed;
+        }
+        defer c.duckdb_destroy_result(&result);
+
+        var existing = std.ArrayListUnmanaged([]u8){};
+        defer {
+            for (existing.items) |name| allocator.free(name);
+            existing.deinit(allocator);
+        }
+
+        const row_count: usize = @intCast(c.duckdb_row_count(&result));
+        for (0..row_count) |row| {
+            const value = c.duckdb_value_varchar(&result, 0, @intCast(row));
+            defer c.duckdb_free(value);
+            try existing.append(allocator, try allocator.dupe(u8, std.mem.span(value)));
+        }
+
+        var missing = std.ArrayListUnmanaged([]const u8){};
+        errdefer missing.deinit(allocator);
+        for (syscall_columns) |col| {
+            if (!containsName(existing.items, col.name)) try missing.append(allocator, col.name);
+        }
+
+        var extra = std.ArrayListUnmanaged([]const u8){};
+        errdefer {
+            for (extra.items) |name| allocator.free(name);
+            extra.deinit(allocator);
+        }
+        for (existing.items) |name| {
+            if (!isSchemaColumn(name)) try extra.append(allocator, try allocator.dupe(u8, name));
+        }
+
+        // Databases created before versioning have no schema_info table
+        const version: ?i64 = self.queryInt64("SELECT MAX(version) FROM schema_info") catch null;
+
+        return SchemaCheck{
+            .allocator = allocator,
+            .version = version,
+            .missing = try missing.toOwnedSlice(allocator),
+            .extra = try extra.toOwnedSlice(allocator),
+        };
+    }
+
+    /// Replace the syscall_aggregates table with the given rows
+    /// Uses its own appender, so it may run while a syscalls append session is open
+    pub fn writeAggregates(self: *Database, rows: []const SyscallAggregate) DatabaseError!void {
+        self.execute(
+            \\CREATE OR REPLACE TABLE syscall_aggregates (
+            \\    syscall VARCHAR,
+            \\    count BIGINT,
+            \\    error_count BIGINT,
+            \\    total_duration DOUBLE
+            \\)
+        ) catch return error.SchemaCreationFailed;
+
+        var appender: c.duckdb_appender = undefined;
+        if (c.duckdb_appender_create(self.conn, null, "syscall_aggregates", &appender) == c.DuckDBError) {
+            return error.AppenderCreateFailed;
+        }
+        defer _ = c.duckdb_appender_destroy(&appender);
+
+        for (rows) |row| {
+            if (c.duckdb_append_varchar_length(appender, @ptrCast(row.syscall.ptr), @intCast(row.syscall.len)) == c.DuckDBError or
+                c.duckdb_append_int64(appender, row.count) == c.DuckDBError or
+                c.duckdb_append_int64(appender, row.error_count) == c.DuckDBError or
+                c.duckdb_append_double(appender, row.total_duration) == c.DuckDBError or
+                c.duckdb_appender_end_row(appender) == c.DuckDBError)
+            {
+                return error.AppendFailed;
+            }
+        }
+
+        if (c.duckdb_appender_flush(appender) == c.DuckDBError) {
+            return error.AppenderFlushFailed;
+        }
+    }
+
+    /// Add one trace_files row for a file that finished processing
+    /// Uses its own appender, so it may run while a syscalls append session is open
+    pub fn recordFileStats(self: *Database, trace_file: []const u8, stats: FileStats, duration_sec: f64) DatabaseError!void {
+        var appender: c.duckdb_appender = undefined;
+        if (c.duckdb_appender_create(self.conn, null, "trace_files", &appender) == c.DuckDBError) {
+            return error.AppenderCreateFailed;
+        }
+        defer _ = c.duckdb_appender_destroy(&appender);
+
+        if (c.duckdb_append_varchar_length(appender, @ptrCast(trace_file.ptr), @intCast(trace_file.len)) == c.DuckDBError or
+            c.duckdb_append_int64(appender, @intCast(stats.total_lines)) == c.DuckDBError or
+            c.duckdb_append_int64(appender, @intCast(stats.parsed_lines)) == c.DuckDBError or
+            c.duckdb_append_int64(appender, @intCast(stats.failed_lines)) == c.DuckDBError or
+            c.duckdb_append_double(appender, duration_sec) == c.DuckDBError or
+            c.duckdb_appender_end_row(appender) == c.DuckDBError)
+        {
+            return error.AppendFailed;
+        }
+
+        if (c.duckdb_appender_flush(appender) == c.DuckDBError) {
+            return error.AppenderFlushFailed;
+        }
+    }
+
+    /// Add the runs row for one invocation, tagged with this connection's run_id
+    /// Times are microseconds since the epoch (UTC)
+    pub fn recordRun(self: *Database, run: RunInfo) DatabaseError!void {
+        var appender: c.duckdb_appender = undefined;
+        if (c.duckdb_appender_create(self.conn, null, "runs", &appender) == c.DuckDBError) {
+            return error.AppenderCreateFailed;
+        }
+        defer _ = c.duckdb_appender_destroy(&appender);
+
+        try appendOptionalVarchar(appender, self.run_id);
+        try appendOptionalTimestamp(appender, run.started_at_us);
+        try appendOptionalTimestamp(appender, run.finished_at_us);
+        if (c.duckdb_append_varchar_length(appender, @ptrCast(run.tool_version.ptr), @intCast(run.tool_version.len)) == c.DuckDBError or
+            c.duckdb_append_int64(appender, @intCast(run.file_count)) == c.DuckDBError or
+            c.duckdb_append_int64(appender, @intCast(run.total_lines)) == c.DuckDBError or
+            c.duckdb_appender_end_row(appender) == c.DuckDBError)
+        {
+            return error.AppendFailed;
+        }
+
+        if (c.duckdb_appender_flush(appender) == c.DuckDBError) {
+            return error.AppenderFlushFailed;
+        }
+    }
+
+    /// Whether file_hashes already holds this hash for trace_file (--incremental)
+    pub fn hasFileHash(self: *Database, trace_file: []const u8, hash: u64) DatabaseError!bool {
+        var sql_buffer: [std.fs.max_path_bytes * 2 + 128]u8 = undefined;
+        var writer = std.Io.Writer.fixed(&sql_buffer);
+        writer.writeAll("SELECT COUNT(*) FROM file_hashes WHERE trace_file = ") catch return error.QueryFailed;
+        writeSqlString(&writer, trace_file) catch return error.QueryFailed;
+        writer.print(" AND hash = '{x:0>16}'\x00", .{hash}) catch return error.QueryFailed;
+
+        const written = writer.buffered();
+        return try self.queryInt64(written[0 .. written.len - 1 :0]) > 0;
+    }
+
+    /// Store the hash of an ingested file, replacing any hash recorded for it earlier
+    pub fn recordFileHash(self: *Database, trace_file: []const u8, hash: u64) DatabaseError!void {
+        var sql_buffer: [std.fs.max_path_bytes * 2 + 64]u8 = undefined;
+        var writer = std.Io.Writer.fixed(&sql_buffer);
+        writer.writeAll("DELETE FROM file_hashes WHERE trace_file = ") catch return error.QueryFailed;
+        writeSqlString(&writer, trace_file) catch return error.QueryFailed;
+        writer.writeByte(0) catch return error.QueryFailed;
+
+        const written = writer.buffered();
+        try self.execute(written[0 .. written.len - 1 :0]);
+
+        var appender: c.duckdb_appender = undefined;
+        if (c.duckdb_appender_create(self.conn, null, "file_hashes", &appender) == c.DuckDBError) {
+            return error.AppenderCreateFailed;
+        }
+        defer _ = c.duckdb_appender_destroy(&appender);
+
+        var hash_buffer: [16]u8 = undefined;
+        const hash_text = std.fmt.bufPrint(&hash_buffer, "{x:0>16}", .{hash}) catch unreachable;
+        if (c.duckdb_append_varchar_length(appender, @ptrCast(trace_file.ptr), @intCast(trace_file.len)) == c.DuckDBError or
+            c.duckdb_append_varchar_length(appender, @ptrCast(hash_text.ptr), @intCast(hash_text.len)) == c.DuckDBError or
+            c.duckdb_appender_end_row(appender) == c.DuckDBError)
+        {
+            return error.AppendFailed;
+        }
+
+        if (c.duckdb_appender_flush(appender) == c.DuckDBError) {
+            return error.AppenderFlushFailed;
+        }
+    }
+
+    /// Derive the process_tree table (pid, parent_pid, first_seen, command) from ingested rows
+    /// Parents come from successful clone/clone3/fork/vfork return values; every PID with rows
+    /// appears, plus forked children that were never traced. CLONE_THREAD clones start threads,
+    /// not processes, and are left out. Call after all appends are flushed
+    pub fn buildProcessTree(self: *Database) DatabaseError!void {
+        var sql_buffer: [1024]u8 = undefined;
+        const sql = self.tableSql(&sql_buffer,
+            \\CREATE OR REPLACE TABLE process_tree AS
+            \\WITH observed AS (
+            \\    SELECT pid, MIN(timestamp) AS first_seen, MAX(command) AS command
+            \\    FROM "{[table]s}" GROUP BY pid
+            \\),
+            \\forks AS (
+            \\    SELECT CAST(return_value AS INTEGER) AS pid, MIN(pid) AS parent_pid, MIN(timestamp) AS forked_at
+            \\    FROM "{[table]s}"
+            \\    WHERE syscall IN ('clone', 'clone3', 'fork', 'vfork') AND return_value > 0
+            \\      AND args NOT LIKE '%CLONE_THREAD%'
+            \\    GROUP BY 1
+            \\)
+            \\SELECT
+            \\    COALESCE(o.pid, f.pid) AS pid,
+            \\    f.parent_pid,
+            \\    COALESCE(o.first_seen, f.forked_at) AS first_seen,
+            \\    o.command
+            \\FROM observed o FULL OUTER JOIN forks f ON o.pid = f.pid
+            \\ORDER BY first_seen, pid
+        ) catch return error.ProcessTreeFailed;
+        self.execute(sql.ptr) catch return error.ProcessTreeFailed;
+    }
+
+    /// Write the syscalls table to a Parquet file, replacing any existing file
+    /// Returns the number of rows written
+    pub fn exportParquet(self: *Database, path: []const u8) DatabaseError!i64 {
+        return self.copySyscallsTo(path, "FORMAT PARQUET");
+    }
+
+    /// Write the syscalls table to a CSV file with a header row, replacing any existing file
+    /// Fields containing commas, quotes or newlines (common in args) are quoted by DuckDB
+    /// Returns the number of rows written
+    pub fn exportCsv(self: *Database, path: []const u8) DatabaseError!i64 {
+        return self.copySyscallsTo(path, "FORMAT CSV, HEADER");
+    }
+
+    /// Write the syscalls table as JSON Lines (one object per row), replacing any existing file
+    /// Strings are JSON-escaped by DuckDB, so quotes and backslashes in args survive
+    /// Returns the number of rows written
+    pub fn exportJsonl(self: *Database, path: []const u8) DatabaseError!i64 {
+        return self.copySyscallsTo(path, "FORMAT JSON");
+    }
+
+    /// COPY the syscalls table to path with the given option list; COPY reports the row count
+    fn copySyscallsTo(self: *Database, path: []const u8, copy_options: []const u8) DatabaseError!i64 {
+        var sql_buffer: [2 * std.fs.max_path_bytes + 128]u8 = undefined;
+        var writer = std.Io.Writer.fixed(&sql_buffer);
+        writer.print("COPY \"{s}\" TO ", .{self.table}) catch return error.ExportFailed;
+        writeSqlString(&writer, path) catch return error.ExportFailed;
+        writer.print(" ({s})\x00", .{copy_options}) catch return error.ExportFailed;
+
+        const written = writer.buffered();
+        const sql: [:0]const u8 = written[0 .. written.len - 1 :0];
+        return self.queryInt64(sql.ptr) catch return error.ExportFailed;
+    }
+
+    /// Copy the rows tables of the database file at path into this one, with its runs,
+    /// trace_files and file_hashes rows (--merge)
+    /// Besides the main table, every --route table in the source (see routedTables) is
+    /// copied into a table of the same name, created here if needed
+    /// The source is ATTACHed read-only and each table copied in one INSERT ... SELECT BY NAME,
+    /// all in one transaction; check the source with checkSchema first
+    /// Returns the rows copied into the main table
+    pub fn mergeFrom(self: *Database, path: []const u8) DatabaseError!i64 {
+        var sql_buffer: [2 * std.fs.max_path_bytes + 128]u8 = undefined;
+        var writer = std.Io.Writer.fixed(&sql_buffer);
+        writer.writeAll("ATTACH ") catch return error.MergeFailed;
+        writeSqlString(&writer, path) catch return error.MergeFailed;
+        writer.writeAll(" AS merge_source (READ_ONLY)\x00") catch return error.MergeFailed;
+        const written = writer.buffered();
+        const attach: [:0]const u8 = written[0 .. written.len - 1 :0];
+        self.execute(attach.ptr) catch return error.MergeFailed;
+        defer self.execute("DETACH merge_source") catch {};
+
+        self.execute("BEGIN TRANSACTION") catch return error.MergeFailed;
+        errdefer self.execute("ROLLBACK") catch {};
+
+        var table_buffer: [256]u8 = undefined;
+        const copy_rows = self.tableSql(&table_buffer, "INSERT INTO \"{[table]s}\" BY NAME SELECT * FROM merge_source.\"{[table]s}\"") catch return error.MergeFailed;
+        const rows = self.queryInt64(copy_rows.ptr) catch return error.MergeFailed;
+
+        var result: c.duckdb_result = undefined;
+        const list = self.otherTablesSql(&table_buffer, "'merge_source'") catch return error.MergeFailed;
+        if (c.duckdb_query(self.conn, list.ptr, &result) == c.DuckDBError) return error.MergeFailed;
+        defer c.duckdb_destroy_result(&result);
+        const table_count: usize = @intCast(c.duckdb_row_count(&result));
+        for (0..table_count) |row| {
+            const value = c.duckdb_value_varchar(&result, 0, @intCast(row));
+            defer c.duckdb_free(value);
+            const name = std.mem.span(value);
+            if (!isValidTableName(name)) continue;
+
+            var name_buffer: [max_table_name_len + 1]u8 = undefined;
+            const routed = std.fmt.bufPrintZ(&name_buffer, "{s}", .{name}) catch return error.MergeFailed;
+            self.createRowsTable(routed) catch return error.MergeFailed;
+            const copy = std.fmt.bufPrintZ(&table_buffer, "INSERT INTO \"{s}\" BY NAME SELECT * FROM merge_source.\"{s}\"", .{ routed, routed }) catch return error.MergeFailed;
+            self.execute(copy.ptr) catch return error.MergeFailed;
+        }
+
+        // Databases from older builds may lack some provenance tables
+        for ([_][]const u8{ "runs", "trace_files", "file_hashes" }) |name| {
+            const exists = std.fmt.bufPrintZ(&table_buffer, "SELECT COUNT(*) FROM duckdb_tables() WHERE database_name = 'merge_source' AND table_name = '{s}'", .{name}) catch return error.MergeFailed;
+            if (try self.queryInt64(exists.ptr) == 0) continue;
+            const copy = std.fmt.bufPrintZ(&table_buffer, "INSERT INTO {s} BY NAME SELECT * FROM merge_source.{s}", .{ name, name }) catch return error.MergeFailed;
+            self.execute(copy.ptr) catch return error.MergeFailed;
+        }
+
+        self.execute("COMMIT") catch return error.MergeFailed;
+        return rows;
+    }
+
+    /// Names of the tables next to the main one that hold rows, i.e. --route tables:
+    /// every table the schema doesn't create for itself (see reserved_tables)
+    /// Caller frees the list with freeTableNames
+    pub fn routedTables(self: *Database, allocator: std.mem.Allocator) (DatabaseError || std.mem.Allocator.Error)![][:0]u8 {
+        var result: c.duckdb_result = undefined;
+        var sql_buffer: [256]u8 = undefined;
+        const query = try self.otherTablesSql(&sql_buffer, "current_database()");
+        if (c.duckdb_query(self.conn, query.ptr, &result) == c.DuckDBError) {
+            return error.QueryFailed;
+        }
+        defer c.duckdb_destroy_result(&result);
+
+        var names = std.ArrayListUnmanaged([:0]u8){};
+        errdefer {
+            for (names.items) |name| allocator.free(name);
+            names.deinit(allocator);
+        }
+        const row_count: usize = @intCast(c.duckdb_row_count(&result));
+        for (0..row_count) |row| {
+            const value = c.duckdb_value_varchar(&result, 0, @intCast(row));
+            defer c.duckdb_free(value);
+            const name = std.mem.span(value);
+            if (!isValidTableName(name)) continue;
+            try names.append(allocator, try allocator.dupeZ(u8, name));
+        }
+        return names.toOwnedSlice(allocator);
     }
 
-    /// Get count of failed syscalls (those with error codes)
-    pub fn getFailedSyscallCount(self: *Database) !i64 {
-        var result: c.duckdb_result = undefined;
-        const query = "SELECT COUNT(*) FROM syscalls WHERE error_code IS NOT NULL";
+    /// Tables of one catalog other than the main table, by name; catalog is SQL, e.g. 'merge_source'
+    /// isValidTableName then drops the schema's own tables
+    fn otherTablesSql(self: *const Database, buffer: []u8, catalog: []const u8) DatabaseError![:0]const u8 {
+        return std.fmt.bufPrintZ(
+            buffer,
+            "SELECT table_name FROM duckdb_tables() WHERE database_name = {s} AND schema_name = 'main' AND lower(table_name) <> lower('{s}') ORDER BY table_name",
+            .{ catalog, self.table },
+        ) catch error.QueryFailed;
+    }
 
-        if (c.duckdb_query(self.conn, query, &result) == c.DuckDBError) {
-            return error.QueryFailed;
-        }
-        defer c.duckdb_destroy_result(&result);
+    /// Write everything in the write-ahead log into the database file and truncate the log (--optimize)
+    /// Space freed by --dedupe or overwritten row groups is reused, leaving a file ready to query
+    /// Call after every connection's appender is closed
+    pub fn checkpoint(self: *Database) DatabaseError!void {
+        self.execute("CHECKPOINT") catch return error.CheckpointFailed;
+    }
 
-        return c.duckdb_value_int64(&result, 0, 0);
+    /// Delete rows identical to an earlier row in every column except run_id and seq (--dedupe)
+    /// The first copy loaded is kept. Scans and groups the whole table, so it is
+    /// expensive on large databases. Returns the number of rows removed
+    pub fn removeDuplicateRows(self: *Database) DatabaseError!i64 {
+        const before = try self.getSyscallCount();
+        var sql_buffer: [2048]u8 = undefined;
+        const sql = self.tableSql(&sql_buffer, "DELETE FROM \"{[table]s}\" WHERE rowid NOT IN (SELECT MIN(rowid) FROM \"{[table]s}\" GROUP BY " ++ dedupe_columns ++ ")") catch return error.DedupeFailed;
+        self.execute(sql.ptr) catch return error.DedupeFailed;
+        return before - try self.getSyscallCount();
+    }
+
+    /// Compare SELECT COUNT(*) against the number of rows the processor reported appending
+    /// Catches appender flush bugs and silently dropped rows
+    pub fn verifyRowCount(self: *Database, expected_rows: usize) DatabaseError!RowCountCheck {
+        return RowCountCheck{
+            .expected = @intCast(expected_rows),
+            .actual = try self.getSyscallCount(),
+        };
     }
 };
 
@@ -345,6 +1480,43 @@ test "schema has syscalls table" {
     try std.testing.expect(state == c.DuckDBSuccess);
 }
 
+test "schema check passes for a current-schema database" {
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    var check = try db.checkSchema(std.testing.allocator);
+    defer check.deinit();
+
+    try std.testing.expect(check.passed());
+    try std.testing.expectEqual(@as(?i64, schema_version), check.version);
+}
+
+test "schema check reports differences for an old-schema database" {
+    // Open without createSchema and build the original unversioned table by hand
+    var db = try Database.open(":memory:", false);
+    defer db.deinit();
+
+    try db.execute(
+        \\CREATE TABLE syscalls (
+        \\    trace_file VARCHAR, pid INTEGER, timestamp VARCHAR, syscall VARCHAR,
+        \\    args TEXT, return_value BIGINT, error_code VARCHAR, error_message VARCHAR,
+        \\    duration DOUBLE, unfinished BOOLEAN, resumed BOOLEAN, legacy_note VARCHAR
+        \\)
+    );
+
+    var check = try db.checkSchema(std.testing.allocator);
+    defer check.deinit();
+
+    try std.testing.expect(!check.passed());
+    try std.testing.expectEqual(@as(?i64, null), check.version);
+    // Every column added after the original 11 is reported, in schema order
+    try std.testing.expectEqual(syscall_columns.len - 11, check.missing.len);
+    try std.testing.expectEqualStrings("target_pid", check.missing[0]);
+    try std.testing.expectEqualStrings("signal", check.missing[1]);
+    try std.testing.expectEqual(@as(usize, 1), check.extra.len);
+    try std.testing.expectEqualStrings("legacy_note", check.extra[0]);
+}
+
 test "insert single syscall" {
     var db = try Database.init(":memory:");
     defer db.deinit();
@@ -541,6 +1713,46 @@ test "insert unfinished and resumed syscalls" {
     try std.testing.expectEqual(@as(i64, 2), count);
 }
 
+test "insert syscall with decoded signal fields" {
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    var syscall = Syscall.init("22:21:24.927000", "kill", "1387721, SIGTERM", 0, null, null, null, false, false);
+    syscall.target_pid = 1387721;
+    syscall.signal = "SIGTERM";
+
+    try db.beginAppend();
+    try db.appendSyscall("test.trace", 1234, syscall);
+    try db.endAppend();
+
+    const count = try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE target_pid = 1387721 AND signal = 'SIGTERM'");
+    try std.testing.expectEqual(@as(i64, 1), count);
+}
+
+test "process tree links forked children to their parent" {
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    try db.appendSyscall("trace.100", 100, Syscall.init("10:00:00.000001", "execve", "\"/bin/sh\"", 0, null, null, null, false, false));
+    try db.appendSyscall("trace.100", 100, Syscall.init("10:00:00.000002", "clone", "child_stack=NULL, flags=SIGCHLD", 200, null, null, null, false, false));
+    try db.appendSyscall("trace.100", 100, Syscall.init("10:00:00.000003", "fork", "", 300, null, null, null, false, false));
+    try db.appendSyscall("trace.100", 100, Syscall.init("10:00:00.000004", "fork", "", -1, "EAGAIN", "Resource temporarily unavailable", null, false, false));
+    try db.appendSyscall("trace.200", 200, Syscall.init("10:00:00.000005", "getppid", "", 100, null, null, null, false, false));
+    try db.endAppend();
+
+    try db.buildProcessTree();
+
+    try std.testing.expectEqual(@as(i64, 3), try db.queryInt64("SELECT COUNT(*) FROM process_tree"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM process_tree WHERE pid = 100 AND parent_pid IS NULL"));
+    try std.testing.expectEqual(@as(i64, 100), try db.queryInt64("SELECT parent_pid FROM process_tree WHERE pid = 200"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM process_tree WHERE pid = 200 AND first_seen = '10:00:00.000005'"));
+
+    // A child that was never traced still appears, first seen at its fork
+    try std.testing.expectEqual(@as(i64, 100), try db.queryInt64("SELECT parent_pid FROM process_tree WHERE pid = 300"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM process_tree WHERE pid = 300 AND first_seen = '10:00:00.000003'"));
+}
+
 test "query statistics" {
     var db = try Database.init(":memory:");
     defer db.deinit();
@@ -563,6 +1775,370 @@ test "query statistics" {
     try std.testing.expectEqual(@as(i64, 1), try db.getFailedSyscallCount()); // only the failed open
 }
 
+test "verifyRowCount reports delta on mismatch" {
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    const syscall = Syscall.init("10:00:00.000001", "close", "3", 0, null, null, 0.001, false, false);
+
+    try db.beginAppend();
+    try db.appendSyscall("test.trace", 1234, syscall);
+    try db.appendSyscall("test.trace", 1234, syscall);
+    try db.endAppend();
+
+    const matching = try db.verifyRowCount(2);
+    try std.testing.expect(matching.passed());
+    try std.testing.expectEqual(@as(i64, 0), matching.delta());
+
+    // Processor claims 3 rows but only 2 landed
+    const dropped = try db.verifyRowCount(3);
+    try std.testing.expect(!dropped.passed());
+    try std.testing.expectEqual(@as(i64, -1), dropped.delta());
+}
+
+test "reopening a database keeps its rows and indexes" {
+    const test_dir = "zig-cache/test-reopen";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    const db_path = "zig-cache/test-reopen/append.db";
+    const syscall = Syscall.init("10:00:00.000001", "close", "3", 0, null, null, 0.001, false, false);
+
+    {
+        var db = try Database.init(db_path);
+        defer db.deinit();
+        try db.beginAppend();
+        try db.appendSyscall("trace.1", 1, syscall);
+        try db.endAppend();
+    }
+
+    var db = try Database.init(db_path);
+    defer db.deinit();
+    try db.beginAppend();
+    try db.appendSyscall("trace.1", 1, syscall);
+    try db.endAppend();
+
+    // Same trace_file twice: append does not deduplicate
+    try std.testing.expectEqual(@as(i64, 2), try db.getSyscallCount());
+    try std.testing.expectEqual(@as(i64, 4), try db.queryInt64("SELECT COUNT(*) FROM duckdb_indexes() WHERE table_name = 'syscalls'"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM schema_info"));
+}
+
+test "openReadOnly queries an existing database but rejects writes" {
+    const test_dir = "zig-cache/test-readonly";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    const db_path = "zig-cache/test-readonly/built.db";
+    {
+        var db = try Database.init(db_path);
+        defer db.deinit();
+        try db.beginAppend();
+        try db.appendSyscall("trace.1", 1, Syscall.init("10:00:00.000001", "close", "3", 0, null, null, 0.001, false, false));
+        try db.endAppend();
+    }
+
+    var db = try Database.openReadOnly(db_path, default_table);
+    defer db.deinit();
+    try std.testing.expectEqual(@as(i64, 1), try db.getSyscallCount());
+    try std.testing.expectError(error.QueryFailed, db.execute("DELETE FROM syscalls"));
+}
+
+test "openReadOnly requires the file and its syscalls table" {
+    const test_dir = "zig-cache/test-readonly-missing";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    try std.testing.expectError(error.DatabaseNotFound, Database.openReadOnly(test_dir ++ "/none.db", default_table));
+
+    const db_path = test_dir ++ "/other.db";
+    {
+        var other = try Database.open(db_path, false);
+        defer other.deinit();
+        try other.execute("CREATE TABLE other (x INTEGER)");
+    }
+    try std.testing.expectError(error.SyscallsTableMissing, Database.openReadOnly(db_path, default_table));
+}
+
+test "isValidTableName accepts plain identifiers only" {
+    try std.testing.expect(isValidTableName("syscalls"));
+    try std.testing.expect(isValidTableName("app_2"));
+    try std.testing.expect(isValidTableName("_nginx"));
+    try std.testing.expect(isValidTableName("order"));
+
+    try std.testing.expect(!isValidTableName(""));
+    try std.testing.expect(!isValidTableName("2app"));
+    try std.testing.expect(!isValidTableName("app-1"));
+    try std.testing.expect(!isValidTableName("x; DROP TABLE runs"));
+    try std.testing.expect(!isValidTableName("\"quoted\""));
+    try std.testing.expect(!isValidTableName("Runs"));
+    try std.testing.expect(!isValidTableName("a" ** (max_table_name_len + 1)));
+}
+
+test "a configured table holds the rows and backs every query helper" {
+    const test_dir = "zig-cache/test-table-name";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    const db_path = test_dir ++ "/tables.db";
+    const syscall = Syscall.init("10:00:00.000001", "open", "\"/a\", O_RDONLY", -1, "ENOENT", "No such file", 0.25, false, false);
+
+    // Two trace sets in one file, each in its own table
+    inline for (.{ "app_a", "app_b" }) |table| {
+        var db = try Database.initWithSettings(db_path, .{ .table = table });
+        defer db.deinit();
+        try db.beginAppend();
+        try db.appendSyscall("trace.1", 1, syscall);
+        try db.appendSyscall("trace.1", 1, syscall);
+        try db.endAppend();
+        try std.testing.expectEqual(@as(i64, 2), try db.getFailedSyscallCount());
+        try std.testing.expectEqual(@as(i64, 1), try db.removeDuplicateRows());
+    }
+
+    var db = try Database.openReadOnly(db_path, "app_b");
+    defer db.deinit();
+    try std.testing.expectEqual(@as(i64, 1), try db.getSyscallCount());
+    try std.testing.expectEqual(@as(i64, 1), try db.getUniqueSyscallCount());
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM app_a"));
+    try std.testing.expectEqual(@as(i64, 0), try db.queryInt64("SELECT COUNT(*) FROM information_schema.tables WHERE table_name = 'syscalls'"));
+    try std.testing.expectEqual(@as(i64, 4), try db.queryInt64("SELECT COUNT(*) FROM duckdb_indexes() WHERE table_name = 'app_b' AND index_name LIKE 'idx_app_b_%'"));
+
+    var check = try db.checkSchema(std.testing.allocator);
+    defer check.deinit();
+    try std.testing.expect(check.passed());
+
+    try std.testing.expectError(error.InvalidTableName, Database.initWithSettings(":memory:", .{ .table = "bad name" }));
+}
+
+test "a SQL keyword can name the rows table" {
+    var db = try Database.initWithSettings(":memory:", .{ .table = "order" });
+    defer db.deinit();
+    try db.addTable("select");
+
+    const syscall = Syscall.init("10:00:00.000001", "close", "3", 0, null, null, 0.25, false, false);
+    try db.beginAppend();
+    try db.appendSyscall("trace.1", 1, syscall);
+    try db.appendSyscall("trace.1", 1, syscall);
+    try db.useTable("select");
+    try db.appendSyscall("trace.2", 2, syscall);
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(i64, 1), try db.countRowsIn("select"));
+    try db.useTable("order");
+    try std.testing.expectEqual(@as(i64, 2), try db.getSyscallCount());
+    try std.testing.expectEqual(@as(i64, 1), try db.removeDuplicateRows());
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM \"order\""));
+}
+
+test "checkpoint empties the write-ahead log" {
+    const test_dir = "zig-cache/test-checkpoint";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    const db_path = "zig-cache/test-checkpoint/optimize.db";
+    var db = try Database.init(db_path);
+    defer db.deinit();
+
+    try db.beginAppend();
+    for (0..100) |_| {
+        try db.appendSyscall("trace.1", 1, Syscall.init("10:00:00.000001", "close", "3", 0, null, null, 0.001, false, false));
+    }
+    try db.endAppend();
+    try db.checkpoint();
+
+    const wal_size = if (std.fs.cwd().statFile(db_path ++ ".wal")) |stat| stat.size else |_| 0;
+    try std.testing.expectEqual(@as(u64, 0), wal_size);
+    try std.testing.expectEqual(@as(i64, 100), try db.getSyscallCount());
+}
+
+test "export parquet writes every row" {
+    const test_dir = "zig-cache/test-export";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    try db.appendSyscall("trace.1", 1, Syscall.init("10:00:00.000001", "open", "\"/tmp/it's\", O_RDONLY", 3, null, null, 0.5, false, false));
+    try db.appendSyscall("trace.1", 1, Syscall.init("10:00:00.000002", "close", "3", 0, null, null, 0.1, false, false));
+    try db.endAppend();
+
+    // Quote in the path must survive SQL quoting; a second export replaces the file
+    const path = "zig-cache/test-export/it's.parquet";
+    try std.testing.expectEqual(@as(i64, 2), try db.exportParquet(path));
+    try std.testing.expectEqual(@as(i64, 2), try db.exportParquet(path));
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT COUNT(*) FROM read_parquet('zig-cache/test-export/it''s.parquet')"));
+}
+
+test "insertIter streams rows from any iterator and appendBatch from a slice" {
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    // A generator: rows made on demand, never held in a slice
+    const Counter = struct {
+        left: usize,
+
+        fn next(self: *@This()) ?Syscall {
+            if (self.left == 0) return null;
+            self.left -= 1;
+            return Syscall.init("10:00:00.000001", "getpid", "", 42, null, null, null, false, false);
+        }
+    };
+    var counter = Counter{ .left = 5 };
+    try std.testing.expectEqual(@as(usize, 5), try db.insertIter("other.log", 7, &counter));
+    try std.testing.expect(db.appender == null);
+    try std.testing.expectEqual(@as(i64, 5), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE trace_file = 'other.log' AND pid = 7"));
+
+    // Inside an open session the rows are flushed and the session stays open
+    const batch = [_]Syscall{
+        Syscall.init("10:00:00.000002", "close", "3", 0, null, null, null, false, false),
+        Syscall.init("10:00:00.000003", "close", "4", -1, "EBADF", "Bad file descriptor", null, false, false),
+    };
+    try db.beginAppend();
+    try std.testing.expectEqual(@as(usize, 2), try db.appendBatch("batch.log", 8, &batch));
+    try std.testing.expect(db.appender != null);
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE syscall = 'close'"));
+    try db.endAppend();
+
+    // A failing iterator keeps the rows before the failure and closes its own session
+    const Failing = struct {
+        calls: usize = 0,
+
+        fn next(self: *@This()) !?Syscall {
+            self.calls += 1;
+            if (self.calls > 1) return error.SourceBroken;
+            return Syscall.init("10:00:00.000004", "read", "3", 1, null, null, null, false, false);
+        }
+    };
+    var failing = Failing{};
+    try std.testing.expectError(error.SourceBroken, db.insertIter("bad.log", 9, &failing));
+    try std.testing.expect(db.appender == null);
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE trace_file = 'bad.log'"));
+}
+
+test "mergeFrom copies rows and runs of each source database" {
+    const test_dir = "zig-cache/test-merge";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    // Two databases built separately, e.g. on two machines
+    const sources = [_][]const u8{ "zig-cache/test-merge/a.db", "zig-cache/test-merge/it's.db" };
+    for (sources, 0..) |path, n| {
+        var source = try Database.init(path);
+        defer source.deinit();
+        source.run_id = if (n == 0) "run-a" else "run-b";
+        try source.beginAppend();
+        for (0..n + 1) |_| {
+            try source.appendSyscall("trace.1", 1, Syscall.init("10:00:00.000001", "close", "3", 0, null, null, null, false, false));
+        }
+        // The first source also has a --route table
+        if (n == 0) {
+            try source.addTable("web");
+            try source.useTable("web");
+            try source.appendSyscall("nginx.1", 2, Syscall.init("10:00:00.000002", "accept", "3", 4, null, null, null, false, false));
+            try source.useTable(default_table);
+        }
+        try source.endAppend();
+        try source.recordRun(.{
+            .started_at_us = 1_700_000_000_000_000,
+            .finished_at_us = 1_700_000_001_000_000,
+            .tool_version = build_options.version,
+            .file_count = 1,
+            .total_lines = n + 1,
+        });
+    }
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+    try std.testing.expectEqual(@as(i64, 1), try db.mergeFrom(sources[0]));
+    try std.testing.expectEqual(@as(i64, 2), try db.mergeFrom(sources[1]));
+
+    try std.testing.expectEqual(@as(i64, 3), try db.getSyscallCount());
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE run_id = 'run-b'"));
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT COUNT(DISTINCT run_id) FROM runs"));
+    try std.testing.expectEqual(@as(i64, 1), try db.countRowsIn("web"));
+
+    const routed = try db.routedTables(std.testing.allocator);
+    defer freeTableNames(std.testing.allocator, routed);
+    try std.testing.expectEqual(@as(usize, 1), routed.len);
+    try std.testing.expectEqualStrings("web", routed[0]);
+
+    // Each source is detached again, and a missing file leaves nothing behind
+    try std.testing.expectError(error.MergeFailed, db.mergeFrom("zig-cache/test-merge/missing.db"));
+    try std.testing.expectEqual(@as(i64, 3), try db.getSyscallCount());
+}
+
+test "export jsonl escapes quotes in args" {
+    const test_dir = "zig-cache/test-export-jsonl";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    const args = "1, \"say \\\"hi\\\"\\n\", 9";
+    try db.beginAppend();
+    try db.appendSyscall("trace.1", 1, Syscall.init("10:00:00.000001", "write", args, 9, null, null, null, false, false));
+    try db.appendSyscall("trace.1", 1, Syscall.init("10:00:00.000002", "close", "1", 0, null, null, null, false, false));
+    try db.endAppend();
+
+    const path = "zig-cache/test-export-jsonl/out.jsonl";
+    try std.testing.expectEqual(@as(i64, 2), try db.exportJsonl(path));
+
+    // One object per line, with the quotes in args escaped
+    const contents = try std.fs.cwd().readFileAlloc(std.testing.allocator, path, 1024 * 1024);
+    defer std.testing.allocator.free(contents);
+    try std.testing.expectEqual(@as(usize, 2), std.mem.count(u8, contents, "\n"));
+    try std.testing.expect(std.mem.indexOf(u8, contents, "\"args\":\"1, \\\"say \\\\\\\"hi") != null);
+
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64(
+        \\SELECT COUNT(*) FROM read_json('zig-cache/test-export-jsonl/out.jsonl', format = 'newline_delimited')
+        \\WHERE args = (SELECT args FROM syscalls WHERE syscall = 'write')
+    ));
+}
+
+test "export csv quotes args with commas and quotes" {
+    const test_dir = "zig-cache/test-export-csv";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    const args = "3, \"a,\\\"b\\\"\", 5";
+    try db.beginAppend();
+    try db.appendSyscall("trace.1", 1, Syscall.init("10:00:00.000001", "write", args, 5, null, null, null, false, false));
+    try db.endAppend();
+
+    const path = "zig-cache/test-export-csv/out.csv";
+    try std.testing.expectEqual(@as(i64, 1), try db.exportCsv(path));
+
+    // Reading the file back yields the original args untouched
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64(
+        \\SELECT COUNT(*) FROM read_csv('zig-cache/test-export-csv/out.csv', header = true, all_varchar = true)
+        \\WHERE args = (SELECT args FROM syscalls)
+    ));
+}
+
+test "time_of_day supports time-range queries" {
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    var early = Syscall.init("09:59:59.900000", "read", "3", 0, null, null, null, false, false);
+    early.time_of_day_us = 35999900000;
+    var late = Syscall.init("10:00:00.5", "read", "3", 0, null, null, null, false, false);
+    late.time_of_day_us = 36000500000;
+
+    try db.beginAppend();
+    try db.appendSyscall("trace.1", 1, early);
+    try db.appendSyscall("trace.1", 1, late);
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE time_of_day >= TIME '10:00:00'"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE time_of_day = TIME '10:00:00.5'"));
+}
+
 // ============================================================================
 // APPENDER API TESTS
 // ============================================================================
@@ -939,3 +2515,148 @@ test "atomic counter correctness with concurrent updates" {
     const final_count = counter.load(.seq_cst);
     try std.testing.expectEqual(@as(usize, num_threads * increments_per_thread), final_count);
 }
+
+test "initWithSettings applies memory and thread pragmas" {
+    var db = try Database.initWithSettings(":memory:", .{ .memory_limit = "1GiB", .threads = 2 });
+    defer db.deinit();
+
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT current_setting('threads')"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) WHERE current_setting('memory_limit') = '1.0 GiB'"));
+}
+
+test "initWithSettings rejects a memory limit DuckDB cannot parse" {
+    try std.testing.expectError(error.InvalidDatabaseSetting, Database.initWithSettings(":memory:", .{ .memory_limit = "4 bananas" }));
+}
+
+test "flush_every makes rows visible before the append session ends" {
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+    db.flush_every = 2;
+
+    var reader = try Database.connectToInstance(db.getDbInstance());
+    defer reader.deinit();
+
+    try db.beginAppend();
+    const syscall = Syscall.init("10:00:00.000001", "close", "3", 0, null, null, null, false, false);
+    for (0..5) |_| try db.appendSyscall("trace.1", 1, syscall);
+
+    // Two flushes so far; the fifth row is still buffered
+    try std.testing.expectEqual(@as(i64, 4), try reader.getSyscallCount());
+    try db.endAppend();
+    try std.testing.expectEqual(@as(i64, 5), try reader.getSyscallCount());
+}
+
+test "syscallCounts orders syscalls by frequency" {
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const names = [_][]const u8{ "read", "close", "read", "write", "read", "close" };
+    for (names) |name| {
+        try db.appendSyscall("trace.1", 1, Syscall.init("10:00:00.000001", name, "", 0, null, null, null, false, false));
+    }
+    try db.endAppend();
+
+    const counts = try db.syscallCounts(std.testing.allocator);
+    defer freeSyscallRows(std.testing.allocator, counts);
+
+    try std.testing.expectEqual(@as(usize, 3), counts.len);
+    try std.testing.expectEqualStrings("read", counts[0].syscall);
+    try std.testing.expectEqual(@as(i64, 3), counts[0].count);
+    try std.testing.expectEqualStrings("close", counts[1].syscall);
+    try std.testing.expectEqualStrings("write", counts[2].syscall);
+    try std.testing.expectEqual(@as(i64, 1), counts[2].count);
+}
+
+test "timeBySyscall sums durations and skips untimed rows" {
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    try db.appendSyscall("trace.1", 1, Syscall.init("10:00:00.000001", "read", "", 1, null, null, 0.25, false, false));
+    try db.appendSyscall("trace.1", 1, Syscall.init("10:00:00.000002", "read", "", 1, null, null, 0.5, false, false));
+    try db.appendSyscall("trace.1", 1, Syscall.init("10:00:00.000003", "poll", "", 0, null, null, 1.0, false, false));
+    try db.appendSyscall("trace.1", 1, Syscall.init("10:00:00.000004", "read", "", null, null, null, null, true, false));
+    try db.appendSyscall("trace.1", 1, Syscall.init("10:00:00.000005", "getpid", "", 1, null, null, null, false, false));
+    try db.endAppend();
+
+    const times = try db.timeBySyscall(std.testing.allocator);
+    defer freeSyscallRows(std.testing.allocator, times);
+
+    try std.testing.expectEqual(@as(usize, 2), times.len);
+    try std.testing.expectEqualStrings("poll", times[0].syscall);
+    try std.testing.expectApproxEqAbs(@as(f64, 1.0), times[0].total_duration, 1e-9);
+    try std.testing.expectEqualStrings("read", times[1].syscall);
+    try std.testing.expectApproxEqAbs(@as(f64, 0.75), times[1].total_duration, 1e-9);
+    try std.testing.expectEqual(@as(i64, 2), times[1].count);
+}
+
+test "removeDuplicateRows drops re-ingested rows from another run" {
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    const rows = [_]Syscall{
+        Syscall.init("10:00:00.000001", "open", "\"/a\", O_RDONLY", 3, null, null, 0.25, false, false),
+        Syscall.init("10:00:00.000002", "close", "3", 0, null, null, null, false, false),
+    };
+    for ([_][]const u8{ "run-1", "run-2" }) |run_id| {
+        db.run_id = run_id;
+        try db.beginAppend();
+        for (rows) |row| try db.appendSyscall("trace.1", 1, row);
+        try db.endAppend();
+    }
+
+    try std.testing.expectEqual(@as(i64, 2), try db.removeDuplicateRows());
+    try std.testing.expectEqual(@as(i64, 2), try db.getSyscallCount());
+    try std.testing.expectEqual(@as(i64, 0), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE run_id = 'run-2'"));
+    try std.testing.expectEqual(@as(i64, 0), try db.removeDuplicateRows());
+}
+
+test "recordFileStats adds one trace_files row per file" {
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+    try db.beginAppend();
+
+    try db.recordFileStats("trace.1", .{ .total_lines = 10, .parsed_lines = 9, .failed_lines = 1, .skipped = false }, 0.5);
+    try db.recordFileStats("trace.2", .{ .total_lines = 4, .parsed_lines = 4, .failed_lines = 0, .skipped = false }, 0.25);
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT COUNT(*) FROM trace_files"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT failed_lines FROM trace_files WHERE trace_file = 'trace.1'"));
+    try std.testing.expectEqual(@as(i64, 14), try db.queryInt64("SELECT SUM(total_lines) FROM trace_files"));
+}
+
+test "recordRun adds one runs row per invocation" {
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    for ([_][]const u8{ "run-1", "run-2" }, 0..) |run_id, i| {
+        db.run_id = run_id;
+        try db.recordRun(.{
+            .started_at_us = 1_700_000_000_000_000,
+            .finished_at_us = 1_700_000_002_500_000,
+            .tool_version = build_options.version,
+            .file_count = i + 1,
+            .total_lines = 100,
+        });
+    }
+
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT COUNT(*) FROM runs"));
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT file_count FROM runs WHERE run_id = 'run-2'"));
+    try std.testing.expectEqual(@as(i64, 2500), try db.queryInt64(std.fmt.comptimePrint("SELECT MAX(date_diff('millisecond', started_at, finished_at)) FROM runs WHERE tool_version = '{s}'", .{build_options.version})));
+}
+
+test "recordFileHash replaces the earlier hash of a file" {
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try std.testing.expect(!try db.hasFileHash("it's.trace", 0xabc));
+    try db.recordFileHash("it's.trace", 0xabc);
+    try std.testing.expect(try db.hasFileHash("it's.trace", 0xabc));
+    try std.testing.expect(!try db.hasFileHash("other.trace", 0xabc));
+
+    try db.recordFileHash("it's.trace", 0xdef);
+    try std.testing.expect(!try db.hasFileHash("it's.trace", 0xabc));
+    try std.testing.expect(try db.hasFileHash("it's.trace", 0xdef));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM file_hashes"));
+}
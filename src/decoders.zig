@@ -0,0 +1,514 @@
+const std = @import("std");
+const types = @import("types.zig");
+const parser = @import("parser.zig");
+const Syscall = types.Syscall;
+const ProcessOptions = types.ProcessOptions;
+
+/// Run every decoder enabled in options against a parsed syscall
+/// Decoders only fill optional fields; they never reject a line
+pub fn apply(syscall: *Syscall, options: ProcessOptions) void {
+    if (options.extract_signals) decodeSignalSend(syscall);
+    if (options.extract_dup) decodeDup(syscall);
+    if (options.extract_accept) decodeAccept(syscall);
+    if (options.extract_clock) decodeClock(syscall);
+    if (options.extract_listen) decodeListen(syscall);
+    if (options.extract_fd) decodeFd(syscall);
+    if (options.decode_mmap) decodeMmap(syscall);
+    if (options.decode_mode) decodeMode(syscall);
+    if (options.decode_sockaddr) decodeSockaddr(syscall);
+}
+
+/// Parse the nth top-level argument as a decimal integer
+fn intArg(comptime T: type, args: []const u8, n: usize) ?T {
+    const arg = parser.nthArg(args, n) orelse return null;
+    return std.fmt.parseInt(T, arg, 10) catch null;
+}
+
+/// Decode target PID/TID and signal for kill/tkill/tgkill/rt_sigqueueinfo
+/// tgkill(tgid, tid, sig) targets a single thread, so target_pid is the tid
+pub fn decodeSignalSend(syscall: *Syscall) void {
+    const name = syscall.syscall;
+
+    // Argument positions of the target and the signal
+    var target_pos: usize = 0;
+    var signal_pos: usize = 1;
+    if (std.mem.eql(u8, name, "tgkill")) {
+        target_pos = 1;
+        signal_pos = 2;
+    } else if (!std.mem.eql(u8, name, "kill") and
+        !std.mem.eql(u8, name, "tkill") and
+        !std.mem.eql(u8, name, "rt_sigqueueinfo"))
+    {
+        return;
+    }
+
+    syscall.target_pid = intArg(i64, syscall.args, target_pos);
+    if (parser.nthArg(syscall.args, signal_pos)) |sig| {
+        syscall.signal = signalName(sig);
+    }
+}
+
+/// Decode the fd aliasing established by dup/dup2/dup3
+/// dup(oldfd) returns the new fd; dup2/dup3(oldfd, newfd, ...) name it explicitly
+pub fn decodeDup(syscall: *Syscall) void {
+    const name = syscall.syscall;
+
+    if (std.mem.eql(u8, name, "dup")) {
+        syscall.oldfd = intArg(i32, syscall.args, 0);
+        if (syscall.return_value) |ret| {
+            if (ret >= 0) syscall.newfd = std.math.cast(i32, ret);
+        }
+    } else if (std.mem.eql(u8, name, "dup2") or std.mem.eql(u8, name, "dup3")) {
+        syscall.oldfd = intArg(i32, syscall.args, 0);
+        syscall.newfd = intArg(i32, syscall.args, 1);
+    }
+}
+
+/// Decode the peer address of an accepted connection
+/// The client fd is already in return_value; the peer sockaddr is found by shape
+/// rather than position so resumed lines (which lack the leading fd) also work
+pub fn decodeAccept(syscall: *Syscall) void {
+    const name = syscall.syscall;
+    if (!std.mem.eql(u8, name, "accept") and !std.mem.eql(u8, name, "accept4")) return;
+
+    const addr = findSockaddr(syscall.args) orelse return;
+    syscall.peer_addr = addr.addr;
+    syscall.peer_port = addr.port;
+}
+
+/// Decode where a server socket listens: bind(fd, addr, len) and listen(fd, backlog)
+pub fn decodeListen(syscall: *Syscall) void {
+    const name = syscall.syscall;
+
+    if (std.mem.eql(u8, name, "bind")) {
+        const addr = findSockaddr(syscall.args) orelse return;
+        syscall.bind_addr = addr.addr;
+        syscall.bind_port = addr.port;
+    } else if (std.mem.eql(u8, name, "listen")) {
+        syscall.backlog = intArg(i32, syscall.args, 1);
+    }
+}
+
+/// Decode the socket address of connect(fd, addr, len), bind(fd, addr, len) or accept/accept4
+/// Unlike --extract-accept and --extract-listen, one set of columns covers every call and
+/// the family is kept, so AF_UNIX paths and AF_INET6 addresses can be told apart
+/// The struct is found by shape like decodeAccept, so resumed accept lines work too
+pub fn decodeSockaddr(syscall: *Syscall) void {
+    const name = syscall.syscall;
+    if (!std.mem.eql(u8, name, "connect") and
+        !std.mem.eql(u8, name, "bind") and
+        !std.mem.eql(u8, name, "accept") and
+        !std.mem.eql(u8, name, "accept4"))
+    {
+        return;
+    }
+
+    const addr = findSockaddr(syscall.args) orelse return;
+    syscall.sa_family = addr.family;
+    syscall.sock_addr = addr.addr;
+    syscall.sock_port = addr.port;
+}
+
+/// Decode a leading integer argument into fd (read(3, ...), close(3), fstat(3, ...))
+/// Any syscall qualifies, so a leading integer is not always a descriptor (kill's pid, exit_group's status)
+pub fn decodeFd(syscall: *Syscall) void {
+    syscall.fd = parser.leadingFd(syscall.args);
+}
+
+/// Decode the protection and mapping flags of a memory mapping call
+/// mmap/mmap2(addr, length, prot, flags, fd, offset) fill both; mremap(old, old_size,
+/// new_size, flags[, new_addr]) has only flags. The mapped address is already in return_addr.
+/// munmap takes no flags and is left alone
+pub fn decodeMmap(syscall: *Syscall) void {
+    const name = syscall.syscall;
+
+    if (std.mem.eql(u8, name, "mmap") or std.mem.eql(u8, name, "mmap2")) {
+        syscall.mmap_prot = parser.nthArg(syscall.args, 2);
+        syscall.mmap_flags = parser.nthArg(syscall.args, 3);
+    } else if (std.mem.eql(u8, name, "mremap")) {
+        syscall.mmap_flags = parser.nthArg(syscall.args, 3);
+    }
+}
+
+/// Position of the mode argument for syscalls that take one
+const mode_arg_positions = std.StaticStringMap(usize).initComptime(.{
+    .{ "open", 2 },
+    .{ "openat", 3 },
+    .{ "creat", 1 },
+    .{ "chmod", 1 },
+    .{ "fchmod", 1 },
+    .{ "fchmodat", 2 },
+    .{ "mkdir", 1 },
+    .{ "mkdirat", 2 },
+    .{ "mknod", 1 },
+    .{ "mknodat", 2 },
+});
+
+/// Decode the permission bits of a call from its octal mode argument
+/// open/openat only print a mode with O_CREAT or O_TMPFILE; mknod modes that
+/// include a file type (S_IFCHR|0600) are not plain octal and stay null
+pub fn decodeMode(syscall: *Syscall) void {
+    const position = mode_arg_positions.get(syscall.syscall) orelse return;
+    const arg = parser.nthArg(syscall.args, position) orelse return;
+    syscall.mode = parseOctalMode(arg);
+}
+
+/// Parse an octal token as strace prints modes: a leading 0, then octal digits ("0644" is 420)
+pub fn parseOctalMode(token: []const u8) ?i32 {
+    if (token.len < 2 or token[0] != '0') return null;
+    return std.fmt.parseInt(i32, token[1..], 8) catch null;
+}
+
+/// Decode the time returned by a clock read into nanoseconds
+/// clock_gettime(clk, {tv_sec, tv_nsec}) also yields clock_id; gettimeofday
+/// reports microseconds and has no clock id. Failed reads leave both null
+pub fn decodeClock(syscall: *Syscall) void {
+    const name = syscall.syscall;
+    if ((syscall.return_value orelse return) != 0) return;
+
+    if (std.mem.eql(u8, name, "clock_gettime") or std.mem.eql(u8, name, "clock_gettime64")) {
+        const clock = parser.nthArg(syscall.args, 0) orelse return;
+        const time = parser.nthArg(syscall.args, 1) orelse return;
+        syscall.clock_id = clock;
+        syscall.clock_value_ns = timeToNs(time, "tv_nsec", 1);
+    } else if (std.mem.eql(u8, name, "gettimeofday")) {
+        const time = parser.nthArg(syscall.args, 0) orelse return;
+        syscall.clock_value_ns = timeToNs(time, "tv_usec", std.time.ns_per_us);
+    }
+}
+
+/// Combine {tv_sec=S, <frac_field>=F} into nanoseconds, null on overflow
+fn timeToNs(time: []const u8, frac_field: []const u8, frac_scale: i64) ?i64 {
+    const sec = std.fmt.parseInt(i64, structField(time, "tv_sec") orelse return null, 10) catch return null;
+    const frac = std.fmt.parseInt(i64, structField(time, frac_field) orelse return null, 10) catch return null;
+    const sec_ns = std.math.mul(i64, sec, std.time.ns_per_s) catch return null;
+    const frac_ns = std.math.mul(i64, frac, frac_scale) catch return null;
+    return std.math.add(i64, sec_ns, frac_ns) catch null;
+}
+
+/// Value of a `name=value` field in a strace struct argument like {a=1, b=2}
+fn structField(arg: []const u8, name: []const u8) ?[]const u8 {
+    if (arg.len < 2 or arg[0] != '{' or arg[arg.len - 1] != '}') return null;
+
+    var fields = parser.ArgIterator.init(arg[1 .. arg.len - 1]);
+    while (fields.next()) |field| {
+        if (field.len > name.len and std.mem.startsWith(u8, field, name) and field[name.len] == '=') {
+            return field[name.len + 1 ..];
+        }
+    }
+    return null;
+}
+
+/// Socket address decoded from a strace sockaddr struct
+pub const Sockaddr = struct {
+    family: []const u8,
+    /// IPv4/IPv6 address or AF_UNIX path
+    addr: ?[]const u8 = null,
+    port: ?u16 = null,
+};
+
+/// Return the first top-level argument that is a sockaddr struct
+pub fn findSockaddr(args: []const u8) ?Sockaddr {
+    var it = parser.ArgIterator.init(args);
+    while (it.next()) |arg| {
+        if (parseSockaddr(arg)) |addr| return addr;
+    }
+    return null;
+}
+
+/// Parse a strace sockaddr struct argument:
+///   {sa_family=AF_INET, sin_port=htons(443), sin_addr=inet_addr("1.2.3.4")}
+///   {sa_family=AF_INET6, sin6_port=htons(443), ..., inet_pton(AF_INET6, "::1", &sin6_addr), ...}
+///   {sa_family=AF_UNIX, sun_path="/run/app.sock"}
+pub fn parseSockaddr(arg: []const u8) ?Sockaddr {
+    if (!std.mem.startsWith(u8, arg, "{sa_family=") or arg[arg.len - 1] != '}') return null;
+
+    var addr = Sockaddr{ .family = "" };
+    var fields = parser.ArgIterator.init(arg[1 .. arg.len - 1]);
+    while (fields.next()) |field| {
+        if (std.mem.startsWith(u8, field, "sa_family=")) {
+            addr.family = field["sa_family=".len..];
+        } else if (std.mem.startsWith(u8, field, "sin_port=") or std.mem.startsWith(u8, field, "sin6_port=")) {
+            addr.port = parenInt(u16, field);
+        } else if (std.mem.startsWith(u8, field, "sin_addr=") or
+            std.mem.startsWith(u8, field, "inet_pton(") or
+            std.mem.startsWith(u8, field, "sun_path="))
+        {
+            addr.addr = quoted(field);
+        }
+    }
+    return addr;
+}
+
+/// Contents of the first double-quoted string in text
+fn quoted(text: []const u8) ?[]const u8 {
+    const start = std.mem.indexOfScalar(u8, text, '"') orelse return null;
+    const end = std.mem.indexOfScalarPos(u8, text, start + 1, '"') orelse return null;
+    return text[start + 1 .. end];
+}
+
+/// Integer inside the first parentheses, e.g. htons(443) -> 443
+fn parenInt(comptime T: type, text: []const u8) ?T {
+    const start = std.mem.indexOfScalar(u8, text, '(') orelse return null;
+    const end = std.mem.indexOfScalarPos(u8, text, start + 1, ')') orelse return null;
+    return std.fmt.parseInt(T, text[start + 1 .. end], 10) catch null;
+}
+
+/// Normalize a signal argument: symbolic names pass through, known numbers map to names
+/// Unknown numbers (e.g. 0 for an existence check) are kept verbatim
+pub fn signalName(token: []const u8) []const u8 {
+    const number = std.fmt.parseInt(u8, token, 10) catch return token;
+    if (number < signal_names.len) {
+        if (signal_names[number]) |name| return name;
+    }
+    return token;
+}
+
+/// Linux signal numbers (x86/arm generic numbering)
+const signal_names = [_]?[]const u8{
+    null,        "SIGHUP",  "SIGINT",    "SIGQUIT", "SIGILL",   "SIGTRAP", "SIGABRT", "SIGBUS",
+    "SIGFPE",    "SIGKILL", "SIGUSR1",   "SIGSEGV", "SIGUSR2",  "SIGPIPE", "SIGALRM", "SIGTERM",
+    "SIGSTKFLT", "SIGCHLD", "SIGCONT",   "SIGSTOP", "SIGTSTP",  "SIGTTIN", "SIGTTOU", "SIGURG",
+    "SIGXCPU",   "SIGXFSZ", "SIGVTALRM", "SIGPROF", "SIGWINCH", "SIGIO",   "SIGPWR",  "SIGSYS",
+};
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+fn parseTestLine(line: []const u8) !Syscall {
+    return (try parser.parseLine(std.testing.allocator, line)).?;
+}
+
+test "decode kill target and symbolic signal" {
+    var syscall = try parseTestLine("22:21:24.927000 kill(1387721, SIGTERM) = 0 <0.000010>");
+    decodeSignalSend(&syscall);
+
+    try std.testing.expectEqual(@as(?i64, 1387721), syscall.target_pid);
+    try std.testing.expectEqualStrings("SIGTERM", syscall.signal.?);
+}
+
+test "decode tgkill targets the thread id" {
+    var syscall = try parseTestLine("22:21:24.927000 tgkill(1387679, 1387680, SIGUSR1) = 0 <0.000004>");
+    decodeSignalSend(&syscall);
+
+    try std.testing.expectEqual(@as(?i64, 1387680), syscall.target_pid);
+    try std.testing.expectEqualStrings("SIGUSR1", syscall.signal.?);
+}
+
+test "decode numeric signals" {
+    var killed = try parseTestLine("10:00:00.000001 kill(-1234, 9) = 0");
+    decodeSignalSend(&killed);
+    try std.testing.expectEqual(@as(?i64, -1234), killed.target_pid);
+    try std.testing.expectEqualStrings("SIGKILL", killed.signal.?);
+
+    // Signal 0 only checks for existence and has no name
+    var probe = try parseTestLine("10:00:00.000002 kill(42, 0) = -1 ESRCH (No such process)");
+    decodeSignalSend(&probe);
+    try std.testing.expectEqual(@as(?i64, 42), probe.target_pid);
+    try std.testing.expectEqualStrings("0", probe.signal.?);
+}
+
+test "decode rt_sigqueueinfo" {
+    var syscall = try parseTestLine("10:00:00.000001 rt_sigqueueinfo(5678, SIGRTMIN, {si_signo=SIGRTMIN, si_code=SI_QUEUE, si_pid=1234}) = 0");
+    decodeSignalSend(&syscall);
+
+    try std.testing.expectEqual(@as(?i64, 5678), syscall.target_pid);
+    try std.testing.expectEqualStrings("SIGRTMIN", syscall.signal.?);
+}
+
+test "decode dup uses the return value as newfd" {
+    var syscall = try parseTestLine("10:00:00.000001 dup(3) = 7 <0.000004>");
+    decodeDup(&syscall);
+
+    try std.testing.expectEqual(@as(?i32, 3), syscall.oldfd);
+    try std.testing.expectEqual(@as(?i32, 7), syscall.newfd);
+
+    var failed = try parseTestLine("10:00:00.000002 dup(99) = -1 EBADF (Bad file descriptor)");
+    decodeDup(&failed);
+    try std.testing.expectEqual(@as(?i32, 99), failed.oldfd);
+    try std.testing.expectEqual(@as(?i32, null), failed.newfd);
+}
+
+test "decode dup2 and dup3" {
+    var dup2 = try parseTestLine("10:00:00.000001 dup2(3, 5) = 5 <0.000004>");
+    decodeDup(&dup2);
+    try std.testing.expectEqual(@as(?i32, 3), dup2.oldfd);
+    try std.testing.expectEqual(@as(?i32, 5), dup2.newfd);
+
+    var dup3 = try parseTestLine("10:00:00.000002 dup3(4, 1, O_CLOEXEC) = 1 <0.000004>");
+    decodeDup(&dup3);
+    try std.testing.expectEqual(@as(?i32, 4), dup3.oldfd);
+    try std.testing.expectEqual(@as(?i32, 1), dup3.newfd);
+}
+
+test "decode accept4 IPv4 peer" {
+    var syscall = try parseTestLine("10:00:00.000001 accept4(3, {sa_family=AF_INET, sin_port=htons(54321), sin_addr=inet_addr(\"10.0.0.5\")}, [16], SOCK_CLOEXEC) = 7 <0.000020>");
+    decodeAccept(&syscall);
+
+    try std.testing.expectEqual(@as(?i64, 7), syscall.return_value);
+    try std.testing.expectEqualStrings("10.0.0.5", syscall.peer_addr.?);
+    try std.testing.expectEqual(@as(?u16, 54321), syscall.peer_port);
+}
+
+test "decode accept without a peer address" {
+    var syscall = try parseTestLine("10:00:00.000001 accept(3, NULL, NULL) = 8");
+    decodeAccept(&syscall);
+
+    try std.testing.expectEqual(@as(?[]const u8, null), syscall.peer_addr);
+    try std.testing.expectEqual(@as(?u16, null), syscall.peer_port);
+}
+
+test "decode bind address and port" {
+    var syscall = try parseTestLine("10:00:00.000001 bind(3, {sa_family=AF_INET, sin_port=htons(8080), sin_addr=inet_addr(\"0.0.0.0\")}, 16) = 0 <0.000010>");
+    decodeListen(&syscall);
+
+    try std.testing.expectEqualStrings("0.0.0.0", syscall.bind_addr.?);
+    try std.testing.expectEqual(@as(?u16, 8080), syscall.bind_port);
+    try std.testing.expectEqual(@as(?i32, null), syscall.backlog);
+}
+
+test "decode connect sockaddr for IPv4, IPv6 and AF_UNIX" {
+    var inet = try parseTestLine("10:00:00.000001 connect(3, {sa_family=AF_INET, sin_port=htons(443), sin_addr=inet_addr(\"1.2.3.4\")}, 16) = 0 <0.000050>");
+    decodeSockaddr(&inet);
+    try std.testing.expectEqualStrings("AF_INET", inet.sa_family.?);
+    try std.testing.expectEqualStrings("1.2.3.4", inet.sock_addr.?);
+    try std.testing.expectEqual(@as(?u16, 443), inet.sock_port);
+
+    var inet6 = try parseTestLine("10:00:00.000002 connect(4, {sa_family=AF_INET6, sin6_port=htons(8443), sin6_flowinfo=htonl(0), inet_pton(AF_INET6, \"2001:db8::1\", &sin6_addr), sin6_scope_id=0}, 28) = -1 EINPROGRESS (Operation now in progress)");
+    decodeSockaddr(&inet6);
+    try std.testing.expectEqualStrings("AF_INET6", inet6.sa_family.?);
+    try std.testing.expectEqualStrings("2001:db8::1", inet6.sock_addr.?);
+    try std.testing.expectEqual(@as(?u16, 8443), inet6.sock_port);
+
+    var unix = try parseTestLine("10:00:00.000003 connect(5, {sa_family=AF_UNIX, sun_path=\"/run/dbus/system_bus_socket\"}, 110) = 0");
+    decodeSockaddr(&unix);
+    try std.testing.expectEqualStrings("AF_UNIX", unix.sa_family.?);
+    try std.testing.expectEqualStrings("/run/dbus/system_bus_socket", unix.sock_addr.?);
+    try std.testing.expectEqual(@as(?u16, null), unix.sock_port);
+}
+
+test "decode sockaddr of bind and accept, but not other calls" {
+    var bind = try parseTestLine("10:00:00.000001 bind(3, {sa_family=AF_INET, sin_port=htons(8080), sin_addr=inet_addr(\"0.0.0.0\")}, 16) = 0");
+    decodeSockaddr(&bind);
+    try std.testing.expectEqualStrings("0.0.0.0", bind.sock_addr.?);
+    try std.testing.expectEqual(@as(?u16, 8080), bind.sock_port);
+    // The --extract-listen columns are separate
+    try std.testing.expectEqual(@as(?[]const u8, null), bind.bind_addr);
+
+    var accept = try parseTestLine("10:00:00.000002 accept4(3, {sa_family=AF_INET6, sin6_port=htons(50000), sin6_flowinfo=htonl(0), inet_pton(AF_INET6, \"::1\", &sin6_addr), sin6_scope_id=0}, [28], SOCK_CLOEXEC) = 7");
+    decodeSockaddr(&accept);
+    try std.testing.expectEqualStrings("AF_INET6", accept.sa_family.?);
+    try std.testing.expectEqualStrings("::1", accept.sock_addr.?);
+
+    var sendto = try parseTestLine("10:00:00.000003 sendto(3, \"x\", 1, 0, {sa_family=AF_INET, sin_port=htons(53), sin_addr=inet_addr(\"8.8.8.8\")}, 16) = 1");
+    decodeSockaddr(&sendto);
+    try std.testing.expectEqual(@as(?[]const u8, null), sendto.sa_family);
+
+    // apply runs it only when enabled
+    var connect = try parseTestLine("10:00:00.000004 connect(3, {sa_family=AF_UNIX, sun_path=\"/tmp/s\"}, 110) = 0");
+    apply(&connect, .{});
+    try std.testing.expectEqual(@as(?[]const u8, null), connect.sa_family);
+    apply(&connect, .{ .decode_sockaddr = true });
+    try std.testing.expectEqualStrings("/tmp/s", connect.sock_addr.?);
+}
+
+test "decode listen backlog" {
+    var syscall = try parseTestLine("10:00:00.000002 listen(3, 128) = 0 <0.000004>");
+    decodeListen(&syscall);
+
+    try std.testing.expectEqual(@as(?i32, 128), syscall.backlog);
+    try std.testing.expectEqual(@as(?[]const u8, null), syscall.bind_addr);
+}
+
+test "decode clock_gettime into nanoseconds" {
+    var syscall = try parseTestLine("10:00:00.000001 clock_gettime(CLOCK_MONOTONIC, {tv_sec=12345, tv_nsec=678901234}) = 0 <0.000003>");
+    decodeClock(&syscall);
+
+    try std.testing.expectEqualStrings("CLOCK_MONOTONIC", syscall.clock_id.?);
+    try std.testing.expectEqual(@as(?i64, 12345678901234), syscall.clock_value_ns);
+}
+
+test "decode gettimeofday microseconds" {
+    var syscall = try parseTestLine("10:00:00.000001 gettimeofday({tv_sec=1700000000, tv_usec=250000}, NULL) = 0");
+    decodeClock(&syscall);
+
+    try std.testing.expectEqual(@as(?[]const u8, null), syscall.clock_id);
+    try std.testing.expectEqual(@as(?i64, 1700000000250000000), syscall.clock_value_ns);
+
+    var failed = try parseTestLine("10:00:00.000002 clock_gettime(CLOCK_BOOTTIME, 0x1) = -1 EFAULT (Bad address)");
+    decodeClock(&failed);
+    try std.testing.expectEqual(@as(?i64, null), failed.clock_value_ns);
+}
+
+test "decode mmap prot and flags" {
+    var syscall = try parseTestLine("10:00:00.000001 mmap(NULL, 8192, PROT_READ|PROT_WRITE, MAP_PRIVATE|MAP_ANONYMOUS, -1, 0) = 0x7f3c2a1f0000");
+    decodeMmap(&syscall);
+
+    try std.testing.expectEqualStrings("PROT_READ|PROT_WRITE", syscall.mmap_prot.?);
+    try std.testing.expectEqualStrings("MAP_PRIVATE|MAP_ANONYMOUS", syscall.mmap_flags.?);
+    try std.testing.expectEqual(@as(?u64, 0x7f3c2a1f0000), syscall.return_addr);
+
+    var remap = try parseTestLine("10:00:00.000002 mremap(0x7f3c2a1f0000, 8192, 16384, MREMAP_MAYMOVE) = 0x7f3c2a1e0000");
+    decodeMmap(&remap);
+    try std.testing.expectEqual(@as(?[]const u8, null), remap.mmap_prot);
+    try std.testing.expectEqualStrings("MREMAP_MAYMOVE", remap.mmap_flags.?);
+
+    var unmap = try parseTestLine("10:00:00.000003 munmap(0x7f3c2a1e0000, 16384) = 0");
+    decodeMmap(&unmap);
+    try std.testing.expectEqual(@as(?[]const u8, null), unmap.mmap_flags);
+}
+
+test "parseOctalMode converts strace mode tokens" {
+    try std.testing.expectEqual(@as(?i32, 420), parseOctalMode("0644"));
+    try std.testing.expectEqual(@as(?i32, 493), parseOctalMode("0755"));
+    try std.testing.expectEqual(@as(?i32, 0), parseOctalMode("000"));
+    try std.testing.expectEqual(@as(?i32, 0o4755), parseOctalMode("04755"));
+    try std.testing.expectEqual(@as(?i32, null), parseOctalMode("644"));
+    try std.testing.expectEqual(@as(?i32, null), parseOctalMode("0"));
+    try std.testing.expectEqual(@as(?i32, null), parseOctalMode("0689"));
+    try std.testing.expectEqual(@as(?i32, null), parseOctalMode("0x1ff"));
+}
+
+test "decode mode for open, chmod and mkdir calls" {
+    var create = try parseTestLine("10:00:00.000001 openat(AT_FDCWD, \"x\", O_WRONLY|O_CREAT|O_TRUNC, 0644) = 3");
+    decodeMode(&create);
+    try std.testing.expectEqual(@as(?i32, 420), create.mode);
+
+    var chmod = try parseTestLine("10:00:00.000002 chmod(\"x\", 0755) = 0");
+    decodeMode(&chmod);
+    try std.testing.expectEqual(@as(?i32, 493), chmod.mode);
+
+    var mkdir = try parseTestLine("10:00:00.000003 mkdirat(AT_FDCWD, \"dir\", 0700) = 0");
+    decodeMode(&mkdir);
+    try std.testing.expectEqual(@as(?i32, 448), mkdir.mode);
+
+    // No O_CREAT means no mode argument
+    var read_only = try parseTestLine("10:00:00.000004 openat(AT_FDCWD, \"x\", O_RDONLY) = 3");
+    decodeMode(&read_only);
+    try std.testing.expectEqual(@as(?i32, null), read_only.mode);
+
+    // Octal-looking arguments of other syscalls are left alone
+    var write = try parseTestLine("10:00:00.000005 write(1, \"x\", 0644) = 420");
+    decodeMode(&write);
+    try std.testing.expectEqual(@as(?i32, null), write.mode);
+}
+
+test "apply leaves signal fields empty when disabled or unrelated" {
+    var syscall = try parseTestLine("22:21:24.927000 kill(1387721, SIGTERM) = 0");
+    apply(&syscall, .{});
+    try std.testing.expectEqual(@as(?i64, null), syscall.target_pid);
+
+    var other = try parseTestLine("10:00:00.000001 close(3) = 0");
+    apply(&other, .{ .extract_signals = true });
+    try std.testing.expectEqual(@as(?i64, null), other.target_pid);
+    try std.testing.expectEqual(@as(?[]const u8, null), other.signal);
+}
+
+test "apply decodes fd only when enabled" {
+    var write = try parseTestLine("10:00:00.000001 write(1, \"hi\\n\", 3) = 3");
+    apply(&write, .{});
+    try std.testing.expectEqual(@as(?i32, null), write.fd);
+    apply(&write, .{ .extract_fd = true });
+    try std.testing.expectEqual(@as(?i32, 1), write.fd);
+}
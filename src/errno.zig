@@ -0,0 +1,137 @@
+const std = @import("std");
+
+/// Linux errno names as strace prints them, with their numeric values
+/// Values are the asm-generic ones shared by x86, x86_64 and arm64
+/// Aliases (EWOULDBLOCK, EDEADLOCK, ENOTSUP) map to the same number as the name they alias
+const errno_values = std.StaticStringMap(i32).initComptime(.{
+    .{ "EPERM", 1 },
+    .{ "ENOENT", 2 },
+    .{ "ESRCH", 3 },
+    .{ "EINTR", 4 },
+    .{ "EIO", 5 },
+    .{ "ENXIO", 6 },
+    .{ "E2BIG", 7 },
+    .{ "ENOEXEC", 8 },
+    .{ "EBADF", 9 },
+    .{ "ECHILD", 10 },
+    .{ "EAGAIN", 11 },
+    .{ "EWOULDBLOCK", 11 },
+    .{ "ENOMEM", 12 },
+    .{ "EACCES", 13 },
+    .{ "EFAULT", 14 },
+    .{ "ENOTBLK", 15 },
+    .{ "EBUSY", 16 },
+    .{ "EEXIST", 17 },
+    .{ "EXDEV", 18 },
+    .{ "ENODEV", 19 },
+    .{ "ENOTDIR", 20 },
+    .{ "EISDIR", 21 },
+    .{ "EINVAL", 22 },
+    .{ "ENFILE", 23 },
+    .{ "EMFILE", 24 },
+    .{ "ENOTTY", 25 },
+    .{ "ETXTBSY", 26 },
+    .{ "EFBIG", 27 },
+    .{ "ENOSPC", 28 },
+    .{ "ESPIPE", 29 },
+    .{ "EROFS", 30 },
+    .{ "EMLINK", 31 },
+    .{ "EPIPE", 32 },
+    .{ "EDOM", 33 },
+    .{ "ERANGE", 34 },
+    .{ "EDEADLK", 35 },
+    .{ "EDEADLOCK", 35 },
+    .{ "ENAMETOOLONG", 36 },
+    .{ "ENOLCK", 37 },
+    .{ "ENOSYS", 38 },
+    .{ "ENOTEMPTY", 39 },
+    .{ "ELOOP", 40 },
+    .{ "ENOMSG", 42 },
+    .{ "EIDRM", 43 },
+    .{ "ENOSTR", 60 },
+    .{ "ENODATA", 61 },
+    .{ "ETIME", 62 },
+    .{ "ENOSR", 63 },
+    .{ "ENOLINK", 67 },
+    .{ "EPROTO", 71 },
+    .{ "EMULTIHOP", 72 },
+    .{ "EBADMSG", 74 },
+    .{ "EOVERFLOW", 75 },
+    .{ "EBADFD", 77 },
+    .{ "EILSEQ", 84 },
+    .{ "ERESTART", 85 },
+    .{ "EUSERS", 87 },
+    .{ "ENOTSOCK", 88 },
+    .{ "EDESTADDRREQ", 89 },
+    .{ "EMSGSIZE", 90 },
+    .{ "EPROTOTYPE", 91 },
+    .{ "ENOPROTOOPT", 92 },
+    .{ "EPROTONOSUPPORT", 93 },
+    .{ "ESOCKTNOSUPPORT", 94 },
+    .{ "EOPNOTSUPP", 95 },
+    .{ "ENOTSUP", 95 },
+    .{ "EPFNOSUPPORT", 96 },
+    .{ "EAFNOSUPPORT", 97 },
+    .{ "EADDRINUSE", 98 },
+    .{ "EADDRNOTAVAIL", 99 },
+    .{ "ENETDOWN", 100 },
+    .{ "ENETUNREACH", 101 },
+    .{ "ENETRESET", 102 },
+    .{ "ECONNABORTED", 103 },
+    .{ "ECONNRESET", 104 },
+    .{ "ENOBUFS", 105 },
+    .{ "EISCONN", 106 },
+    .{ "ENOTCONN", 107 },
+    .{ "ESHUTDOWN", 108 },
+    .{ "ETOOMANYREFS", 109 },
+    .{ "ETIMEDOUT", 110 },
+    .{ "ECONNREFUSED", 111 },
+    .{ "EHOSTDOWN", 112 },
+    .{ "EHOSTUNREACH", 113 },
+    .{ "EALREADY", 114 },
+    .{ "EINPROGRESS", 115 },
+    .{ "ESTALE", 116 },
+    .{ "EUCLEAN", 117 },
+    .{ "EREMOTEIO", 121 },
+    .{ "EDQUOT", 122 },
+    .{ "ENOMEDIUM", 123 },
+    .{ "EMEDIUMTYPE", 124 },
+    .{ "ECANCELED", 125 },
+    .{ "ENOKEY", 126 },
+    .{ "EKEYEXPIRED", 127 },
+    .{ "EKEYREVOKED", 128 },
+    .{ "EKEYREJECTED", 129 },
+    .{ "EOWNERDEAD", 130 },
+    .{ "ENOTRECOVERABLE", 131 },
+    .{ "ERFKILL", 132 },
+    .{ "EHWPOISON", 133 },
+    // Kernel-internal codes strace shows for interrupted calls
+    .{ "ERESTARTSYS", 512 },
+    .{ "ERESTARTNOINTR", 513 },
+    .{ "ERESTARTNOHAND", 514 },
+    .{ "ENOIOCTLCMD", 515 },
+    .{ "ERESTART_RESTARTBLOCK", 516 },
+});
+
+/// Numeric errno for a name such as "ENOENT", or null for names not in the table
+pub fn errnoNumber(name: []const u8) ?i32 {
+    return errno_values.get(name);
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+test "errnoNumber maps common names and aliases" {
+    try std.testing.expectEqual(@as(?i32, 2), errnoNumber("ENOENT"));
+    try std.testing.expectEqual(@as(?i32, 13), errnoNumber("EACCES"));
+    try std.testing.expectEqual(@as(?i32, 11), errnoNumber("EWOULDBLOCK"));
+    try std.testing.expectEqual(errnoNumber("EOPNOTSUPP"), errnoNumber("ENOTSUP"));
+    try std.testing.expectEqual(@as(?i32, 512), errnoNumber("ERESTARTSYS"));
+}
+
+test "errnoNumber returns null for unknown names" {
+    try std.testing.expectEqual(@as(?i32, null), errnoNumber("EMADEUP"));
+    try std.testing.expectEqual(@as(?i32, null), errnoNumber("enoent"));
+    try std.testing.expectEqual(@as(?i32, null), errnoNumber(""));
+}
@@ -0,0 +1,81 @@
+const std = @import("std");
+const parser = @import("parser.zig");
+
+/// Failures reported by the DuckDB layer (Database methods)
+pub const DatabaseError = error{
+    DatabaseOpenFailed,
+    DatabaseConnectFailed,
+    DatabaseNotFound,
+    SyscallsTableMissing,
+    InvalidDatabaseSetting,
+    InvalidTableName,
+    SchemaCreationFailed,
+    IndexCreationFailed,
+    QueryFailed,
+    AppenderNotInitialized,
+    AppenderCreateFailed,
+    AppendFailed,
+    AppenderFlushFailed,
+    AppenderDestroyFailed,
+    ProcessTreeFailed,
+    DedupeFailed,
+    ExportFailed,
+    MergeFailed,
+    CheckpointFailed,
+};
+
+/// Failures confined to one input file; a parallel run counts them and moves on
+pub const InputError = error{
+    FileNotFound,
+    AccessDenied,
+    LineTooLong,
+};
+
+/// Reasons a single line is rejected (see parser.parseLineVerbose)
+pub const ParseError = parser.ParseError;
+
+/// Broad category of an error returned by the library
+pub const Kind = enum {
+    input,
+    parse,
+    database,
+    out_of_memory,
+    other,
+};
+
+/// Classify any error, including those of the processor APIs whose error sets are inferred
+pub fn kindOf(err: anyerror) Kind {
+    if (err == error.OutOfMemory) return .out_of_memory;
+    if (isIn(InputError, err)) return .input;
+    if (isIn(ParseError, err)) return .parse;
+    if (isIn(DatabaseError, err)) return .database;
+    return .other;
+}
+
+/// Whether err is a member of the error set Set
+pub fn isIn(comptime Set: type, err: anyerror) bool {
+    inline for (@typeInfo(Set).error_set.?) |member| {
+        if (err == @field(Set, member.name)) return true;
+    }
+    return false;
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+test "kindOf sorts errors into input, parse and database failures" {
+    try std.testing.expectEqual(Kind.input, kindOf(error.FileNotFound));
+    try std.testing.expectEqual(Kind.input, kindOf(error.LineTooLong));
+    try std.testing.expectEqual(Kind.parse, kindOf(error.MissingTimestamp));
+    try std.testing.expectEqual(Kind.database, kindOf(error.QueryFailed));
+    try std.testing.expectEqual(Kind.database, kindOf(error.AppenderFlushFailed));
+    try std.testing.expectEqual(Kind.out_of_memory, kindOf(error.OutOfMemory));
+    try std.testing.expectEqual(Kind.other, kindOf(error.Unexpected));
+}
+
+test "isIn checks membership of a single set" {
+    try std.testing.expect(isIn(DatabaseError, error.CheckpointFailed));
+    try std.testing.expect(!isIn(DatabaseError, error.FileNotFound));
+    try std.testing.expect(isIn(ParseError, error.InvalidReturn));
+}
@@ -0,0 +1,72 @@
+const std = @import("std");
+const parser = @import("parser.zig");
+const types = @import("types.zig");
+const Syscall = types.Syscall;
+
+/// Run the verbose parser on one line and describe the result
+/// Prints every extracted field, or the ParseError and the offending slice
+/// Returns true if the line parsed
+pub fn explainLine(allocator: std.mem.Allocator, writer: *std.Io.Writer, line: []const u8) !bool {
+    var diagnostic = parser.Diagnostic{};
+    const syscall = parser.parseLineVerbose(allocator, line, &diagnostic) catch |err| {
+        try writer.print("error: {s}\n", .{@errorName(err)});
+        try writer.print("at: {s}\n", .{diagnostic.slice});
+        return false;
+    };
+
+    inline for (std.meta.fields(Syscall)) |field| {
+        try writer.print("{s}: ", .{field.name});
+        try writeValue(writer, field.type, @field(syscall, field.name));
+        try writer.writeByte('\n');
+    }
+    return true;
+}
+
+fn writeValue(writer: *std.Io.Writer, comptime T: type, value: T) !void {
+    switch (@typeInfo(T)) {
+        .optional => |optional| {
+            if (value) |present| {
+                try writeValue(writer, optional.child, present);
+            } else {
+                try writer.writeAll("null");
+            }
+        },
+        .pointer => try writer.print("{s}", .{value}),
+        .bool => try writer.print("{}", .{value}),
+        .@"enum" => try writer.writeAll(@tagName(value)),
+        .int, .float => try writer.print("{d}", .{value}),
+        else => @compileError("unsupported Syscall field type: " ++ @typeName(T)),
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+test "explainLine prints the fields of a good line" {
+    var buffer: [4096]u8 = undefined;
+    var writer = std.Io.Writer.fixed(&buffer);
+
+    const parsed = try explainLine(std.testing.allocator, &writer, "10:23:45.123456 open(\"/tmp/file\", O_RDONLY) = -1 ENOENT (No such file) <0.000042>");
+    const output = writer.buffered();
+
+    try std.testing.expect(parsed);
+    try std.testing.expect(std.mem.indexOf(u8, output, "timestamp: 10:23:45.123456\n") != null);
+    try std.testing.expect(std.mem.indexOf(u8, output, "syscall: open\n") != null);
+    try std.testing.expect(std.mem.indexOf(u8, output, "args: \"/tmp/file\", O_RDONLY\n") != null);
+    try std.testing.expect(std.mem.indexOf(u8, output, "return_value: -1\n") != null);
+    try std.testing.expect(std.mem.indexOf(u8, output, "error_code: ENOENT\n") != null);
+    try std.testing.expect(std.mem.indexOf(u8, output, "duration: 0.000042\n") != null);
+    try std.testing.expect(std.mem.indexOf(u8, output, "unfinished: false\n") != null);
+    try std.testing.expect(std.mem.indexOf(u8, output, "target_pid: null\n") != null);
+}
+
+test "explainLine reports the parse error of a bad line" {
+    var buffer: [1024]u8 = undefined;
+    var writer = std.Io.Writer.fixed(&buffer);
+
+    const parsed = try explainLine(std.testing.allocator, &writer, "10:23:45.123456 read(3, \"abc\", 3");
+
+    try std.testing.expect(!parsed);
+    try std.testing.expectEqualStrings("error: MissingClosingParen\nat: 3, \"abc\", 3\n", writer.buffered());
+}
@@ -2,9 +2,28 @@ const std = @import("std");
 const parser = @import("parser.zig");
 const database = @import("database.zig");
 const Database = database.Database;
+const decoders = @import("decoders.zig");
+const log = @import("log.zig");
+const pairing = @import("pairing.zig");
+const proc = @import("proc.zig");
+const rejects = @import("rejects.zig");
+const sink = @import("sink.zig");
+const SyscallSink = sink.SyscallSink;
+const threads = @import("threads.zig");
 const types = @import("types.zig");
 const utils = @import("utils.zig");
 const FileStats = types.FileStats;
+const Syscall = types.Syscall;
+const ProcessOptions = types.ProcessOptions;
+
+/// Maximum line length we'll process (10MB sanity cap)
+const max_line_size: usize = 10 * 1024 * 1024;
+
+/// How long --follow sleeps when it has caught up with the end of the file
+const follow_poll_ns: u64 = 250 * std.time.ns_per_ms;
+
+/// Longest time --follow keeps rows in the sinks' buffers while the file keeps growing
+const follow_flush_ns: u64 = std.time.ns_per_s;
 
 /// Line counting statistics
 const LineStats = struct {
@@ -15,6 +34,11 @@ const LineStats = struct {
 /// Count total lines and find maximum line length in a file
 /// Returns error.LineTooLong if any line exceeds max_allowed bytes
 fn countLinesAndMaxLength(file_path: []const u8, max_allowed: usize) !LineStats {
+    return countRangeLinesAndMaxLength(file_path, max_allowed, null);
+}
+
+/// countLinesAndMaxLength restricted to one byte range (the whole file when null)
+fn countRangeLinesAndMaxLength(file_path: []const u8, max_allowed: usize, range: ?ByteRange) !LineStats {
     var stats = LineStats{ .total_lines = 0, .max_line_length = 0 };
 
     const file = try std.fs.cwd().openFile(file_path, .{});
@@ -22,8 +46,12 @@ fn countLinesAndMaxLength(file_path: []const u8, max_allowed: usize) !LineStats
 
     var count_buffer: [8192]u8 = undefined;
     var count_reader = file.reader(&count_buffer);
+    if (range) |r| try count_reader.seekTo(r.start);
 
     while (true) {
+        if (range) |r| {
+            if (count_reader.logicalPos() >= r.end) break;
+        }
         const bytes_discarded = count_reader.interface.discardDelimiterInclusive('\n') catch break;
         stats.total_lines += 1;
         stats.max_line_length = @max(stats.max_line_length, bytes_discarded);
@@ -42,25 +70,421 @@ fn countLinesAndMaxLength(file_path: []const u8, max_allowed: usize) !LineStats
     return stats;
 }
 
-/// Process a single strace trace file
+/// Line-aligned slice of a plain trace file, processed on its own (--split-large-files)
+pub const ByteRange = struct {
+    start: u64,
+    /// One past the last byte: just after a newline, or the end of the file
+    end: u64,
+    /// Lines before start, so line_number stays relative to the whole file
+    lines_before: usize,
+    /// Threads linked to their process by CLONE_THREAD clones before start, so rows
+    /// keep the file's PID; owned by the range (see freeRanges)
+    thread_links: []const threads.Link = &.{},
+};
+
+/// Cut a plain file into line-aligned ranges of at least chunk_size bytes (the last may be shorter)
+/// Reads the file once to find each cut, count the lines before it and follow the clones
+/// that create threads (only lines mentioning clone are parsed)
+/// Caller owns the returned slice and must free it with freeRanges
+pub fn planRanges(allocator: std.mem.Allocator, file_path: []const u8, chunk_size: u64) ![]ByteRange {
+    const file = try std.fs.cwd().openFile(file_path, .{});
+    defer file.close();
+    const file_size = (try file.stat()).size;
+    const pid = utils.extractPidFromFilename(std.fs.path.basename(file_path)) orelse 0;
+
+    var ranges = std.ArrayListUnmanaged(ByteRange){};
+    errdefer ranges.deinit(allocator);
+    errdefer for (ranges.items) |range| allocator.free(range.thread_links);
+
+    var groups = threads.ThreadGroups.init(allocator);
+    defer groups.deinit();
+
+    var scan_buffer: [64 * 1024]u8 = undefined;
+    var scan_reader = file.reader(&scan_buffer);
+
+    var start: u64 = 0;
+    var lines_before: usize = 0;
+    // Links taken at a cut, owned here until the range starting there is appended
+    var thread_links: []const threads.Link = &.{};
+    defer allocator.free(thread_links);
+    var pos: u64 = 0;
+    var lines: usize = 0;
+    while (true) {
+        // A line too long for the scan buffer is never a clone
+        if (scan_reader.interface.peekDelimiterInclusive('\n')) |line| {
+            if (std.mem.indexOf(u8, line, "clone") != null) {
+                if (parser.parseLine(allocator, line) catch null) |parsed| try groups.observe(parsed.pid orelse pid, parsed);
+            }
+        } else |err| switch (err) {
+            error.EndOfStream, error.StreamTooLong => {},
+            else => |e| return e,
+        }
+        const line_length = scan_reader.interface.discardDelimiterInclusive('\n') catch |err| switch (err) {
+            error.EndOfStream => break,
+            else => |e| return e,
+        };
+        pos += line_length;
+        lines += 1;
+        if (pos - start >= chunk_size) {
+            try ranges.append(allocator, .{ .start = start, .end = pos, .lines_before = lines_before, .thread_links = thread_links });
+            start = pos;
+            lines_before = lines;
+            thread_links = &.{};
+            thread_links = try groups.links(allocator);
+        }
+    }
+    // Whatever follows the last cut, including a final line without a newline
+    if (file_size > start or ranges.items.len == 0) {
+        try ranges.append(allocator, .{ .start = start, .end = file_size, .lines_before = lines_before, .thread_links = thread_links });
+        thread_links = &.{};
+    }
+    return ranges.toOwnedSlice(allocator);
+}
+
+/// Free ranges from planRanges along with their thread links
+pub fn freeRanges(allocator: std.mem.Allocator, ranges: []const ByteRange) void {
+    for (ranges) |range| allocator.free(range.thread_links);
+    allocator.free(ranges);
+}
+
+/// Trace path that reads from standard input
+pub const stdin_path = "-";
+
+/// trace_file value for rows read from standard input
+pub const stdin_trace_file = "stdin";
+
+/// trace_file value stored for a trace path, matching the syscalls rows
+pub fn traceFileName(file_path: []const u8) []const u8 {
+    return if (std.mem.eql(u8, file_path, stdin_path)) stdin_trace_file else std.fs.path.basename(file_path);
+}
+
+/// Per-file state for turning parsed lines into rows on every sink
+const RowEmitter = struct {
+    sinks: []const SyscallSink,
+    filename: []const u8,
+    pid: i32,
+    command: ?[]const u8,
+    options: ProcessOptions,
+    file_index: usize,
+    allocator: std.mem.Allocator,
+    // Scratch space for --normalize-hex-addrs, grown to the longest args seen
+    args_buffer: *std.ArrayListUnmanaged(u8),
+    // Scratch space for --args-json, reused for every row
+    json_buffer: *std.ArrayListUnmanaged(u8),
+    // Day tracking for --date; null when no base date is given
+    clock: ?*WallClock,
+    // Thread-to-process mapping learned from CLONE_THREAD clones in this trace
+    threads: *threads.ThreadGroups,
+
+    /// --min-duration: only rows with a duration of at least the threshold are kept
+    /// Unfinished halves have no duration, but --reassemble pairs them before they get here
+    fn fastEnough(self: *const RowEmitter, syscall: Syscall) bool {
+        const min = self.options.min_duration orelse return true;
+        const duration = syscall.duration orelse return false;
+        return duration >= min;
+    }
+
+    /// Decode one row and fan it out to every sink
+    /// A row counts as parsed only if every sink accepted it
+    fn emit(self: *const RowEmitter, parsed: Syscall, stats: *FileStats) !void {
+        // A per-line PID prefix (strace -f) takes precedence over the filename; it is really a TID
+        const row_tid = parsed.pid orelse self.pid;
+        // Clones are observed even when the filter drops their row, so later rows still map
+        try self.threads.observe(row_tid, parsed);
+
+        // Filtered rows never reach a sink and are counted apart from failed lines
+        if (!self.options.row_filter.accepts(parsed) or !self.options.syscall_filter.accepts(parsed) or !self.fastEnough(parsed)) {
+            stats.filtered_lines += 1;
+            return;
+        }
+
+        var syscall = parsed;
+
+        const row_pid = self.threads.leaderOf(row_tid);
+        syscall.tid = row_tid;
+        syscall.command = self.command;
+        if (row_pid != self.pid) {
+            if (self.options.comm_lookup) |comms| syscall.command = try comms.lookup(row_pid);
+        }
+        if (syscall.line_number) |line| syscall.seq = (@as(i64, @intCast(self.file_index)) << 32) | line;
+        if (self.options.partition_by_hour) syscall.hour = utils.timestampHour(syscall.timestamp);
+        // Epoch timestamps already carry their date
+        if (self.clock) |clock| {
+            if (syscall.wall_time_us == null) {
+                if (syscall.time_of_day_us) |time_of_day| syscall.wall_time_us = clock.resolve(time_of_day);
+            }
+        }
+        decoders.apply(&syscall, self.options);
+        // The parser reads it for every timed row; the column is only filled on request
+        if (!self.options.duration_ns) syscall.duration_ns = null;
+
+        // Decoders above still see the original addresses
+        if (self.options.normalize_hex_addrs) {
+            // Normalized args are never longer than the original
+            try self.args_buffer.resize(self.allocator, syscall.args.len);
+            if (utils.normalizeHexAddrs(syscall.args, self.args_buffer.items)) |normalized| {
+                syscall.args_raw = syscall.args;
+                syscall.args = normalized;
+            }
+        }
+
+        // Cut after the decoders and normalization read the whole list; args_json follows the stored args
+        if (self.options.max_args_len) |max_len| {
+            if (syscall.args.len > max_len or (syscall.args_raw != null and syscall.args_raw.?.len > max_len)) {
+                syscall.args = utils.truncateUtf8(syscall.args, max_len);
+                if (syscall.args_raw) |raw| syscall.args_raw = utils.truncateUtf8(raw, max_len);
+                syscall.args_truncated = true;
+            }
+        }
+
+        // Split halves hold only part of the argument list (see arg_count)
+        if (self.options.args_json and syscall.kind == .syscall and !syscall.unfinished and !syscall.resumed) {
+            self.json_buffer.clearRetainingCapacity();
+            var json = std.Io.Writer.Allocating.fromArrayList(self.allocator, self.json_buffer);
+            defer self.json_buffer.* = json.toArrayList();
+            try parser.writeArgsJson(&json.writer, syscall.args);
+            syscall.args_json = json.written();
+        }
+
+        // Successfully parsed - fan out to every sink (database uses fast appender API)
+        var append_failed = false;
+        for (self.sinks) |output| {
+            output.append(self.filename, row_pid, syscall) catch |err| {
+                append_failed = true;
+                std.debug.print("Append error ({s} sink) on line {}: {}\n", .{ output.name, stats.total_lines, err });
+            };
+        }
+        if (append_failed) {
+            stats.failed_lines += 1;
+            return;
+        }
+        stats.parsed_lines += 1;
+    }
+};
+
+/// Turns times of day into full timestamps for --date
+/// A time more than 12 hours earlier than the previous row means the trace crossed midnight
+const WallClock = struct {
+    midnight_us: i64,
+    last_time_us: ?i64 = null,
+
+    fn resolve(self: *WallClock, time_of_day_us: i64) i64 {
+        if (self.last_time_us) |last| {
+            if (last - time_of_day_us > 12 * std.time.us_per_hour) self.midnight_us += std.time.us_per_day;
+        }
+        self.last_time_us = time_of_day_us;
+        return self.midnight_us + time_of_day_us;
+    }
+};
+
+/// Route one parsed line through the pairer (--reassemble)
+/// Unfinished calls are held back until their resumed line arrives and are then
+/// stored as one reassembled row; with keep_fragments the original rows are kept too
+fn reassembleLine(
+    allocator: std.mem.Allocator,
+    pairer: *pairing.Pairer,
+    emitter: *const RowEmitter,
+    parsed: Syscall,
+    stats: *FileStats,
+) !void {
+    const row_pid = parsed.pid orelse emitter.pid;
+
+    if (parsed.unfinished and !pairing.isUnpaired(parsed.syscall)) {
+        try pairer.open(row_pid, parsed);
+        if (emitter.options.keep_fragments) try emitter.emit(parsed, stats);
+        return;
+    }
+
+    if (parsed.resumed) {
+        if (pairer.close(row_pid, parsed)) |pending| {
+            defer pending.deinit(allocator);
+
+            // The unfinished line ends where the resumed line picks up, e.g. "3, " ++ "\"data\", 100"
+            const args = try std.mem.concat(allocator, u8, &.{ pending.row.args, parsed.args });
+            defer allocator.free(args);
+
+            var combined = parsed;
+            combined.timestamp = pending.row.timestamp;
+            combined.line_number = pending.row.line_number;
+            combined.rel_time = pending.row.rel_time;
+            // Time fields follow the timestamp; the resumed line may not even have one
+            combined.wall_time_us = parser.parseEpochMicros(pending.row.timestamp);
+            combined.time_of_day_us = parser.parseTimeOfDay(pending.row.timestamp) orelse
+                if (combined.wall_time_us) |epoch_us| @mod(epoch_us, std.time.us_per_day) else null;
+            combined.args = args;
+            combined.truncated = parser.hasTruncatedString(args);
+            combined.arg_count = parser.argCount(args);
+            combined.resumed = false;
+            combined.reassembled = true;
+
+            if (emitter.options.keep_fragments) try emitter.emit(parsed, stats);
+            try emitter.emit(combined, stats);
+            return;
+        }
+    }
+
+    try emitter.emit(parsed, stats);
+}
+
+/// Process a single strace trace file with default options
 /// Returns statistics about the processing
 pub fn processFile(
     allocator: std.mem.Allocator,
     db: *Database,
     file_path: []const u8,
+) !FileStats {
+    return processFileWithOptions(allocator, db, file_path, .{});
+}
+
+/// Process a single strace trace file into a database, decoding lines according to options
+/// Returns statistics about the processing
+pub fn processFileWithOptions(
+    allocator: std.mem.Allocator,
+    db: *Database,
+    file_path: []const u8,
+    options: ProcessOptions,
+) !FileStats {
+    const sinks = [_]SyscallSink{sink.databaseSink(db)};
+    return processFileToSinks(allocator, &sinks, file_path, options);
+}
+
+/// Every row of one trace file, kept in memory instead of a database
+pub const ParsedFile = struct {
+    arena: std.heap.ArenaAllocator,
+    /// Rows in file order, with pid set to the effective row PID; owned by the arena
+    rows: []const Syscall,
+    stats: FileStats,
+
+    pub fn deinit(self: *ParsedFile) void {
+        self.arena.deinit();
+    }
+};
+
+/// Process a single strace trace file into memory rather than a database
+/// Rows go through the same per-row work as processFileWithOptions (filters, decoders, --reassemble)
+pub fn parseFileToList(allocator: std.mem.Allocator, file_path: []const u8, options: ProcessOptions) !ParsedFile {
+    var parsed = ParsedFile{ .arena = std.heap.ArenaAllocator.init(allocator), .rows = &.{}, .stats = undefined };
+    errdefer parsed.deinit();
+
+    var collected = sink.CollectingSink.init(parsed.arena.allocator());
+    const sinks = [_]SyscallSink{collected.sink()};
+    parsed.stats = try processFileToSinks(allocator, &sinks, file_path, options);
+    parsed.rows = collected.rows.items;
+    return parsed;
+}
+
+/// Process a single strace trace file, appending every parsed syscall to each sink
+/// A row counts as parsed only if every sink accepted it
+/// Returns statistics about the processing
+pub fn processFileToSinks(
+    allocator: std.mem.Allocator,
+    sinks: []const SyscallSink,
+    file_path: []const u8,
+    options: ProcessOptions,
+) !FileStats {
+    return processFileRangeToSinks(allocator, sinks, file_path, 0, null, options);
+}
+
+/// Like processFileToSinks, but only the lines of one range of a plain file (whole file when null)
+/// Rows keep the file's PID and trace_file and are numbered from the start of the file
+/// file_index is the file's position in the input list, the high half of seq
+pub fn processFileRangeToSinks(
+    allocator: std.mem.Allocator,
+    sinks: []const SyscallSink,
+    file_path: []const u8,
+    file_index: usize,
+    range: ?ByteRange,
+    options: ProcessOptions,
 ) !FileStats {
     var stats = FileStats.init();
 
-    // Extract PID from filename
+    // Piped input is streamed line by line; there is no filename to take a PID from
+    if (std.mem.eql(u8, file_path, stdin_path)) {
+        var stdin_buffer: [8192]u8 = undefined;
+        var stdin_reader = std.fs.File.stdin().reader(&stdin_buffer);
+
+        var lines = StreamedLines.init(allocator, &stdin_reader.interface);
+        defer lines.deinit();
+        try processLines(allocator, &lines, .{
+            .sinks = sinks,
+            .filename = stdin_trace_file,
+            .pid = 0,
+            .command = null,
+            .options = options,
+            .file_index = file_index,
+        }, &stats);
+        return stats;
+    }
+
+    // Size guard runs before either pass so runaway logs cost only a stat call
+    if (options.max_file_size) |limit| {
+        const file_size = (try std.fs.cwd().statFile(file_path)).size;
+        if (file_size > limit) {
+            std.debug.print("Warning: Skipping {s}: {} bytes exceeds --max-file-size of {} bytes\n", .{ file_path, file_size, limit });
+            stats.skipped = true;
+            return stats;
+        }
+    }
+
+    // Extract PID from filename (trace.1234.gz and trace.1234.zst carry the same PID as trace.1234)
     const filename = std.fs.path.basename(file_path);
-    const pid = utils.extractPidFromFilename(filename) orelse 0; // Default to 0 if no PID found
+    const pid = utils.extractPidFromFilename(utils.stripCompressionSuffix(filename)) orelse 0; // Default to 0 if no PID found
+
+    // Command name for this file's PID, read lazily from the /proc snapshot
+    const command = if (options.comm_lookup) |comms| try comms.lookup(pid) else null;
+
+    var source = Source{
+        .sinks = sinks,
+        .filename = filename,
+        .pid = pid,
+        .command = command,
+        .options = options,
+        .file_index = file_index,
+        .lines_before = if (range) |r| r.lines_before else 0,
+        .thread_links = if (range) |r| r.thread_links else &.{},
+    };
 
-    // Maximum line length we'll process (10MB sanity cap)
-    const MAX_LINE_SIZE: usize = 10 * 1024 * 1024;
+    // NOTE: Caller must call db.beginAppend() before calling this function
+    // and db.endAppend() after processing all files (database sinks)
+
+    // Compressed traces can't be scanned twice cheaply, so they are inflated
+    // once and streamed line by line
+    const compression: Compression = if (range == null) try detectCompression(file_path) else .none;
+    if (compression != .none) {
+        const file = try std.fs.cwd().openFile(file_path, .{});
+        defer file.close();
+
+        var file_buffer: [8192]u8 = undefined;
+        var file_reader = file.reader(&file_buffer);
+
+        switch (compression) {
+            .gzip => {
+                const window = try allocator.alloc(u8, std.compress.flate.max_window_len);
+                defer allocator.free(window);
+                var decompress = std.compress.flate.Decompress.init(&file_reader.interface, .gzip, window);
+
+                var lines = StreamedLines.init(allocator, &decompress.reader);
+                defer lines.deinit();
+                try processLines(allocator, &lines, source, &stats);
+            },
+            .zstd => {
+                // Big enough for any frame using the default window, so memory stays bounded
+                const window = try allocator.alloc(u8, std.compress.zstd.default_window_len + std.compress.zstd.block_size_max);
+                defer allocator.free(window);
+                var decompress = std.compress.zstd.Decompress.init(&file_reader.interface, window, .{});
+
+                var lines = StreamedLines.init(allocator, &decompress.reader);
+                defer lines.deinit();
+                try processLines(allocator, &lines, source, &stats);
+            },
+            .none => unreachable,
+        }
+        return stats;
+    }
 
     // First pass: count total lines and find maximum line length
     // Fails fast with error.LineTooLong if any line > 10MB
-    const line_stats = try countLinesAndMaxLength(file_path, MAX_LINE_SIZE);
+    const line_stats = try countRangeLinesAndMaxLength(file_path, max_line_size, range);
 
     // Allocate buffer based on actual maximum line length
     // Use at least 4KB to avoid tiny allocations for empty/small files
@@ -68,47 +492,565 @@ pub fn processFile(
     const line_buffer = try allocator.alloc(u8, buffer_size);
     defer allocator.free(line_buffer);
 
-    // NOTE: Caller must call db.beginAppend() before calling this function
-    // and db.endAppend() after processing all files
-
     // Second pass: process file
     // Buffer is sized to max line length, so takeDelimiter should never fail with StreamTooLong
     const file = try std.fs.cwd().openFile(file_path, .{});
     defer file.close();
+    source.count_bytes = true;
 
     var reader = file.reader(line_buffer);
+    if (range) |r| {
+        try reader.seekTo(r.start);
+        var lines = RangeLines{ .file_reader = &reader, .end = r.end };
+        try processLines(allocator, &lines, source, &stats);
+        return stats;
+    }
+    var lines = BufferedLines{ .reader = &reader.interface };
+    try processLines(allocator, &lines, source, &stats);
+    return stats;
+}
+
+/// Process a plain trace file that is still being written, like `tail -f` (--follow)
+/// Reads from the start, then polls for appended lines until stop is set; sinks are
+/// flushed whenever the reader catches up and at least once per follow_flush_ns
+/// A trailing line without its newline is held back until the newline arrives,
+/// and is dropped if stop is set first
+pub fn followFileToSinks(
+    allocator: std.mem.Allocator,
+    sinks: []const SyscallSink,
+    file_path: []const u8,
+    options: ProcessOptions,
+    stop: *const std.atomic.Value(bool),
+) !FileStats {
+    var stats = FileStats.init();
+
+    const filename = std.fs.path.basename(file_path);
+    const pid = utils.extractPidFromFilename(filename) orelse 0;
+    const command = if (options.comm_lookup) |comms| try comms.lookup(pid) else null;
+
+    const file = try std.fs.cwd().openFile(file_path, .{});
+    defer file.close();
+
+    var lines = FollowLines{
+        .file = file,
+        .allocator = allocator,
+        .sinks = sinks,
+        .stop = stop,
+        .flush_timer = try std.time.Timer.start(),
+    };
+    defer lines.deinit();
+    try processLines(allocator, &lines, .{
+        .sinks = sinks,
+        .filename = filename,
+        .pid = pid,
+        .command = command,
+        .options = options,
+    }, &stats);
+    return stats;
+}
+
+/// Where the rows of one trace go and how they are attributed
+const Source = struct {
+    sinks: []const SyscallSink,
+    filename: []const u8,
+    pid: i32,
+    command: ?[]const u8,
+    options: ProcessOptions,
+    /// Position of the trace in the input list, for seq
+    file_index: usize = 0,
+    /// Lines of the file before the first line read (nonzero for later split ranges)
+    lines_before: usize = 0,
+    /// Threads already linked to their process before the first line read
+    thread_links: []const threads.Link = &.{},
+    /// Add line lengths to options.bytes_read; only lines of plain files match bytes on disk
+    count_bytes: bool = false,
+};
+
+/// Lines' bytes are added to options.bytes_read in batches of at least this size
+const bytes_read_batch: u64 = 1024 * 1024;
+
+/// Lines from a reader whose buffer already holds the longest line (plain files)
+const BufferedLines = struct {
+    reader: *std.Io.Reader,
+
+    fn next(self: *BufferedLines) !?[]u8 {
+        return self.reader.takeDelimiter('\n') catch |err| {
+            // Should not happen - buffer is sized correctly
+            std.debug.print("Unexpected read error: {}\n", .{err});
+            return err;
+        };
+    }
+};
+
+/// BufferedLines that stop at the end of a byte range
+/// Ranges end on a line boundary, so no line is cut in two
+const RangeLines = struct {
+    file_reader: *std.fs.File.Reader,
+    end: u64,
+
+    fn next(self: *RangeLines) !?[]u8 {
+        if (self.file_reader.logicalPos() >= self.end) return null;
+        var lines = BufferedLines{ .reader = &self.file_reader.interface };
+        return lines.next();
+    }
+};
+
+/// Lines from a reader that can only be read once (gzip, zstd, stdin)
+/// Each line is copied into a growable buffer, so only one line is held in memory at a time
+const StreamedLines = struct {
+    reader: *std.Io.Reader,
+    line: std.Io.Writer.Allocating,
+    line_number: usize = 0,
+    done: bool = false,
+
+    fn init(allocator: std.mem.Allocator, reader: *std.Io.Reader) StreamedLines {
+        return .{ .reader = reader, .line = std.Io.Writer.Allocating.init(allocator) };
+    }
+
+    fn deinit(self: *StreamedLines) void {
+        self.line.deinit();
+    }
+
+    fn next(self: *StreamedLines) !?[]u8 {
+        if (self.done) return null;
+        self.line.clearRetainingCapacity();
+        self.line_number += 1;
+
+        _ = self.reader.streamDelimiterLimit(&self.line.writer, '\n', .limited(max_line_size)) catch |err| switch (err) {
+            error.StreamTooLong => {
+                std.debug.print("Error: Line {} exceeds maximum allowed size of {} bytes\n", .{ self.line_number, max_line_size });
+                return error.LineTooLong;
+            },
+            else => |e| return e,
+        };
+
+        // The delimiter is left in the stream; its absence means end of input
+        _ = self.reader.takeByte() catch |err| switch (err) {
+            error.EndOfStream => {
+                self.done = true;
+                if (self.line.written().len == 0) return null;
+            },
+            else => |e| return e,
+        };
+        return self.line.written();
+    }
+};
+
+/// Lines from a file that another process is still appending to (--follow)
+/// End of file means "no more yet": the sinks are flushed and the file is polled again
+const FollowLines = struct {
+    file: std.fs.File,
+    allocator: std.mem.Allocator,
+    sinks: []const SyscallSink,
+    stop: *const std.atomic.Value(bool),
+    flush_timer: std.time.Timer,
+    /// Bytes read but not yet returned; starts with a partial line waiting for its newline
+    buffer: std.ArrayListUnmanaged(u8) = .{},
+    start: usize = 0,
+
+    const read_size = 64 * 1024;
+
+    fn deinit(self: *FollowLines) void {
+        self.buffer.deinit(self.allocator);
+    }
+
+    fn next(self: *FollowLines) !?[]u8 {
+        while (true) {
+            if (std.mem.indexOfScalarPos(u8, self.buffer.items, self.start, '\n')) |newline| {
+                const line = self.buffer.items[self.start..newline];
+                self.start = newline + 1;
+                return line;
+            }
+
+            // Keep only the partial line, then read more after it
+            const partial = self.buffer.items.len - self.start;
+            std.mem.copyForwards(u8, self.buffer.items[0..partial], self.buffer.items[self.start..]);
+            self.buffer.shrinkRetainingCapacity(partial);
+            self.start = 0;
+            if (partial > max_line_size) {
+                std.debug.print("Error: Line exceeds maximum allowed size of {} bytes\n", .{max_line_size});
+                return error.LineTooLong;
+            }
+
+            try self.buffer.ensureUnusedCapacity(self.allocator, read_size);
+            const read = try self.file.read(self.buffer.unusedCapacitySlice());
+            self.buffer.items.len += read;
+            if (read > 0) {
+                if (self.flush_timer.read() >= follow_flush_ns) try self.flush();
+                continue;
+            }
+
+            // Caught up with the writer: make the rows so far visible, then wait for more
+            try self.flush();
+            if (self.stop.load(.seq_cst)) return null;
+            std.Thread.sleep(follow_poll_ns);
+        }
+    }
+
+    fn flush(self: *FollowLines) !void {
+        for (self.sinks) |output| try output.flush();
+        self.flush_timer.reset();
+    }
+};
 
-    while (reader.interface.takeDelimiter('\n') catch |err| {
-        // Should not happen - buffer is sized correctly
-        std.debug.print("Unexpected read error: {}\n", .{err});
-        return err;
-    }) |line| {
+/// Unmatched lines shown per file with --verbose
+const max_unmatched_samples = 5;
+
+/// Turns one raw line into a parsed row: UTF-8 repair, --keep-raw, --strip-ansi, then the parser
+/// Failed and unmatched lines are counted (and sent to --rejects) here, so callers only see rows
+/// A row points into the line and the parser's buffers, so it is valid until the next line
+const LineParser = struct {
+    allocator: std.mem.Allocator,
+    options: ProcessOptions,
+    filename: []const u8,
+    lines_before: usize = 0,
+    // Copy of the unmodified line for --keep-raw when --strip-ansi rewrites it in place
+    raw_buffer: std.ArrayListUnmanaged(u8) = .{},
+    // Repaired copy of a line holding invalid UTF-8
+    utf8_buffer: std.ArrayListUnmanaged(u8) = .{},
+    // Unmatched lines printed so far under --verbose
+    samples_shown: usize = 0,
+
+    fn deinit(self: *LineParser) void {
+        self.raw_buffer.deinit(self.allocator);
+        self.utf8_buffer.deinit(self.allocator);
+    }
+
+    fn parse(self: *LineParser, delimited_line: []u8, stats: *FileStats) !?Syscall {
+        const options = self.options;
         stats.total_lines += 1;
+        const line_number = self.lines_before + stats.total_lines;
+
+        // Lines are split on '\n', so a CRLF file (copied through Windows tools) leaves a '\r'
+        // that would otherwise end up in raw_line and the rejects file
+        const read_line = if (std.mem.endsWith(u8, delimited_line, "\r")) delimited_line[0 .. delimited_line.len - 1] else delimited_line;
+
+        // strace usually escapes non-printable bytes, but raw ones slip through in some
+        // string args; DuckDB rejects such VARCHARs, so replace them instead of failing the row
+        // Validation is a fast scan for the common all-ASCII line
+        const line = if (std.unicode.utf8ValidateSlice(read_line)) read_line else blk: {
+            stats.lossy_lines += 1;
+            break :blk try utils.replaceInvalidUtf8(self.allocator, read_line, &self.utf8_buffer);
+        };
+
+        const raw_line: ?[]const u8 = if (!options.keep_raw) null else if (options.strip_ansi) blk: {
+            self.raw_buffer.clearRetainingCapacity();
+            try self.raw_buffer.appendSlice(self.allocator, line);
+            break :blk self.raw_buffer.items;
+        } else line;
+
+        // Colorized captures carry escape codes that would corrupt parsing
+        const input = if (options.strip_ansi) utils.stripAnsi(line) else line;
 
         // Parse the line
-        const maybe_syscall = parser.parseLine(allocator, line) catch |err| {
+        const maybe_syscall = parser.parseLineAs(self.allocator, input, options.time_format) catch |err| {
             // Parsing error - count as failed
             stats.failed_lines += 1;
-            std.debug.print("Parse error on line {}: {}\n", .{ stats.total_lines, err });
-            continue;
+            std.debug.print("Parse error on line {}: {}\n", .{ line_number, err });
+            if (options.rejects) |rejected| try rejected.write(self.filename, line_number, input);
+            return null;
         };
 
-        if (maybe_syscall) |syscall| {
-            // Successfully parsed - append to database using fast appender API
-            db.appendSyscall(filename, pid, syscall) catch |err| {
-                // Database append error
-                stats.failed_lines += 1;
-                std.debug.print("Append error on line {}: {}\n", .{ stats.total_lines, err });
-                continue;
-            };
-            stats.parsed_lines += 1;
-        } else {
+        var parsed = maybe_syscall orelse {
             // Line didn't match any pattern (comment, empty, etc.)
             // Don't count as failed - these are expected
+            if (std.mem.trim(u8, input, " \t\r").len > 0) {
+                if (options.rejects) |rejected| try rejected.write(self.filename, line_number, input);
+                if (log.enabled(.verbose) and self.samples_shown < max_unmatched_samples) {
+                    self.samples_shown += 1;
+                    log.verbose("{s}:{d}: no syscall found: {s}\n", .{ self.filename, line_number, input[0..@min(input.len, 200)] });
+                }
+            }
+            return null;
+        };
+        parsed.raw_line = raw_line;
+        // Every line advances the count, so numbers match the source file (1-based)
+        parsed.line_number = std.math.cast(i32, line_number);
+        return parsed;
+    }
+};
+
+/// Rows of one trace, without a database or any sinks (for library use)
+/// Plain, gzip and zstd files (and "-" for stdin) are streamed one line at a time through
+/// the same per-row work as processFile (filters, decoders, --reassemble, thread mapping,
+/// --normalize-hex-addrs, --max-args-len, ...); like parseFileToList, each row's pid is
+/// the effective row PID. --max-file-size and --split-large-files do not apply.
+/// A row's slices are only valid until the next call to next()
+pub const TraceIterator = struct {
+    allocator: std.mem.Allocator,
+    file: std.fs.File,
+    /// False for stdin, which is never closed
+    owns_file: bool,
+    /// PID from the file name (0 for stdin); rows with a `[pid N]` prefix carry their own
+    pid: i32,
+    /// trace_file value processFile stores for these rows
+    trace_file: []const u8,
+    /// Line counts so far; parsed_lines counts rows returned or about to be
+    stats: FileStats,
+    file_buffer: [8192]u8,
+    file_reader: std.fs.File.Reader,
+    decompress: union(Compression) {
+        none: void,
+        gzip: std.compress.flate.Decompress,
+        zstd: std.compress.zstd.Decompress,
+    },
+    window: []u8,
+    lines: StreamedLines,
+    pipeline: RowPipeline,
+    // Rows the pipeline emitted for the last line (two with --keep-fragments), copied
+    // into an arena that is reset before the next line is read
+    row_arena: std.heap.ArenaAllocator,
+    rows: sink.CollectingSink,
+    row_sinks: [1]SyscallSink,
+    next_row: usize = 0,
+    finished: bool = false,
+
+    /// Open a trace for reading
+    /// Heap-allocated because the line reader and the pipeline point into the iterator's own buffers
+    pub fn open(allocator: std.mem.Allocator, file_path: []const u8, options: ProcessOptions) !*TraceIterator {
+        const self = try allocator.create(TraceIterator);
+        errdefer allocator.destroy(self);
+
+        const from_stdin = std.mem.eql(u8, file_path, stdin_path);
+        const compression: Compression = if (from_stdin) .none else try detectCompression(file_path);
+        const file = if (from_stdin) std.fs.File.stdin() else try std.fs.cwd().openFile(file_path, .{});
+        errdefer if (!from_stdin) file.close();
+
+        const filename = traceFileName(file_path);
+        const pid = if (from_stdin) 0 else utils.extractPidFromFilename(utils.stripCompressionSuffix(filename)) orelse 0;
+        const command = if (options.comm_lookup) |comms| try comms.lookup(pid) else null;
+        self.* = .{
+            .allocator = allocator,
+            .file = file,
+            .owns_file = !from_stdin,
+            .pid = pid,
+            .trace_file = filename,
+            .stats = FileStats.init(),
+            .file_buffer = undefined,
+            .file_reader = undefined,
+            .decompress = .none,
+            .window = &.{},
+            .lines = undefined,
+            .pipeline = undefined,
+            .row_arena = std.heap.ArenaAllocator.init(allocator),
+            .rows = undefined,
+            .row_sinks = undefined,
+        };
+        errdefer self.row_arena.deinit();
+        self.file_reader = file.reader(&self.file_buffer);
+
+        const reader: *std.Io.Reader = switch (compression) {
+            .none => &self.file_reader.interface,
+            .gzip => blk: {
+                self.window = try allocator.alloc(u8, std.compress.flate.max_window_len);
+                self.decompress = .{ .gzip = std.compress.flate.Decompress.init(&self.file_reader.interface, .gzip, self.window) };
+                break :blk &self.decompress.gzip.reader;
+            },
+            .zstd => blk: {
+                // Same bounded window as processFile
+                self.window = try allocator.alloc(u8, std.compress.zstd.default_window_len + std.compress.zstd.block_size_max);
+                self.decompress = .{ .zstd = std.compress.zstd.Decompress.init(&self.file_reader.interface, self.window, .{}) };
+                break :blk &self.decompress.zstd.reader;
+            },
+        };
+        self.lines = StreamedLines.init(allocator, reader);
+
+        self.rows = sink.CollectingSink.init(self.row_arena.allocator());
+        self.row_sinks = .{self.rows.sink()};
+        self.pipeline.init(allocator, .{
+            .sinks = &self.row_sinks,
+            .filename = filename,
+            .pid = pid,
+            .command = command,
+            .options = options,
+        });
+        return self;
+    }
+
+    /// Close the file (stdin is left open) and free the iterator
+    pub fn destroy(self: *TraceIterator) void {
+        self.pipeline.deinit();
+        self.row_arena.deinit();
+        self.lines.deinit();
+        self.allocator.free(self.window);
+        if (self.owns_file) self.file.close();
+        self.allocator.destroy(self);
+    }
+
+    /// Next row, or null at the end of the trace
+    pub fn next(self: *TraceIterator) !?Syscall {
+        while (self.next_row >= self.rows.rows.items.len) {
+            if (self.finished) return null;
+            _ = self.row_arena.reset(.retain_capacity);
+            self.rows.rows = .{};
+            self.next_row = 0;
+
+            if (try self.lines.next()) |line| {
+                try self.pipeline.feed(line, &self.stats);
+            } else {
+                // Held-back unfinished calls come out at the end, as processFile stores them
+                try self.pipeline.finish(&self.stats);
+                self.finished = true;
+            }
         }
+        defer self.next_row += 1;
+        return self.rows.rows.items[self.next_row];
     }
+};
 
-    return stats;
+/// Everything between a raw line and the sinks for one trace: line parsing, --reassemble,
+/// thread mapping, filters, decoders and the args rewrites (see RowEmitter)
+/// processLines pushes lines through it and TraceIterator pulls rows out of it
+/// The emitter points into the pipeline's own buffers, so it must not move after init
+const RowPipeline = struct {
+    allocator: std.mem.Allocator,
+    args_buffer: std.ArrayListUnmanaged(u8) = .{},
+    json_buffer: std.ArrayListUnmanaged(u8) = .{},
+    clock: ?WallClock,
+    thread_groups: threads.ThreadGroups,
+    // Only allocated when --reassemble pairs unfinished/resumed lines
+    pairer: ?pairing.Pairer,
+    line_parser: LineParser,
+    emitter: RowEmitter,
+
+    fn init(self: *RowPipeline, allocator: std.mem.Allocator, source: Source) void {
+        const options = source.options;
+        self.* = .{
+            .allocator = allocator,
+            // Every trace starts on the base date; rollovers are tracked per trace
+            .clock = if (options.base_date_us) |midnight| .{ .midnight_us = midnight } else null,
+            .thread_groups = threads.ThreadGroups.init(allocator),
+            .pairer = if (options.reassemble) pairing.Pairer.init(allocator) else null,
+            .line_parser = .{
+                .allocator = allocator,
+                .options = options,
+                .filename = source.filename,
+                .lines_before = source.lines_before,
+            },
+            .emitter = undefined,
+        };
+        self.emitter = .{
+            .sinks = source.sinks,
+            .filename = source.filename,
+            .pid = source.pid,
+            .command = source.command,
+            .options = options,
+            .file_index = source.file_index,
+            .allocator = allocator,
+            .args_buffer = &self.args_buffer,
+            .json_buffer = &self.json_buffer,
+            .clock = if (self.clock) |*wall| wall else null,
+            .threads = &self.thread_groups,
+        };
+    }
+
+    fn deinit(self: *RowPipeline) void {
+        self.line_parser.deinit();
+        if (self.pairer) |*calls| calls.deinit();
+        self.thread_groups.deinit();
+        self.json_buffer.deinit(self.allocator);
+        self.args_buffer.deinit(self.allocator);
+    }
+
+    /// Parse one line and emit its rows (none for a held-back unfinished call)
+    fn feed(self: *RowPipeline, line: []u8, stats: *FileStats) !void {
+        const parsed = try self.line_parser.parse(line, stats) orelse return;
+        if (self.pairer) |*calls| {
+            try reassembleLine(self.allocator, calls, &self.emitter, parsed, stats);
+        } else {
+            try self.emitter.emit(parsed, stats);
+        }
+    }
+
+    /// Emit the unfinished calls whose resumed line never came, as they were
+    /// With keep_fragments they were already emitted when they were opened
+    fn finish(self: *RowPipeline, stats: *FileStats) !void {
+        const calls = if (self.pairer) |*pairer| pairer else return;
+        var orphans = try calls.drain();
+        defer orphans.deinit(self.allocator);
+        for (orphans.items) |orphan| {
+            defer orphan.pending.deinit(self.allocator);
+            if (self.emitter.options.keep_fragments) continue;
+            try self.emitter.emit(orphan.pending.row, stats);
+        }
+    }
+};
+
+/// Parse every line and emit the rows
+/// lines is a BufferedLines, RangeLines, StreamedLines or FollowLines
+fn processLines(
+    allocator: std.mem.Allocator,
+    lines: anytype,
+    source: Source,
+    stats: *FileStats,
+) !void {
+    const options = source.options;
+
+    var pipeline: RowPipeline = undefined;
+    pipeline.init(allocator, source);
+    defer pipeline.deinit();
+    try pipeline.thread_groups.seed(source.thread_links);
+
+    // Batched so workers don't contend on the shared counter for every line
+    var unreported_bytes: u64 = 0;
+    const bytes_read = if (source.count_bytes) options.bytes_read else null;
+    defer if (bytes_read) |counter| {
+        _ = counter.fetchAdd(unreported_bytes, .seq_cst);
+    };
+
+    while (try lines.next()) |line| {
+        if (options.stopRequested()) break;
+        if (bytes_read) |counter| {
+            unreported_bytes += line.len + 1; // and its newline
+            if (unreported_bytes >= bytes_read_batch) {
+                _ = counter.fetchAdd(unreported_bytes, .seq_cst);
+                unreported_bytes = 0;
+            }
+        }
+        try pipeline.feed(line, stats);
+    }
+
+    try pipeline.finish(stats);
+}
+
+/// Compressed formats a trace can be read from
+pub const Compression = enum { none, gzip, zstd };
+
+/// gzip streams start with the magic bytes 1f 8b
+const gzip_magic = [_]u8{ 0x1f, 0x8b };
+
+/// zstd frames start with the magic number 0xFD2FB528 (little-endian)
+const zstd_magic = [_]u8{ 0x28, 0xb5, 0x2f, 0xfd };
+
+/// Compression of a file, from its .gz/.zst extension or else its leading magic bytes
+pub fn detectCompression(file_path: []const u8) !Compression {
+    if (std.mem.endsWith(u8, file_path, ".gz")) return .gzip;
+    if (std.mem.endsWith(u8, file_path, ".zst")) return .zstd;
+
+    const file = try std.fs.cwd().openFile(file_path, .{});
+    defer file.close();
+
+    var magic: [zstd_magic.len]u8 = undefined;
+    const n = try file.readAll(&magic);
+    if (n >= gzip_magic.len and std.mem.eql(u8, magic[0..gzip_magic.len], &gzip_magic)) return .gzip;
+    if (n == zstd_magic.len and std.mem.eql(u8, &magic, &zstd_magic)) return .zstd;
+    return .none;
+}
+
+/// XxHash64 of a file's bytes as stored (compressed files are not decompressed)
+/// Used by --incremental to recognise files that were already ingested
+pub fn hashFile(file_path: []const u8) !u64 {
+    const file = try std.fs.cwd().openFile(file_path, .{});
+    defer file.close();
+
+    var hasher = std.hash.XxHash64.init(0);
+    var buffer: [64 * 1024]u8 = undefined;
+    while (true) {
+        const n = try file.read(&buffer);
+        if (n == 0) break;
+        hasher.update(buffer[0..n]);
+    }
+    return hasher.final();
 }
 
 // ============================================================================
@@ -440,62 +1382,1210 @@ test "processFile counts lines correctly with mixed lengths" {
     try std.testing.expectEqual(@as(i64, 5), count);
 }
 
-test "countLinesAndMaxLength with normal file" {
+test "processFileWithOptions reports every byte of the file as read" {
+    const allocator = std.testing.allocator;
+
     const test_dir = "zig-cache/test-traces";
     try std.fs.cwd().makePath(test_dir);
 
-    const test_file = "zig-cache/test-traces/count-test.7777";
+    const test_file = "zig-cache/test-traces/bytes.1313";
     const file = try std.fs.cwd().createFile(test_file, .{});
     defer std.fs.cwd().deleteFile(test_file) catch {};
 
-    // Create file with known line lengths
-    try file.writeAll("short line\n"); // 11 bytes (10 + newline)
-    try file.writeAll("medium length line here\n"); // 24 bytes
-    try file.writeAll("x\n"); // 2 bytes
-    const long_line = "L" ** 5000;
-    try file.writeAll(long_line);
-    try file.writeAll("\n"); // 5001 bytes
+    const test_data =
+        \\10:23:45.123456 getpid() = 1234 <0.000010>
+        \\not a syscall line
+        \\10:23:45.123457 close(3) = 0 <0.000010>
+        \\
+    ;
+    try file.writeAll(test_data);
     file.close();
 
-    const stats = try countLinesAndMaxLength(test_file, 10 * 1024 * 1024);
+    var db = try Database.init(":memory:");
+    defer db.deinit();
 
-    try std.testing.expectEqual(@as(usize, 4), stats.total_lines);
-    try std.testing.expectEqual(@as(usize, 5001), stats.max_line_length);
+    var bytes_read = std.atomic.Value(u64).init(0);
+    try db.beginAppend();
+    _ = try processFileWithOptions(allocator, &db, test_file, .{ .bytes_read = &bytes_read });
+    try db.endAppend();
+
+    // Unparsed lines count too: progress tracks the file, not the rows
+    try std.testing.expectEqual(@as(u64, test_data.len), bytes_read.load(.seq_cst));
 }
 
-test "countLinesAndMaxLength fails on line exceeding limit" {
+test "processFileWithOptions extracts signal targets" {
+    const allocator = std.testing.allocator;
+
     const test_dir = "zig-cache/test-traces";
     try std.fs.cwd().makePath(test_dir);
 
-    const test_file = "zig-cache/test-traces/toolong.8888";
+    const test_file = "zig-cache/test-traces/signals.1212";
     const file = try std.fs.cwd().createFile(test_file, .{});
     defer std.fs.cwd().deleteFile(test_file) catch {};
 
-    // Create file with line exceeding 1MB limit (using smaller limit for test speed)
-    try file.writeAll("normal line\n");
-    const huge_line = "X" ** (1024 * 1024 + 100); // Just over 1MB
-    try file.writeAll(huge_line);
-    try file.writeAll("\n");
+    const test_data =
+        \\22:21:24.927000 kill(1387721, SIGTERM) = 0 <0.000010>
+        \\22:21:24.927100 tgkill(1387679, 1387680, SIGUSR1) = 0 <0.000004>
+        \\22:21:24.927200 close(3) = 0 <0.000010>
+    ;
+    try file.writeAll(test_data);
     file.close();
 
-    // Should fail with LineTooLong when limit is 1MB
-    const result = countLinesAndMaxLength(test_file, 1024 * 1024);
-    try std.testing.expectError(error.LineTooLong, result);
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const stats = try processFileWithOptions(allocator, &db, test_file, .{ .extract_signals = true });
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(usize, 3), stats.parsed_lines);
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE signal IS NOT NULL"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE target_pid = 1387680"));
 }
 
-test "countLinesAndMaxLength handles exactly at limit" {
+test "processFileWithOptions strips ANSI color codes" {
+    const allocator = std.testing.allocator;
+
     const test_dir = "zig-cache/test-traces";
     try std.fs.cwd().makePath(test_dir);
 
-    const test_file = "zig-cache/test-traces/exactlimit.9999";
+    const test_file = "zig-cache/test-traces/ansi.1313";
     const file = try std.fs.cwd().createFile(test_file, .{});
     defer std.fs.cwd().deleteFile(test_file) catch {};
 
-    // Create file with line exactly at limit (100KB for test speed)
-    const limit = 100 * 1024;
-    const exact_line = "E" ** (limit - 1); // -1 for newline
-    try file.writeAll(exact_line);
-    try file.writeAll("\n");
+    try file.writeAll("\x1b[2m10:23:45.123456\x1b[0m \x1b[1;34mopen\x1b[0m(\"/tmp/file\", O_RDONLY) = \x1b[32m3\x1b[0m <0.000042>\n");
+    try file.writeAll("10:23:45.123456 open(\"/tmp/file\", O_RDONLY) = 3 <0.000042>\n");
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const stats = try processFileWithOptions(allocator, &db, test_file, .{ .strip_ansi = true });
+    try db.endAppend();
+
+    // The colorized line parses identically to the clean one
+    try std.testing.expectEqual(@as(usize, 2), stats.parsed_lines);
+    const identical = try db.queryInt64(
+        \\SELECT COUNT(*) FROM syscalls
+        \\WHERE timestamp = '10:23:45.123456' AND syscall = 'open'
+        \\  AND args = '"/tmp/file", O_RDONLY' AND return_value = 3 AND duration = 0.000042
+    );
+    try std.testing.expectEqual(@as(i64, 2), identical);
+}
+
+test "processFileWithOptions fills command from a proc snapshot" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces-proc";
+    try std.fs.cwd().makePath(test_dir ++ "/4242");
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+    try std.fs.cwd().writeFile(.{ .sub_path = test_dir ++ "/4242/comm", .data = "worker\n" });
+
+    const known = test_dir ++ "/trace.4242";
+    const unknown = test_dir ++ "/trace.4343";
+    try std.fs.cwd().writeFile(.{ .sub_path = known, .data = "10:00:00.000001 getpid() = 4242 <0.000001>\n" });
+    try std.fs.cwd().writeFile(.{ .sub_path = unknown, .data = "10:00:00.000001 getpid() = 4343 <0.000001>\n" });
+
+    var comms = proc.CommLookup.init(allocator, test_dir);
+    defer comms.deinit();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    _ = try processFileWithOptions(allocator, &db, known, .{ .comm_lookup = &comms });
+    _ = try processFileWithOptions(allocator, &db, unknown, .{ .comm_lookup = &comms });
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE pid = 4242 AND command = 'worker'"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE pid = 4343 AND command IS NULL"));
+}
+
+test "processFileWithOptions buckets rows by hour" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/hours.2525";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try file.writeAll(
+        \\09:59:59.999999 getpid() = 2525 <0.000001>
+        \\10:00:00.000001 close(3) = 0 <0.000001>
+        \\10:30:00.000001 close(4) = 0 <0.000001>
+        \\23:15:00.000001 close(5) = 0 <0.000001>
+    );
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    _ = try processFileWithOptions(allocator, &db, test_file, .{ .partition_by_hour = true });
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE hour = 9"));
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE hour = 10"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE hour = 23"));
+    try std.testing.expectEqual(@as(i64, 0), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE hour IS NULL"));
+}
+
+test "processFileWithOptions normalizes hex addresses and keeps the original" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/aslr.2626";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try file.writeAll(
+        \\10:00:00.000001 munmap(0x7f3a1c000000, 8192) = 0 <0.000004>
+        \\10:00:00.000002 munmap(0x7f8842000000, 8192) = 0 <0.000004>
+        \\10:00:00.000003 close(3) = 0 <0.000001>
+    );
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    _ = try processFileWithOptions(allocator, &db, test_file, .{ .normalize_hex_addrs = true });
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(DISTINCT args) FROM syscalls WHERE syscall = 'munmap'"));
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE args = '0x<ptr>, 8192'"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE args_raw = '0x7f3a1c000000, 8192'"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE args_raw IS NULL"));
+}
+
+test "processFile uses the per-line PID prefix over the filename" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/follow.2727";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try file.writeAll(
+        \\1387679 22:21:11.524449 brk(NULL) = 0x55edad95f000 <0.000004>
+        \\1387680 22:21:11.524500 getpid() = 1387679 <0.000002>
+        \\22:21:11.524600 close(3) = 0 <0.000001>
+    );
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const stats = try processFile(allocator, &db, test_file);
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(usize, 3), stats.parsed_lines);
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE pid = 1387679 AND syscall = 'brk'"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE pid = 1387680"));
+    // No prefix: falls back to the filename PID
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE pid = 2727 AND syscall = 'close'"));
+}
+
+test "processFile uses the bracketed PID prefix over the filename" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/bracketed.2828";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try file.writeAll(
+        \\[pid  1387679] 22:21:11.524449 openat(AT_FDCWD, "/etc/hosts", O_RDONLY) = 3 <0.000010>
+        \\[pid 42] 22:21:11.524500 close(3) = 0 <0.000001>
+    );
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    _ = try processFile(allocator, &db, test_file);
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE pid = 1387679 AND syscall = 'openat'"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE pid = 42 AND syscall = 'close'"));
+    try std.testing.expectEqual(@as(i64, 0), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE pid = 2828"));
+}
+
+test "processFile stores signal deliveries with their kind" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/signals.2929";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try file.writeAll(
+        \\22:21:24.927000 wait4(-1, <unfinished ...>
+        \\22:21:24.927885 --- SIGCHLD {si_signo=SIGCHLD, si_code=CLD_EXITED, si_pid=1387721} ---
+        \\22:21:24.928000 <... wait4 resumed>NULL, 0, NULL) = 1387721 <0.001000>
+    );
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const stats = try processFile(allocator, &db, test_file);
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(usize, 3), stats.parsed_lines);
+    try std.testing.expectEqual(@as(usize, 0), stats.failed_lines);
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64(
+        \\SELECT COUNT(*) FROM syscalls
+        \\WHERE kind = 'signal' AND syscall = 'SIGCHLD' AND pid = 2929
+        \\  AND args = '{si_signo=SIGCHLD, si_code=CLD_EXITED, si_pid=1387721}'
+    ));
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE kind = 'syscall'"));
+}
+
+test "processFile records exit codes per pid" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/exits.3030";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try file.writeAll(
+        \\3031 22:21:24.929000 exit_group(1) = ?
+        \\3031 22:21:24.929500 +++ exited with 1 +++
+        \\22:21:24.930000 +++ exited with 0 +++
+    );
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const stats = try processFile(allocator, &db, test_file);
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(usize, 0), stats.failed_lines);
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT return_value FROM syscalls WHERE kind = 'exit' AND pid = 3031"));
+    try std.testing.expectEqual(@as(i64, 0), try db.queryInt64("SELECT return_value FROM syscalls WHERE kind = 'exit' AND pid = 3030"));
+}
+
+test "processFile records which processes were killed and by what" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/killed.3131";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try file.writeAll(
+        \\3132 22:21:24.931000 +++ killed by SIGSEGV (core dumped) +++
+        \\22:21:24.932000 +++ killed by SIGKILL +++
+    );
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const stats = try processFile(allocator, &db, test_file);
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(usize, 0), stats.failed_lines);
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE kind = 'killed' AND pid = 3132 AND signal = 'SIGSEGV' AND core_dumped"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE kind = 'killed' AND pid = 3131 AND signal = 'SIGKILL' AND NOT core_dumped"));
+}
+
+test "processFile stores lines with invalid UTF-8 instead of failing them" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/bytes.4646";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try file.writeAll("10:00:00.000001 write(1, \"caf\xe9\", 4) = 4\n10:00:00.000002 close(1) = 0\n");
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const stats = try processFile(allocator, &db, test_file);
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(usize, 2), stats.parsed_lines);
+    try std.testing.expectEqual(@as(usize, 0), stats.failed_lines);
+    try std.testing.expectEqual(@as(usize, 1), stats.lossy_lines);
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE args = '1, \"caf\u{FFFD}\", 4'"));
+}
+
+test "TraceIterator rows can be inserted with insertIter" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/insertiter.4850";
+    try std.fs.cwd().writeFile(.{ .sub_path = test_file, .data =
+        \\10:00:00.000001 read(3, <unfinished ...>
+        \\[pid 4851] 10:00:00.000002 munmap(0x7f3a1c000000, 8192) = 0
+        \\not a syscall
+        \\10:00:00.000003 <... read resumed>"x", 1) = 1
+        \\
+    });
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    // The iterator's rows match what processFile stores with the same options
+    const options = ProcessOptions{ .reassemble = true, .normalize_hex_addrs = true };
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+    try db.beginAppend();
+    _ = try processFileWithOptions(allocator, &db, test_file, options);
+    try db.endAppend();
+
+    try db.addTable("iterated");
+    try db.useTable("iterated");
+    const trace = try TraceIterator.open(allocator, test_file, options);
+    defer trace.destroy();
+    try std.testing.expectEqual(@as(usize, 2), try db.insertIter(trace.trace_file, trace.pid, trace));
+    try std.testing.expectEqual(@as(usize, 2), trace.stats.parsed_lines);
+
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT COUNT(*) FROM iterated"));
+    try std.testing.expectEqual(@as(i64, 0), try db.queryInt64(
+        \\SELECT COUNT(*) FROM (
+        \\    SELECT trace_file, pid, tid, syscall, args, args_raw, reassembled FROM syscalls
+        \\    EXCEPT SELECT trace_file, pid, tid, syscall, args, args_raw, reassembled FROM iterated)
+    ));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM iterated WHERE pid = 4851 AND args = '0x<ptr>, 8192'"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM iterated WHERE pid = 4850 AND reassembled AND args = '3, \"x\", 1'"));
+}
+
+test "TraceIterator yields parsed rows from plain and compressed traces" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const data =
+        \\10:00:00.000001 open("/tmp/a", O_RDONLY) = 3
+        \\# a comment
+        \\[pid 4849] 10:00:00.000002 read(3, "abc", 3) = 3
+        \\10:00:00.000003 read(3
+        \\10:00:00.000004 close(3) = 0
+    ;
+    const plain_file = "zig-cache/test-traces/iterate.4848";
+    try std.fs.cwd().writeFile(.{ .sub_path = plain_file, .data = data });
+    defer std.fs.cwd().deleteFile(plain_file) catch {};
+
+    const gzip_file = "zig-cache/test-traces/iterate.4848.gz";
+    const gzip = try std.fs.cwd().createFile(gzip_file, .{});
+    defer std.fs.cwd().deleteFile(gzip_file) catch {};
+    try writeStoredGzip(gzip, data);
+    gzip.close();
+
+    for ([_][]const u8{ plain_file, gzip_file }) |path| {
+        const trace = try TraceIterator.open(allocator, path, .{});
+        defer trace.destroy();
+        try std.testing.expectEqual(@as(i32, 4848), trace.pid);
+
+        const open_row = (try trace.next()).?;
+        try std.testing.expectEqualStrings("open", open_row.syscall);
+        try std.testing.expectEqual(@as(?i32, 1), open_row.line_number);
+
+        const read_row = (try trace.next()).?;
+        try std.testing.expectEqual(@as(?i32, 4849), read_row.pid);
+        try std.testing.expectEqual(@as(?i32, 3), read_row.line_number);
+
+        // The malformed read is counted and skipped
+        const close_row = (try trace.next()).?;
+        try std.testing.expectEqualStrings("close", close_row.syscall);
+        try std.testing.expect((try trace.next()) == null);
+
+        try std.testing.expectEqual(@as(usize, 5), trace.stats.total_lines);
+        try std.testing.expectEqual(@as(usize, 3), trace.stats.parsed_lines);
+        try std.testing.expectEqual(@as(usize, 1), trace.stats.failed_lines);
+    }
+}
+
+test "parseFileToList collects rows without a database" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/in-memory.4949";
+    try std.fs.cwd().writeFile(.{ .sub_path = test_file, .data =
+        \\10:00:00.000001 open("/tmp/a", O_RDONLY) = 3
+        \\[pid 4950] 10:00:00.000002 read(3, "abc", 3) = 3
+        \\not a syscall line
+        \\10:00:00.000003 close(3) = 0
+    });
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    var parsed = try parseFileToList(allocator, test_file, .{ .extract_fd = true });
+    defer parsed.deinit();
+
+    try std.testing.expectEqual(@as(usize, 3), parsed.rows.len);
+    try std.testing.expectEqual(@as(usize, 3), parsed.stats.parsed_lines);
+    try std.testing.expectEqual(@as(usize, 4), parsed.stats.total_lines);
+    try std.testing.expectEqualStrings("3, \"abc\", 3", parsed.rows[1].args);
+    try std.testing.expectEqual(@as(?i32, 4950), parsed.rows[1].pid);
+    try std.testing.expectEqual(@as(?i32, 4949), parsed.rows[2].pid);
+    try std.testing.expectEqual(@as(?i32, 3), parsed.rows[2].fd);
+}
+
+test "processFile reassembles interleaved unfinished/resumed pairs" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/reassemble.4040";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try file.writeAll(
+        \\[pid 4041] 10:00:00.000001 read(3, <unfinished ...>
+        \\[pid 4041] 10:00:00.000002 futex(0x7f00, FUTEX_WAIT_PRIVATE, 0, NULL <unfinished ...>
+        \\[pid 4042] 10:00:00.000003 read(5, <unfinished ...>
+        \\[pid 4041] 10:00:00.000004 <... read resumed>"data", 100) = 4 <0.000003>
+        \\[pid 4041] 10:00:00.000005 <... futex resumed>) = 0 <0.000003>
+        \\10:00:00.000006 close(3) = 0
+    );
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const stats = try processFileWithOptions(allocator, &db, test_file, .{ .reassemble = true });
+    try db.endAppend();
+
+    // Two combined rows, the never-resumed read on 4042, and close
+    try std.testing.expectEqual(@as(usize, 4), stats.parsed_lines);
+    try std.testing.expectEqual(@as(i64, 4), try db.getSyscallCount());
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64(
+        \\SELECT COUNT(*) FROM syscalls
+        \\WHERE reassembled AND pid = 4041 AND syscall = 'read' AND args = '3, "data", 100'
+        \\  AND timestamp = '10:00:00.000001' AND return_value = 4 AND NOT unfinished AND NOT resumed
+    ));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64(
+        \\SELECT COUNT(*) FROM syscalls
+        \\WHERE reassembled AND pid = 4041 AND syscall = 'futex' AND timestamp = '10:00:00.000002' AND return_value = 0
+    ));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64(
+        \\SELECT COUNT(*) FROM syscalls WHERE unfinished AND NOT reassembled AND pid = 4042 AND args = '5, '
+    ));
+}
+
+test "processFile reassembles resumed lines that have no timestamp" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/untimed.4343";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try file.writeAll(
+        \\[pid 4344] 10:00:00.000001 read(3, <unfinished ...>
+        \\[pid 4344] <... read resumed>"data", 100) = 4
+        \\[pid 4345] <... poll resumed>) = 1
+    );
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const stats = try processFileWithOptions(allocator, &db, test_file, .{ .reassemble = true });
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(usize, 2), stats.parsed_lines);
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64(
+        \\SELECT COUNT(*) FROM syscalls
+        \\WHERE reassembled AND pid = 4344 AND args = '3, "data", 100'
+        \\  AND timestamp = '10:00:00.000001' AND time_of_day = TIME '10:00:00.000001'
+    ));
+    // Without an unfinished half the row keeps its empty timestamp
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE resumed AND pid = 4345 AND timestamp = ''"));
+}
+
+test "processFile normalizes reassembled args longer than any line" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/longpair.4646";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    // Each half is longer than the 4KB minimum line buffer; the joined args are longer still
+    const half = "a" ** 5000;
+    try file.writeAll("10:00:00.000001 writev(3, \"" ++ half ++ "\", <unfinished ...>\n");
+    try file.writeAll("10:00:00.000002 <... writev resumed>0x7f3a1c000000, \"" ++ half ++ "\") = 10000\n");
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const stats = try processFileWithOptions(allocator, &db, test_file, .{ .reassemble = true, .normalize_hex_addrs = true });
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(usize, 1), stats.parsed_lines);
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64(
+        \\SELECT COUNT(*) FROM syscalls
+        \\WHERE reassembled AND args LIKE '%0x<ptr>%' AND length(args) > 10000 AND args_raw LIKE '%0x7f3a1c000000%'
+    ));
+}
+
+test "processFile keeps fragments alongside reassembled rows" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/fragments.4141";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try file.writeAll(
+        \\10:00:00.000001 wait4(-1, <unfinished ...>
+        \\10:00:00.000002 <... wait4 resumed>NULL, 0, NULL) = 4142
+    );
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const stats = try processFileWithOptions(allocator, &db, test_file, .{ .reassemble = true, .keep_fragments = true });
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(usize, 3), stats.parsed_lines);
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE reassembled AND args = '-1, NULL, 0, NULL'"));
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE (unfinished OR resumed) AND NOT reassembled"));
+}
+
+test "processFile keeps a never resumed fragment once" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/fragments.4143";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try file.writeAll(
+        \\10:00:00.000001 wait4(-1, <unfinished ...>
+        \\10:00:00.000002 close(3) = 0
+    );
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const stats = try processFileWithOptions(allocator, &db, test_file, .{ .reassemble = true, .keep_fragments = true });
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(usize, 2), stats.parsed_lines);
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE syscall = 'wait4'"));
+}
+
+test "processFile stores a never resumed call as it would without reassemble" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/orphan.4144";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try file.writeAll(
+        \\10:00:00.000001 read(3, "abc"..., <unfinished ...>
+        \\10:00:00.000002 close(4) = 0
+    );
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    const base_date = try utils.parseDate("2024-03-01");
+    try db.beginAppend();
+    _ = try processFileWithOptions(allocator, &db, test_file, .{ .base_date_us = base_date * std.time.us_per_day, .keep_raw = true });
+    try db.endAppend();
+    try db.execute("CREATE TABLE plain AS SELECT * FROM syscalls WHERE syscall = 'read'");
+    try db.execute("DELETE FROM syscalls");
+
+    try db.beginAppend();
+    _ = try processFileWithOptions(allocator, &db, test_file, .{ .base_date_us = base_date * std.time.us_per_day, .keep_raw = true, .reassemble = true });
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE syscall = 'read' AND unfinished AND truncated AND wall_time IS NOT NULL"));
+    try std.testing.expectEqual(@as(i64, 0), try db.queryInt64(
+        \\SELECT COUNT(*) FROM (
+        \\  SELECT timestamp, args, time_of_day, wall_time, arg_count, truncated, raw_line, line_number, rel_time FROM plain
+        \\  EXCEPT
+        \\  SELECT timestamp, args, time_of_day, wall_time, arg_count, truncated, raw_line, line_number, rel_time FROM syscalls WHERE syscall = 'read'
+        \\)
+    ));
+}
+
+test "followFileToSinks reads complete lines and holds back a partial one" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/follow.4747";
+    try std.fs.cwd().writeFile(.{ .sub_path = test_file, .data = "10:00:00.000001 open(\"/tmp/a\", O_RDONLY) = 3\n10:00:00.000002 close(3) = 0\n10:00:00.000003 read(3," });
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    // Already stopped: read to the current end of the file once, then return
+    var stop = std.atomic.Value(bool).init(true);
+    try db.beginAppend();
+    const sinks = [_]SyscallSink{sink.databaseSink(&db)};
+    const stats = try followFileToSinks(allocator, &sinks, test_file, .{}, &stop);
+
+    // The reader flushed when it caught up, so the rows are visible before endAppend
+    try std.testing.expectEqual(@as(i64, 2), try db.getSyscallCount());
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(usize, 2), stats.total_lines);
+    try std.testing.expectEqual(@as(usize, 2), stats.parsed_lines);
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE pid = 4747"));
+}
+
+/// Write data as a gzip member holding one uncompressed deflate block
+fn writeStoredGzip(file: std.fs.File, data: []const u8) !void {
+    const header = [_]u8{ 0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 3 };
+    try file.writeAll(&header);
+
+    const len: u16 = @intCast(data.len);
+    var block: [5]u8 = undefined;
+    block[0] = 1; // BFINAL, BTYPE=00 (stored)
+    std.mem.writeInt(u16, block[1..3], len, .little);
+    std.mem.writeInt(u16, block[3..5], ~len, .little);
+    try file.writeAll(&block);
+    try file.writeAll(data);
+
+    var trailer: [8]u8 = undefined;
+    std.mem.writeInt(u32, trailer[0..4], std.hash.Crc32.hash(data), .little);
+    std.mem.writeInt(u32, trailer[4..8], @intCast(data.len), .little);
+    try file.writeAll(&trailer);
+}
+
+test "processFile reads gzip-compressed traces" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/compressed.5151.gz";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try writeStoredGzip(file,
+        \\10:00:00.000001 open("/tmp/a", O_RDONLY) = 3
+        \\10:00:00.000002 close(3) = 0
+        \\
+    );
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const stats = try processFile(allocator, &db, test_file);
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(usize, 2), stats.total_lines);
+    try std.testing.expectEqual(@as(usize, 2), stats.parsed_lines);
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE pid = 5151 AND trace_file = 'compressed.5151.gz'"));
+}
+
+test "processFile detects gzip by magic bytes" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/unlabelled.5252";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try writeStoredGzip(file, "10:00:00.000001 close(3) = 0\n");
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const stats = try processFile(allocator, &db, test_file);
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(usize, 1), stats.parsed_lines);
+    try std.testing.expectEqual(@as(usize, 0), stats.failed_lines);
+}
+
+/// Write data as a single-segment zstd frame holding one raw (uncompressed) block
+fn writeRawZstd(file: std.fs.File, data: []const u8) !void {
+    // Frame header: single segment, 1-byte content size, no checksum or dictionary
+    try file.writeAll(&zstd_magic);
+    try file.writeAll(&[_]u8{ 0x20, @intCast(data.len) });
+
+    // Block header: last block, raw, size in the upper 21 bits
+    var block: [3]u8 = undefined;
+    std.mem.writeInt(u24, &block, @intCast(1 | (data.len << 3)), .little);
+    try file.writeAll(&block);
+    try file.writeAll(data);
+}
+
+test "processFile reads zstd-compressed traces" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/compressed.5353.zst";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try writeRawZstd(file,
+        \\10:00:00.000001 open("/tmp/a", O_RDONLY) = 3
+        \\10:00:00.000002 close(3) = 0
+        \\
+    );
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const stats = try processFile(allocator, &db, test_file);
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(usize, 2), stats.parsed_lines);
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE pid = 5353 AND trace_file = 'compressed.5353.zst'"));
+}
+
+test "detectCompression uses the extension, then magic bytes" {
+    const test_dir = "zig-cache/test-detect-compression";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    const unlabelled = try std.fs.cwd().createFile(test_dir ++ "/trace.1", .{});
+    try writeRawZstd(unlabelled, "10:00:00.000001 close(3) = 0\n");
+    unlabelled.close();
+    try std.fs.cwd().writeFile(.{ .sub_path = test_dir ++ "/trace.2", .data = "10:00:00.000001 close(3) = 0\n" });
+    try std.fs.cwd().writeFile(.{ .sub_path = test_dir ++ "/t", .data = "" });
+
+    try std.testing.expectEqual(Compression.zstd, try detectCompression(test_dir ++ "/trace.1"));
+    try std.testing.expectEqual(Compression.none, try detectCompression(test_dir ++ "/trace.2"));
+    try std.testing.expectEqual(Compression.none, try detectCompression(test_dir ++ "/t"));
+    try std.testing.expectEqual(Compression.gzip, try detectCompression("not-opened.gz"));
+    try std.testing.expectEqual(Compression.zstd, try detectCompression("not-opened.zst"));
+}
+
+test "streamed lines share the line loop and stdin attribution" {
+    const allocator = std.testing.allocator;
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+    try db.beginAppend();
+
+    // Last line has no trailing newline
+    var reader = std.Io.Reader.fixed("10:00:00.000001 getpid() = 42\n\n[pid 43] 10:00:00.000002 close(3) = 0");
+    var lines = StreamedLines.init(allocator, &reader);
+    defer lines.deinit();
+
+    const sinks = [_]SyscallSink{sink.databaseSink(&db)};
+    var stats = FileStats.init();
+    try processLines(allocator, &lines, .{
+        .sinks = &sinks,
+        .filename = stdin_trace_file,
+        .pid = 0,
+        .command = null,
+        .options = .{},
+    }, &stats);
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(usize, 3), stats.total_lines);
+    try std.testing.expectEqual(@as(usize, 2), stats.parsed_lines);
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE trace_file = 'stdin' AND pid = 0"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE trace_file = 'stdin' AND pid = 43"));
+}
+
+test "processFile builds wall_time from --date across midnight" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/midnight.6060";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try file.writeAll(
+        \\23:59:59.900000 read(3, "", 1) = 0
+        \\00:00:00.100000 close(3) = 0
+    );
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const base_date = try utils.parseDate("2024-03-01");
+    _ = try processFileWithOptions(allocator, &db, test_file, .{ .base_date_us = base_date * std.time.us_per_day });
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE syscall = 'read' AND wall_time = TIMESTAMP '2024-03-01 23:59:59.9'"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE syscall = 'close' AND wall_time = TIMESTAMP '2024-03-02 00:00:00.1'"));
+}
+
+test "processFile keeps the unmodified raw line with keep_raw" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/raw.6161";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try file.writeAll("10:00:00.000001 \x1b[1mclose\x1b[0m(3) = 0\n");
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    _ = try processFileWithOptions(allocator, &db, test_file, .{ .strip_ansi = true, .keep_raw = true });
+    _ = try processFileWithOptions(allocator, &db, test_file, .{ .strip_ansi = true });
+    try db.endAppend();
+
+    // The escape codes survive in raw_line even though parsing saw the stripped text
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64(
+        \\SELECT COUNT(*) FROM syscalls
+        \\WHERE syscall = 'close' AND raw_line = '10:00:00.000001 ' || chr(27) || '[1mclose' || chr(27) || '[0m(3) = 0'
+    ));
+    // Off by default
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE raw_line IS NULL"));
+}
+
+test "CRLF line endings parse like LF" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/crlf.6262";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try file.writeAll("10:00:00.000001 open(\"/tmp/a\", O_RDONLY) = 3 <0.000042>\r\n\r\n10:00:00.000002 close(3) = 0\r\n");
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const stats = try processFileWithOptions(allocator, &db, test_file, .{ .keep_raw = true });
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(usize, 3), stats.total_lines);
+    try std.testing.expectEqual(@as(usize, 2), stats.parsed_lines);
+    try std.testing.expectEqual(@as(usize, 0), stats.failed_lines);
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE syscall = 'open' AND duration = 0.000042 AND return_value = 3"));
+    try std.testing.expectEqual(@as(i64, 0), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE raw_line LIKE '%' || chr(13)"));
+}
+
+test "processFile records source line numbers" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/lines.6262";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    // Blank and unparseable lines still count
+    try file.writeAll(
+        \\10:00:00.000001 open("/a", O_RDONLY) = 3
+        \\
+        \\garbage
+        \\10:00:00.000002 read(3, <unfinished ...>
+        \\10:00:00.000003 <... read resumed>"x", 1) = 1
+    );
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    _ = try processFile(allocator, &db, test_file);
+    _ = try processFileWithOptions(allocator, &db, test_file, .{ .reassemble = true });
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE syscall = 'open' AND line_number = 1"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE unfinished AND line_number = 4"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE resumed AND line_number = 5"));
+    // A reassembled row points at the line the call started on
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE reassembled AND line_number = 4"));
+}
+
+test "processFile writes unparsed lines to the rejects file" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/rejects.6363";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try file.writeAll(
+        \\10:00:00.000001 close(3) = 0
+        \\
+        \\not a syscall
+        \\10:00:00.000002 close(4) = 0
+    );
+    file.close();
+
+    const rejects_path = "zig-cache/test-traces/rejects.6363.txt";
+    const rejected = try rejects.RejectsFile.create(allocator, rejects_path);
+    defer std.fs.cwd().deleteFile(rejects_path) catch {};
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const stats = try processFileWithOptions(allocator, &db, test_file, .{ .rejects = rejected });
+    try db.endAppend();
+    rejected.destroy();
+
+    try std.testing.expectEqual(@as(usize, 2), stats.parsed_lines);
+
+    // Blank lines are not rejects
+    const contents = try std.fs.cwd().readFileAlloc(allocator, rejects_path, 1024);
+    defer allocator.free(contents);
+    try std.testing.expectEqualStrings("rejects.6363:3: not a syscall\n", contents);
+}
+
+fn writeMixedCompletionFixture(test_file: []const u8) !void {
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer file.close();
+
+    try file.writeAll(
+        \\10:23:45.123456 read(3, <unfinished ...>
+        \\10:23:45.123457 write(1, "hello", 5) = 5 <0.000020>
+        \\10:23:45.123458 <... read resumed>"data", 100) = 4 <0.000042>
+        \\10:23:45.123459 close(3) = 0 <0.000010>
+        \\10:23:45.123460 getpid() = 2323 <0.000002>
+    );
+}
+
+test "processFileWithOptions keeps only unfinished and resumed rows" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/only-unfinished.2323";
+    try writeMixedCompletionFixture(test_file);
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const stats = try processFileWithOptions(allocator, &db, test_file, .{ .row_filter = .unfinished });
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(usize, 5), stats.total_lines);
+    try std.testing.expectEqual(@as(usize, 2), stats.parsed_lines);
+    try std.testing.expectEqual(@as(usize, 0), stats.failed_lines);
+    try std.testing.expectEqual(@as(usize, 3), stats.filtered_lines);
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE unfinished OR resumed"));
+    try std.testing.expectEqual(@as(i64, 2), try db.getSyscallCount());
+}
+
+test "processFileWithOptions keeps only completed rows" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/only-completed.2424";
+    try writeMixedCompletionFixture(test_file);
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const stats = try processFileWithOptions(allocator, &db, test_file, .{ .row_filter = .completed });
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(usize, 3), stats.parsed_lines);
+    try std.testing.expectEqual(@as(i64, 0), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE unfinished OR resumed"));
+    try std.testing.expectEqual(@as(i64, 3), try db.getSyscallCount());
+}
+
+test "processFileWithOptions filters rows by syscall name" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/syscall-filter.2525";
+    try std.fs.cwd().writeFile(.{ .sub_path = test_file, .data =
+        \\10:00:00.000001 openat(AT_FDCWD, "/tmp/a", O_RDONLY) = 3
+        \\10:00:00.000002 read(3, "abc", 3) = 3
+        \\10:00:00.000003 mmap(NULL, 4096, PROT_READ, MAP_PRIVATE, 3, 0) = 0x7f0000000000
+        \\10:00:00.000004 close(3) = 0
+    });
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    // Includes win over excludes
+    try db.beginAppend();
+    const included = try processFileWithOptions(allocator, &db, test_file, .{ .syscall_filter = .{
+        .include = &.{ "open*", "read" },
+        .exclude = &.{"read"},
+    } });
+    try db.endAppend();
+    try std.testing.expectEqual(@as(usize, 2), included.parsed_lines);
+    try std.testing.expectEqual(@as(usize, 2), included.filtered_lines);
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE syscall IN ('openat', 'read')"));
+
+    try db.beginAppend();
+    const excluded = try processFileWithOptions(allocator, &db, test_file, .{ .syscall_filter = .{ .exclude = &.{ "mmap", "close" } } });
+    try db.endAppend();
+    try std.testing.expectEqual(@as(usize, 2), excluded.parsed_lines);
+    try std.testing.expectEqual(@as(usize, 2), excluded.filtered_lines);
+    try std.testing.expectEqual(@as(i64, 0), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE syscall IN ('mmap', 'close')"));
+}
+
+test "processFileWithOptions keeps only rows at or over --min-duration" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/min-duration.2626";
+    try std.fs.cwd().writeFile(.{ .sub_path = test_file, .data =
+        \\10:00:00.000001 read(3, "abc", 3) = 3 <0.000010>
+        \\10:00:00.000002 fsync(3) = 0 <0.250000>
+        \\10:00:00.000003 close(3) = 0
+        \\10:00:00.000004 futex(0x7f00, FUTEX_WAIT_PRIVATE, 0, NULL <unfinished ...>
+        \\10:00:00.000005 <... futex resumed>) = 0 <1.500000>
+    });
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    const stats = try processFileWithOptions(allocator, &db, test_file, .{ .min_duration = 0.1, .reassemble = true });
+    try db.endAppend();
+
+    // read is too fast and close has no duration
+    try std.testing.expectEqual(@as(usize, 2), stats.parsed_lines);
+    try std.testing.expectEqual(@as(usize, 2), stats.filtered_lines);
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE syscall = 'fsync'"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE syscall = 'futex' AND reassembled"));
+}
+
+test "countLinesAndMaxLength with normal file" {
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/count-test.7777";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    // Create file with known line lengths
+    try file.writeAll("short line\n"); // 11 bytes (10 + newline)
+    try file.writeAll("medium length line here\n"); // 24 bytes
+    try file.writeAll("x\n"); // 2 bytes
+    const long_line = "L" ** 5000;
+    try file.writeAll(long_line);
+    try file.writeAll("\n"); // 5001 bytes
+    file.close();
+
+    const stats = try countLinesAndMaxLength(test_file, 10 * 1024 * 1024);
+
+    try std.testing.expectEqual(@as(usize, 4), stats.total_lines);
+    try std.testing.expectEqual(@as(usize, 5001), stats.max_line_length);
+}
+
+test "countLinesAndMaxLength fails on line exceeding limit" {
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/toolong.8888";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    // Create file with line exceeding 1MB limit (using smaller limit for test speed)
+    try file.writeAll("normal line\n");
+    const huge_line = "X" ** (1024 * 1024 + 100); // Just over 1MB
+    try file.writeAll(huge_line);
+    try file.writeAll("\n");
+    file.close();
+
+    // Should fail with LineTooLong when limit is 1MB
+    const result = countLinesAndMaxLength(test_file, 1024 * 1024);
+    try std.testing.expectError(error.LineTooLong, result);
+}
+
+test "countLinesAndMaxLength handles exactly at limit" {
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/exactlimit.9999";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    // Create file with line exactly at limit (100KB for test speed)
+    const limit = 100 * 1024;
+    const exact_line = "E" ** (limit - 1); // -1 for newline
+    try file.writeAll(exact_line);
+    try file.writeAll("\n");
     file.close();
 
     // Should succeed - exactly at limit
@@ -546,3 +2636,225 @@ test "countLinesAndMaxLength with empty file" {
     try std.testing.expectEqual(@as(usize, 0), stats.total_lines);
     try std.testing.expectEqual(@as(usize, 0), stats.max_line_length);
 }
+
+test "planRanges cuts after a newline and counts the lines before each range" {
+    const test_dir = "zig-cache/test-plan-ranges";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    // 10-byte lines, the last one without a newline
+    const test_file = test_dir ++ "/trace.1";
+    try std.fs.cwd().writeFile(.{ .sub_path = test_file, .data = "line one.\nline two.\nline 333.\nline 4444" });
+
+    const ranges = try planRanges(std.testing.allocator, test_file, 15);
+    defer freeRanges(std.testing.allocator, ranges);
+
+    try std.testing.expectEqual(@as(usize, 2), ranges.len);
+    try std.testing.expectEqualDeep(ByteRange{ .start = 0, .end = 20, .lines_before = 0 }, ranges[0]);
+    try std.testing.expectEqualDeep(ByteRange{ .start = 20, .end = 39, .lines_before = 2 }, ranges[1]);
+}
+
+test "processFileRangeToSinks reads only its range and keeps file line numbers" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-range";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    const test_file = test_dir ++ "/trace.4242";
+    try std.fs.cwd().writeFile(.{ .sub_path = test_file, .data =
+        \\10:00:00.000001 getpid() = 4242
+        \\10:00:00.000002 close(3) = 0
+        \\10:00:00.000003 close(4) = 0
+        \\10:00:00.000004 getuid() = 1000
+        \\
+    });
+
+    const ranges = try planRanges(allocator, test_file, 40);
+    defer freeRanges(allocator, ranges);
+    try std.testing.expectEqual(@as(usize, 2), ranges.len);
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+    try db.beginAppend();
+
+    const sinks = [_]SyscallSink{sink.databaseSink(&db)};
+    const stats = try processFileRangeToSinks(allocator, &sinks, test_file, 0, ranges[1], .{});
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(usize, 2), stats.total_lines);
+    try std.testing.expectEqual(@as(i64, 2), try db.getSyscallCount());
+    try std.testing.expectEqual(@as(i64, 3), try db.queryInt64("SELECT MIN(line_number) FROM syscalls"));
+    try std.testing.expectEqual(@as(i64, 4242), try db.queryInt64("SELECT MIN(pid) FROM syscalls WHERE syscall = 'getuid'"));
+}
+
+test "processFileRangeToSinks maps threads cloned in an earlier range" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-range-threads";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    const test_file = test_dir ++ "/trace.500";
+    try std.fs.cwd().writeFile(.{ .sub_path = test_file, .data =
+        \\[pid  500] 10:00:00.000001 clone3({flags=CLONE_VM|CLONE_THREAD, exit_signal=0}, 88) = 501
+        \\[pid  501] 10:00:00.000002 read(3, "x", 1) = 1
+        \\
+    });
+
+    const ranges = try planRanges(allocator, test_file, 40);
+    defer freeRanges(allocator, ranges);
+    try std.testing.expectEqual(@as(usize, 2), ranges.len);
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+    try db.beginAppend();
+
+    const sinks = [_]SyscallSink{sink.databaseSink(&db)};
+    _ = try processFileRangeToSinks(allocator, &sinks, test_file, 0, ranges[1], .{});
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE syscall = 'read' AND pid = 500 AND tid = 501"));
+}
+
+test "hashFile changes only when the contents change" {
+    const test_dir = "zig-cache/test-hash-file";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    try std.fs.cwd().writeFile(.{ .sub_path = test_dir ++ "/a", .data = "10:00:00.000001 close(3) = 0\n" });
+    try std.fs.cwd().writeFile(.{ .sub_path = test_dir ++ "/b", .data = "10:00:00.000001 close(3) = 0\n" });
+    try std.fs.cwd().writeFile(.{ .sub_path = test_dir ++ "/c", .data = "10:00:00.000001 close(4) = 0\n" });
+
+    try std.testing.expectEqual(try hashFile(test_dir ++ "/a"), try hashFile(test_dir ++ "/b"));
+    try std.testing.expect(try hashFile(test_dir ++ "/a") != try hashFile(test_dir ++ "/c"));
+    try std.testing.expectError(error.FileNotFound, hashFile(test_dir ++ "/missing"));
+}
+
+test "processFile maps threads to their process and keeps the tid" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/threads.trace";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try file.writeAll(
+        \\[pid  500] 10:00:00.000001 clone3({flags=CLONE_VM|CLONE_FS|CLONE_FILES|CLONE_SIGHAND|CLONE_THREAD|CLONE_SYSVSEM, exit_signal=0}, 88) = 501
+        \\[pid  501] 10:00:00.000002 read(3, "x", 1) = 1
+        \\[pid  500] 10:00:00.000003 clone(child_stack=NULL, flags=CLONE_CHILD_CLEARTID|CLONE_CHILD_SETTID|SIGCHLD) = 600
+        \\[pid  600] 10:00:00.000004 getpid() = 600
+        \\
+    );
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    _ = try processFile(allocator, &db, test_file);
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE syscall = 'read' AND pid = 500 AND tid = 501"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE syscall = 'getpid' AND pid = 600 AND tid = 600"));
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT COUNT(DISTINCT pid) FROM syscalls"));
+    try std.testing.expectEqual(@as(i64, 3), try db.queryInt64("SELECT COUNT(DISTINCT tid) FROM syscalls"));
+
+    // The thread is not a child process in process_tree
+    try db.buildProcessTree();
+    try std.testing.expectEqual(@as(i64, 0), try db.queryInt64("SELECT COUNT(*) FROM process_tree WHERE pid = 501"));
+}
+
+test "duration_ns is stored only with the duration_ns option" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/durationns.3133";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try file.writeAll(
+        \\10:00:00.000001 read(3, "x", 1) = 1 <0.000042>
+        \\10:00:00.000002 read(3, "x", 1) = 1 <0.100001>
+        \\10:00:00.000003 close(3) = 0
+        \\
+    );
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    _ = try processFileWithOptions(allocator, &db, test_file, .{ .duration_ns = true });
+    _ = try processFileWithOptions(allocator, &db, test_file, .{});
+    try db.endAppend();
+
+    // Exact sums, where SUM(duration) would carry float error
+    try std.testing.expectEqual(@as(i64, 100_043_000), try db.queryInt64("SELECT SUM(duration_ns) FROM syscalls WHERE syscall = 'read'"));
+    try std.testing.expectEqual(@as(i64, 4), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE duration_ns IS NULL"));
+}
+
+test "max_args_len cuts long args on a character boundary and flags them" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/maxargs.3132";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    // The 15th byte of the write's args is the second half of "é"
+    try file.writeAll(
+        \\10:00:00.000001 write(1, "aaaaaaaaaé and more", 20) = 20
+        \\10:00:00.000002 close(1) = 0
+        \\
+    );
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    _ = try processFileWithOptions(allocator, &db, test_file, .{ .max_args_len = 14 });
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE args = '1, \"aaaaaaaaa' AND args_truncated"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE syscall = 'close' AND args = '1' AND NOT args_truncated"));
+    // arg_count still counts the whole list
+    try std.testing.expectEqual(@as(i64, 3), try db.queryInt64("SELECT arg_count FROM syscalls WHERE syscall = 'write'"));
+}
+
+test "args_json holds the arguments of complete calls (--args-json)" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-traces";
+    try std.fs.cwd().makePath(test_dir);
+
+    const test_file = "zig-cache/test-traces/argsjson.3131";
+    const file = try std.fs.cwd().createFile(test_file, .{});
+    defer std.fs.cwd().deleteFile(test_file) catch {};
+
+    try file.writeAll(
+        \\10:00:00.000001 openat(AT_FDCWD, "/etc/hosts", O_RDONLY|O_CLOEXEC) = 3
+        \\10:00:00.000002 read(3, <unfinished ...>
+        \\10:00:00.000003 <... read resumed>"x", 1) = 1
+        \\
+    );
+    file.close();
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    try db.beginAppend();
+    _ = try processFileWithOptions(allocator, &db, test_file, .{ .args_json = true });
+    try db.endAppend();
+
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE json_extract_string(args_json, '$[1]') = '\"/etc/hosts\"'"));
+    try std.testing.expectEqual(@as(i64, 3), try db.queryInt64("SELECT json_array_length(args_json) FROM syscalls WHERE syscall = 'openat'"));
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE args_json IS NULL"));
+}
@@ -0,0 +1,182 @@
+const std = @import("std");
+
+/// Standard input marker, passed through expansion untouched
+const stdin_path = "-";
+
+/// Trace file paths after directory and glob expansion
+pub const Expansion = struct {
+    arena: std.heap.ArenaAllocator,
+    /// Sorted, de-duplicated paths; owned by the arena
+    paths: []const []const u8,
+    /// Directory and glob arguments that matched no regular file
+    unmatched: usize = 0,
+
+    pub fn deinit(self: *Expansion) void {
+        self.arena.deinit();
+    }
+};
+
+/// Expand trace file arguments into the list of files to process
+/// - A directory is walked recursively; every regular file below it is included
+/// - An argument whose last component contains `*` or `?` is matched against the
+///   entries of its directory (wildcards in directory components are not supported)
+/// - Anything else (plain paths, stdin) is kept as given, so missing files are
+///   still reported by the processor
+/// Non-regular files (sockets, fifos, symlinks to directories) found by expansion are skipped
+pub fn expand(allocator: std.mem.Allocator, args: []const []const u8) !Expansion {
+    var expansion = Expansion{ .arena = std.heap.ArenaAllocator.init(allocator), .paths = &.{} };
+    errdefer expansion.deinit();
+    const arena = expansion.arena.allocator();
+
+    var paths = std.ArrayListUnmanaged([]const u8){};
+    for (args) |arg| {
+        const before = paths.items.len;
+        if (std.mem.eql(u8, arg, stdin_path)) {
+            try paths.append(arena, arg);
+            continue;
+        } else if (isPattern(std.fs.path.basename(arg))) {
+            try expandPattern(arena, arg, &paths);
+        } else if (isDirectory(arg)) {
+            try expandDirectory(arena, arg, &paths);
+        } else {
+            try paths.append(arena, arg);
+            continue;
+        }
+        if (paths.items.len == before) expansion.unmatched += 1;
+    }
+
+    std.mem.sort([]const u8, paths.items, {}, pathLessThan);
+    expansion.paths = dedupe(paths.items);
+    return expansion;
+}
+
+fn expandDirectory(arena: std.mem.Allocator, dir_path: []const u8, paths: *std.ArrayListUnmanaged([]const u8)) !void {
+    var dir = try std.fs.cwd().openDir(dir_path, .{ .iterate = true });
+    defer dir.close();
+
+    var walker = try dir.walk(arena);
+    defer walker.deinit();
+    while (try walker.next()) |entry| {
+        if (entry.kind != .file) continue;
+        try paths.append(arena, try std.fs.path.join(arena, &.{ dir_path, entry.path }));
+    }
+}
+
+fn expandPattern(arena: std.mem.Allocator, pattern: []const u8, paths: *std.ArrayListUnmanaged([]const u8)) !void {
+    const dir_path = std.fs.path.dirname(pattern);
+    const name_pattern = std.fs.path.basename(pattern);
+
+    var dir = std.fs.cwd().openDir(dir_path orelse ".", .{ .iterate = true }) catch |err| switch (err) {
+        error.FileNotFound, error.NotDir => return,
+        else => return err,
+    };
+    defer dir.close();
+
+    var it = dir.iterate();
+    while (try it.next()) |entry| {
+        if (entry.kind != .file or !globMatch(name_pattern, entry.name)) continue;
+        const path = if (dir_path) |parent|
+            try std.fs.path.join(arena, &.{ parent, entry.name })
+        else
+            try arena.dupe(u8, entry.name);
+        try paths.append(arena, path);
+    }
+}
+
+fn isDirectory(path: []const u8) bool {
+    const stat = std.fs.cwd().statFile(path) catch return false;
+    return stat.kind == .directory;
+}
+
+fn isPattern(name: []const u8) bool {
+    return std.mem.indexOfAny(u8, name, "*?") != null;
+}
+
+/// Shell-style match of a single path component: `*` matches any run of bytes, `?` one byte
+pub fn globMatch(pattern: []const u8, name: []const u8) bool {
+    var p: usize = 0;
+    var n: usize = 0;
+    // Position to retry from after the most recent `*`
+    var star: ?usize = null;
+    var star_name: usize = 0;
+
+    while (n < name.len) {
+        if (p < pattern.len and (pattern[p] == '?' or pattern[p] == name[n])) {
+            p += 1;
+            n += 1;
+        } else if (p < pattern.len and pattern[p] == '*') {
+            star = p;
+            star_name = n;
+            p += 1;
+        } else if (star) |s| {
+            p = s + 1;
+            star_name += 1;
+            n = star_name;
+        } else {
+            return false;
+        }
+    }
+    while (p < pattern.len and pattern[p] == '*') p += 1;
+    return p == pattern.len;
+}
+
+fn pathLessThan(_: void, a: []const u8, b: []const u8) bool {
+    return std.mem.lessThan(u8, a, b);
+}
+
+/// Drop adjacent duplicates from a sorted list, in place
+fn dedupe(paths: [][]const u8) []const []const u8 {
+    if (paths.len == 0) return paths;
+    var write: usize = 1;
+    for (paths[1..]) |path| {
+        if (std.mem.eql(u8, path, paths[write - 1])) continue;
+        paths[write] = path;
+        write += 1;
+    }
+    return paths[0..write];
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+test "globMatch handles stars and question marks" {
+    try std.testing.expect(globMatch("*.trace", "a.trace"));
+    try std.testing.expect(globMatch("trace.*", "trace.1234"));
+    try std.testing.expect(globMatch("trace.??", "trace.12"));
+    try std.testing.expect(globMatch("*", ""));
+    try std.testing.expect(globMatch("a*b*c", "axxbyyc"));
+    try std.testing.expect(!globMatch("trace.??", "trace.123"));
+    try std.testing.expect(!globMatch("*.trace", "a.trace.gz"));
+    try std.testing.expect(!globMatch("a*b*c", "axxbyy"));
+}
+
+test "expand walks directories and matches globs in sorted order" {
+    const test_dir = "zig-cache/test-inputs";
+    try std.fs.cwd().makePath(test_dir ++ "/nested");
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    try std.fs.cwd().writeFile(.{ .sub_path = test_dir ++ "/trace.2", .data = "" });
+    try std.fs.cwd().writeFile(.{ .sub_path = test_dir ++ "/trace.1", .data = "" });
+    try std.fs.cwd().writeFile(.{ .sub_path = test_dir ++ "/notes.txt", .data = "" });
+    try std.fs.cwd().writeFile(.{ .sub_path = test_dir ++ "/nested/trace.3", .data = "" });
+
+    var glob = try expand(std.testing.allocator, &.{ test_dir ++ "/trace.*", "-" });
+    defer glob.deinit();
+    try std.testing.expectEqual(@as(usize, 3), glob.paths.len);
+    try std.testing.expectEqualStrings("-", glob.paths[0]);
+    try std.testing.expectEqualStrings(test_dir ++ "/trace.1", glob.paths[1]);
+    try std.testing.expectEqualStrings(test_dir ++ "/trace.2", glob.paths[2]);
+
+    // The directory itself is not a file; overlapping arguments are listed once
+    var walked = try expand(std.testing.allocator, &.{ test_dir, test_dir ++ "/trace.1" });
+    defer walked.deinit();
+    try std.testing.expectEqual(@as(usize, 4), walked.paths.len);
+    try std.testing.expectEqualStrings(test_dir ++ "/nested/trace.3", walked.paths[0]);
+    try std.testing.expectEqualStrings(test_dir ++ "/notes.txt", walked.paths[1]);
+
+    var none = try expand(std.testing.allocator, &.{ test_dir ++ "/*.strace", "missing.1" });
+    defer none.deinit();
+    try std.testing.expectEqual(@as(usize, 1), none.unmatched);
+    try std.testing.expectEqualStrings("missing.1", none.paths[0]);
+}
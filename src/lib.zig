@@ -0,0 +1,9 @@
+//! Library root for code built outside the CLI (the benchmarks)
+//! One module shares every source file, so types.zig is compiled once for all of them
+
+pub const log = @import("log.zig");
+pub const parser = @import("parser.zig");
+pub const types = @import("types.zig");
+pub const database = @import("database.zig");
+pub const file_processor = @import("file_processor.zig");
+pub const worker_pool = @import("worker_pool.zig");
@@ -0,0 +1,56 @@
+const std = @import("std");
+
+/// How much the CLI prints (--quiet, --verbose)
+/// Errors and warnings are printed at every level
+pub const Level = enum {
+    /// Only the final row count
+    quiet,
+    /// Progress, per-run summary and database statistics
+    normal,
+    /// Also samples of lines that did not parse
+    verbose,
+};
+
+/// Set once by main before processing starts; read from worker threads
+pub var level: Level = .normal;
+
+/// Whether messages at this level are shown
+pub fn enabled(at: Level) bool {
+    return @intFromEnum(level) >= @intFromEnum(at);
+}
+
+/// Status and summary text on stderr, hidden by --quiet
+pub fn print(comptime fmt: []const u8, args: anytype) void {
+    if (enabled(.normal)) std.debug.print(fmt, args);
+}
+
+/// Status text on stdout, hidden by --quiet
+pub fn write(text: []const u8) !void {
+    if (enabled(.normal)) try std.fs.File.stdout().writeAll(text);
+}
+
+/// Diagnostic detail on stderr, shown only with --verbose
+pub fn verbose(comptime fmt: []const u8, args: anytype) void {
+    if (enabled(.verbose)) std.debug.print(fmt, args);
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+test "levels include everything below them" {
+    const saved = level;
+    defer level = saved;
+
+    level = .quiet;
+    try std.testing.expect(enabled(.quiet));
+    try std.testing.expect(!enabled(.normal));
+
+    level = .normal;
+    try std.testing.expect(enabled(.normal));
+    try std.testing.expect(!enabled(.verbose));
+
+    level = .verbose;
+    try std.testing.expect(enabled(.normal));
+    try std.testing.expect(enabled(.verbose));
+}
@@ -1,8 +1,21 @@
 const std = @import("std");
 const database = @import("database.zig");
+const explain = @import("explain.zig");
+const file_processor = @import("file_processor.zig");
+const inputs = @import("inputs.zig");
+const log = @import("log.zig");
+const proc = @import("proc.zig");
+const rejects = @import("rejects.zig");
+const report = @import("report.zig");
+const sink = @import("sink.zig");
+const types = @import("types.zig");
+const utils = @import("utils.zig");
 const worker_pool = @import("worker_pool.zig");
 const Database = database.Database;
 
+/// Recorded in the runs table; .version in build.zig.zon, passed in by build.zig
+const tool_version = @import("build_options").version;
+
 pub fn main() !void {
     var gpa = std.heap.GeneralPurposeAllocator(.{}){};
     defer _ = gpa.deinit();
@@ -17,10 +30,56 @@ pub fn main() !void {
         std.process.exit(1);
     }
 
+    // Debug subcommand: show how a single line parses, without touching a database
+    if (std.mem.eql(u8, args[1], "explain-line")) {
+        if (args.len != 3) {
+            try std.fs.File.stdout().writeAll("Error: explain-line requires exactly one line argument\n");
+            std.process.exit(1);
+        }
+        var stdout_buffer: [4096]u8 = undefined;
+        var stdout_writer = std.fs.File.stdout().writer(&stdout_buffer);
+        const parsed = try explain.explainLine(allocator, &stdout_writer.interface, args[2]);
+        try stdout_writer.interface.flush();
+        std.process.exit(if (parsed) 0 else 1);
+    }
+
     // Default output database
     var output_db: []const u8 = "strace.db";
+    var verify = false;
+    var append = false;
+    var dedupe = false;
+    var dry_run = false;
+    var dry_schema_check = false;
+    var query_only = false;
+    var follow = false;
+    var quiet = false;
+    var verbose = false;
+    var optimize = false;
+    var run_id: ?[]const u8 = null;
+    var jsonl_out: ?[]const u8 = null;
+    var export_parquet: ?[]const u8 = null;
+    var export_csv: ?[]const u8 = null;
+    var export_jsonl: ?[]const u8 = null;
+    var stats_json: ?[]const u8 = null;
+    var split_output: ?[]const u8 = null;
+    var merge_output: ?[]const u8 = null;
+    var proc_dir: ?[]const u8 = null;
+    var rejects_out: ?[]const u8 = null;
+    var aggregate = false;
+    var process_tree = false;
+    var summary_top: ?usize = null;
+    var time_summary_top: ?usize = null;
+    var threads: usize = 0; // 0 = one worker per CPU
+    var db_settings = database.Settings{};
+    var process_options = types.ProcessOptions{};
     var trace_files = std.ArrayListUnmanaged([]const u8){};
     defer trace_files.deinit(allocator);
+    var include_syscalls = std.ArrayListUnmanaged([]const u8){};
+    defer include_syscalls.deinit(allocator);
+    var exclude_syscalls = std.ArrayListUnmanaged([]const u8){};
+    defer exclude_syscalls.deinit(allocator);
+    var routes = std.ArrayListUnmanaged(types.Route){};
+    defer routes.deinit(allocator);
 
     // Parse arguments
     var i: usize = 1;
@@ -35,6 +94,336 @@ pub fn main() !void {
                 std.process.exit(1);
             }
             output_db = args[i];
+        } else if (std.mem.eql(u8, arg, "--jsonl-out")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --jsonl-out requires an argument\n");
+                std.process.exit(1);
+            }
+            jsonl_out = args[i];
+        } else if (std.mem.eql(u8, arg, "--export-parquet")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --export-parquet requires an argument\n");
+                std.process.exit(1);
+            }
+            export_parquet = args[i];
+        } else if (std.mem.eql(u8, arg, "--export-csv")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --export-csv requires an argument\n");
+                std.process.exit(1);
+            }
+            export_csv = args[i];
+        } else if (std.mem.eql(u8, arg, "--export-jsonl")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --export-jsonl requires an argument\n");
+                std.process.exit(1);
+            }
+            export_jsonl = args[i];
+        } else if (std.mem.eql(u8, arg, "--stats-json")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --stats-json requires an argument\n");
+                std.process.exit(1);
+            }
+            stats_json = args[i];
+        } else if (std.mem.eql(u8, arg, "--merge")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --merge requires an output database\n");
+                std.process.exit(1);
+            }
+            merge_output = args[i];
+        } else if (std.mem.eql(u8, arg, "--split-output")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --split-output requires an argument\n");
+                std.process.exit(1);
+            }
+            split_output = args[i];
+        } else if (std.mem.eql(u8, arg, "--rejects")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --rejects requires an argument\n");
+                std.process.exit(1);
+            }
+            rejects_out = args[i];
+        } else if (std.mem.eql(u8, arg, "--proc-dir")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --proc-dir requires an argument\n");
+                std.process.exit(1);
+            }
+            proc_dir = args[i];
+        } else if (std.mem.eql(u8, arg, "--run-id")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --run-id requires an argument\n");
+                std.process.exit(1);
+            }
+            run_id = args[i];
+        } else if (std.mem.eql(u8, arg, "--table")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --table requires an argument\n");
+                std.process.exit(1);
+            }
+            if (!database.isValidTableName(args[i])) {
+                std.debug.print("Error: Invalid table name for --table: {s} (expected letters, digits and _, not starting with a digit; at most {d} characters; not a name the tool uses itself)\n", .{ args[i], database.max_table_name_len });
+                std.process.exit(1);
+            }
+            db_settings.table = args[i];
+        } else if (std.mem.eql(u8, arg, "--route")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --route requires an argument\n");
+                std.process.exit(1);
+            }
+            // The table name can't hold '=', so the last one ends the pattern
+            const separator = std.mem.lastIndexOfScalar(u8, args[i], '=') orelse 0;
+            const table = args[i][separator + 1 ..];
+            if (separator == 0 or !database.isValidTableName(table)) {
+                std.debug.print("Error: Invalid --route: {s} (expected pattern=table, with a table name like --table's)\n", .{args[i]});
+                std.process.exit(1);
+            }
+            try routes.append(allocator, .{ .pattern = args[i][0..separator], .table = table });
+        } else if (std.mem.eql(u8, arg, "--threads")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --threads requires an argument\n");
+                std.process.exit(1);
+            }
+            threads = std.fmt.parseInt(usize, args[i], 10) catch {
+                std.debug.print("Error: Invalid thread count for --threads: {s} (expected an integer >= 1, or 0 for one per CPU)\n", .{args[i]});
+                std.process.exit(1);
+            };
+        } else if (std.mem.eql(u8, arg, "--db-memory-limit")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --db-memory-limit requires an argument\n");
+                std.process.exit(1);
+            }
+            if (!utils.isMemoryLimit(args[i])) {
+                std.debug.print("Error: Invalid size for --db-memory-limit: {s} (expected e.g. 4GB or 512MiB)\n", .{args[i]});
+                std.process.exit(1);
+            }
+            db_settings.memory_limit = args[i];
+        } else if (std.mem.eql(u8, arg, "--db-threads")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --db-threads requires an argument\n");
+                std.process.exit(1);
+            }
+            db_settings.threads = std.fmt.parseInt(usize, args[i], 10) catch null;
+            if (db_settings.threads == null or db_settings.threads.? == 0) {
+                std.debug.print("Error: Invalid thread count for --db-threads: {s} (expected an integer >= 1)\n", .{args[i]});
+                std.process.exit(1);
+            }
+        } else if (std.mem.eql(u8, arg, "--summary")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --summary requires an argument\n");
+                std.process.exit(1);
+            }
+            summary_top = std.fmt.parseInt(usize, args[i], 10) catch {
+                std.debug.print("Error: Invalid count for --summary: {s}\n", .{args[i]});
+                std.process.exit(1);
+            };
+        } else if (std.mem.eql(u8, arg, "--time-summary")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --time-summary requires an argument\n");
+                std.process.exit(1);
+            }
+            time_summary_top = std.fmt.parseInt(usize, args[i], 10) catch {
+                std.debug.print("Error: Invalid count for --time-summary: {s}\n", .{args[i]});
+                std.process.exit(1);
+            };
+        } else if (std.mem.eql(u8, arg, "--date")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --date requires an argument\n");
+                std.process.exit(1);
+            }
+            const days = utils.parseDate(args[i]) catch {
+                std.debug.print("Error: Invalid date for --date: {s} (expected YYYY-MM-DD)\n", .{args[i]});
+                std.process.exit(1);
+            };
+            process_options.base_date_us = days * std.time.us_per_day;
+        } else if (std.mem.eql(u8, arg, "--max-file-size")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --max-file-size requires an argument\n");
+                std.process.exit(1);
+            }
+            process_options.max_file_size = utils.parseSize(args[i]) catch {
+                std.debug.print("Error: Invalid size for --max-file-size: {s}\n", .{args[i]});
+                std.process.exit(1);
+            };
+        } else if (std.mem.eql(u8, arg, "--split-large-files")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --split-large-files requires an argument\n");
+                std.process.exit(1);
+            }
+            const chunk_size = utils.parseSize(args[i]) catch 0;
+            if (chunk_size == 0) {
+                std.debug.print("Error: Invalid size for --split-large-files: {s}\n", .{args[i]});
+                std.process.exit(1);
+            }
+            process_options.split_chunk_size = chunk_size;
+        } else if (std.mem.eql(u8, arg, "--duration-ns")) {
+            process_options.duration_ns = true;
+        } else if (std.mem.eql(u8, arg, "--max-args-len")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --max-args-len requires an argument\n");
+                std.process.exit(1);
+            }
+            const max_len = utils.parseSize(args[i]) catch 0;
+            if (max_len == 0) {
+                std.debug.print("Error: Invalid length for --max-args-len: {s} (expected bytes >= 1)\n", .{args[i]});
+                std.process.exit(1);
+            }
+            process_options.max_args_len = @intCast(max_len);
+        } else if (std.mem.eql(u8, arg, "--flush-every")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --flush-every requires an argument\n");
+                std.process.exit(1);
+            }
+            const rows = std.fmt.parseInt(usize, args[i], 10) catch 0;
+            if (rows == 0) {
+                std.debug.print("Error: Invalid row count for --flush-every: {s} (expected an integer >= 1)\n", .{args[i]});
+                std.process.exit(1);
+            }
+            process_options.flush_every = rows;
+        } else if (std.mem.eql(u8, arg, "--min-duration")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --min-duration requires an argument\n");
+                std.process.exit(1);
+            }
+            const seconds = std.fmt.parseFloat(f64, args[i]) catch -1;
+            if (!(seconds >= 0) or std.math.isInf(seconds)) {
+                std.debug.print("Error: Invalid duration for --min-duration: {s} (expected seconds >= 0, e.g. 0.01)\n", .{args[i]});
+                std.process.exit(1);
+            }
+            process_options.min_duration = seconds;
+        } else if (std.mem.eql(u8, arg, "--time-format")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --time-format requires an argument\n");
+                std.process.exit(1);
+            }
+            process_options.time_format = std.meta.stringToEnum(types.TimeFormat, args[i]) orelse {
+                std.debug.print("Error: Unsupported --time-format value: {s} (supported: auto, clock, relative, epoch)\n", .{args[i]});
+                std.process.exit(1);
+            };
+        } else if (std.mem.eql(u8, arg, "--partition-by")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --partition-by requires an argument\n");
+                std.process.exit(1);
+            }
+            if (!std.mem.eql(u8, args[i], "hour")) {
+                std.debug.print("Error: Unsupported --partition-by value: {s} (supported: hour)\n", .{args[i]});
+                std.process.exit(1);
+            }
+            process_options.partition_by_hour = true;
+        } else if (std.mem.eql(u8, arg, "--aggregate")) {
+            aggregate = true;
+        } else if (std.mem.eql(u8, arg, "--aggregate-only")) {
+            aggregate = true;
+            process_options.store_rows = false;
+        } else if (std.mem.eql(u8, arg, "--process-tree")) {
+            process_tree = true;
+        } else if (std.mem.eql(u8, arg, "--append")) {
+            append = true;
+        } else if (std.mem.eql(u8, arg, "--incremental")) {
+            // Previous hashes live in the output database, so it must be kept
+            append = true;
+            process_options.incremental = true;
+        } else if (std.mem.eql(u8, arg, "--quiet") or std.mem.eql(u8, arg, "-q")) {
+            quiet = true;
+        } else if (std.mem.eql(u8, arg, "--verbose") or std.mem.eql(u8, arg, "-v")) {
+            verbose = true;
+        } else if (std.mem.eql(u8, arg, "--follow")) {
+            follow = true;
+        } else if (std.mem.eql(u8, arg, "--optimize")) {
+            optimize = true;
+        } else if (std.mem.eql(u8, arg, "--dedupe")) {
+            dedupe = true;
+        } else if (std.mem.eql(u8, arg, "--dry-run")) {
+            dry_run = true;
+        } else if (std.mem.eql(u8, arg, "--verify")) {
+            verify = true;
+        } else if (std.mem.eql(u8, arg, "--dry-schema-check")) {
+            dry_schema_check = true;
+        } else if (std.mem.eql(u8, arg, "--query-only")) {
+            query_only = true;
+        } else if (std.mem.eql(u8, arg, "--keep-raw")) {
+            process_options.keep_raw = true;
+        } else if (std.mem.eql(u8, arg, "--strip-ansi")) {
+            process_options.strip_ansi = true;
+        } else if (std.mem.eql(u8, arg, "--extract-signals")) {
+            process_options.extract_signals = true;
+        } else if (std.mem.eql(u8, arg, "--extract-dup")) {
+            process_options.extract_dup = true;
+        } else if (std.mem.eql(u8, arg, "--extract-accept")) {
+            process_options.extract_accept = true;
+        } else if (std.mem.eql(u8, arg, "--extract-clock")) {
+            process_options.extract_clock = true;
+        } else if (std.mem.eql(u8, arg, "--extract-listen")) {
+            process_options.extract_listen = true;
+        } else if (std.mem.eql(u8, arg, "--extract-fd")) {
+            process_options.extract_fd = true;
+        } else if (std.mem.eql(u8, arg, "--decode-mmap")) {
+            process_options.decode_mmap = true;
+        } else if (std.mem.eql(u8, arg, "--decode-mode")) {
+            process_options.decode_mode = true;
+        } else if (std.mem.eql(u8, arg, "--decode-sockaddr")) {
+            process_options.decode_sockaddr = true;
+        } else if (std.mem.eql(u8, arg, "--args-json")) {
+            process_options.args_json = true;
+        } else if (std.mem.eql(u8, arg, "--normalize-hex-addrs")) {
+            process_options.normalize_hex_addrs = true;
+        } else if (std.mem.eql(u8, arg, "--reassemble")) {
+            process_options.reassemble = true;
+        } else if (std.mem.eql(u8, arg, "--keep-fragments")) {
+            process_options.keep_fragments = true;
+        } else if (std.mem.eql(u8, arg, "--include-syscall")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --include-syscall requires an argument\n");
+                std.process.exit(1);
+            }
+            try include_syscalls.append(allocator, args[i]);
+        } else if (std.mem.eql(u8, arg, "--exclude-syscall")) {
+            i += 1;
+            if (i >= args.len) {
+                try std.fs.File.stdout().writeAll("Error: --exclude-syscall requires an argument\n");
+                std.process.exit(1);
+            }
+            try exclude_syscalls.append(allocator, args[i]);
+        } else if (std.mem.eql(u8, arg, "--only-unfinished") or std.mem.eql(u8, arg, "--only-completed")) {
+            const filter: types.RowFilter = if (std.mem.eql(u8, arg, "--only-unfinished")) .unfinished else .completed;
+            if (process_options.row_filter != .all and process_options.row_filter != filter) {
+                try std.fs.File.stdout().writeAll("Error: --only-unfinished and --only-completed are mutually exclusive\n");
+                std.process.exit(1);
+            }
+            process_options.row_filter = filter;
+        } else if (std.mem.eql(u8, arg, file_processor.stdin_path) or std.mem.eql(u8, arg, "--stdin")) {
+            // Standard input can only be consumed once
+            for (trace_files.items) |existing| {
+                if (std.mem.eql(u8, existing, file_processor.stdin_path)) {
+                    try std.fs.File.stdout().writeAll("Error: stdin ('-' or --stdin) can only be given once\n");
+                    std.process.exit(1);
+                }
+            }
+            try trace_files.append(allocator, file_processor.stdin_path);
         } else if (std.mem.eql(u8, arg, "-h") or std.mem.eql(u8, arg, "--help")) {
             try printUsage(args[0]);
             std.process.exit(0);
@@ -44,76 +433,989 @@ pub fn main() !void {
         }
     }
 
-    if (trace_files.items.len == 0) {
+    process_options.syscall_filter = .{ .include = include_syscalls.items, .exclude = exclude_syscalls.items };
+    process_options.routes = routes.items;
+    if (include_syscalls.items.len > 0 and exclude_syscalls.items.len > 0) {
+        std.debug.print("Warning: --exclude-syscall is ignored when --include-syscall is given\n", .{});
+    }
+
+    if (quiet and verbose) {
+        try std.fs.File.stdout().writeAll("Error: --quiet and --verbose can't be combined\n");
+        std.process.exit(1);
+    }
+    log.level = if (quiet) .quiet else if (verbose) .verbose else .normal;
+
+    if (process_options.keep_fragments and !process_options.reassemble) {
+        try std.fs.File.stdout().writeAll("Error: --keep-fragments requires --reassemble\n");
+        std.process.exit(1);
+    }
+
+    // A file's hash covers all of it, but a split file is processed range by range
+    if (process_options.incremental and process_options.split_chunk_size != null) {
+        try std.fs.File.stdout().writeAll("Error: --incremental can't be combined with --split-large-files\n");
+        std.process.exit(1);
+    }
+
+    // A followed file never ends, so it can't be hashed or cut into ranges
+    if (follow and (process_options.incremental or process_options.split_chunk_size != null)) {
+        try std.fs.File.stdout().writeAll("Error: --follow can't be combined with --incremental or --split-large-files\n");
+        std.process.exit(1);
+    }
+
+    // Pairing and midnight tracking follow a file from its first line, which a later range never sees
+    if (process_options.split_chunk_size != null and (process_options.reassemble or process_options.base_date_us != null)) {
+        try std.fs.File.stdout().writeAll("Error: --split-large-files can't be combined with --reassemble or --date\n");
+        std.process.exit(1);
+    }
+
+    if (dry_run and (append or dedupe or optimize or aggregate or process_tree or verify or export_parquet != null or export_csv != null or export_jsonl != null or summary_top != null or time_summary_top != null)) {
+        try std.fs.File.stdout().writeAll("Error: --dry-run writes no database, so it can't be combined with --append, --incremental, --dedupe, --optimize, --aggregate, --process-tree, --verify, --summary, --time-summary or --export-*\n");
+        std.process.exit(1);
+    }
+
+    // Combine databases built by earlier runs; the positional arguments are databases, not traces
+    if (merge_output) |output| {
+        if (follow or dry_run or dry_schema_check or query_only or split_output != null) {
+            try std.fs.File.stdout().writeAll("Error: --merge can't be combined with --follow, --dry-run, --dry-schema-check, --query-only or --split-output\n");
+            std.process.exit(1);
+        }
+        try runMerge(allocator, output, trace_files.items, db_settings, append);
+        return;
+    }
+
+    // Read-only compatibility check against an existing database, no ingest
+    if (dry_schema_check) {
+        const passed = try runSchemaCheck(allocator, output_db, db_settings.table, false);
+        std.process.exit(if (passed) 0 else 1);
+    }
+
+    // Statistics of an already-built database, opened read-only, no ingest
+    if (query_only) {
+        if (trace_files.items.len > 0) {
+            try std.fs.File.stdout().writeAll("Error: --query-only reads an existing database and takes no trace files\n");
+            std.process.exit(1);
+        }
+        const found = try runQueryOnly(allocator, output_db, db_settings.table, summary_top, time_summary_top);
+        std.process.exit(if (found) 0 else 1);
+    }
+
+    // Directories and glob patterns become the sorted list of files they contain
+    var expansion = try inputs.expand(allocator, trace_files.items);
+    defer expansion.deinit();
+    const input_files = expansion.paths;
+    if (expansion.unmatched > 0) {
+        std.debug.print("Warning: {d} directory or glob argument(s) matched no files\n", .{expansion.unmatched});
+    }
+
+    if (input_files.len == 0) {
         try std.fs.File.stdout().writeAll("Error: No trace files specified\n\n");
         try printUsage(args[0]);
         std.process.exit(1);
     }
+    log.print("Discovered {d} trace file(s)\n", .{input_files.len});
+
+    // Polling needs a plain file that can be read again past its current end
+    if (follow) {
+        const path = input_files[0];
+        if (input_files.len != 1 or std.mem.eql(u8, path, file_processor.stdin_path) or try file_processor.detectCompression(path) != .none) {
+            try std.fs.File.stdout().writeAll("Error: --follow requires exactly one uncompressed trace file\n");
+            std.process.exit(1);
+        }
+    }
+
+    // One database per input file instead of one for all of them
+    if (split_output) |dir| {
+        if (follow or dry_run or append or dedupe or optimize or aggregate or process_tree or verify or jsonl_out != null or export_parquet != null or export_csv != null or export_jsonl != null or stats_json != null or rejects_out != null or proc_dir != null or summary_top != null or time_summary_top != null) {
+            try std.fs.File.stdout().writeAll("Error: --split-output writes a database per file, so it can't be combined with --follow, --dry-run, --append, --incremental, --dedupe, --optimize, --aggregate, --process-tree, --verify, --summary, --time-summary, --jsonl-out, --stats-json, --rejects, --proc-dir or --export-*\n");
+            std.process.exit(1);
+        }
+        try runSplitOutput(allocator, dir, input_files, db_settings, process_options, threads, run_id);
+        return;
+    }
+
+    // A dry run parses into a scratch in-memory database that never receives a row
+    if (dry_run) process_options.store_rows = false;
+    const db_path = if (dry_run) ":memory:" else output_db;
+
+    // Appending only makes sense into an existing database with the current column set
+    // A --table the database doesn't have yet is simply created
+    const db_exists = if (std.fs.cwd().access(output_db, .{})) |_| true else |_| false;
+    const appending = append and db_exists;
+    if (appending and !try runSchemaCheck(allocator, output_db, db_settings.table, true)) {
+        try std.fs.File.stdout().writeAll("Error: --append requires a database with the current schema\n");
+        std.process.exit(1);
+    }
+    if (appending) {
+        for (routes.items) |route| {
+            if (!try runSchemaCheck(allocator, output_db, route.table, true)) {
+                try std.fs.File.stdout().writeAll("Error: --append requires a database with the current schema\n");
+                std.process.exit(1);
+            }
+        }
+    }
 
     // Print what we're doing
-    try std.fs.File.stdout().writeAll("Creating database: ");
-    try std.fs.File.stdout().writeAll(output_db);
-    try std.fs.File.stdout().writeAll("\n");
+    if (dry_run) {
+        try log.write("Dry run: parsing only, no database is written\n");
+    } else {
+        try log.write(if (appending) "Appending to database: " else "Creating database: ");
+        try log.write(output_db);
+        try log.write("\n");
+    }
 
     // Delete existing database if it exists (overwrite mode)
-    std.fs.cwd().deleteFile(output_db) catch |err| {
-        if (err != error.FileNotFound) {
-            std.debug.print("Warning: Could not delete existing database: {}\n", .{err});
-        }
-    };
+    if (!append and !dry_run) {
+        std.fs.cwd().deleteFile(output_db) catch |err| {
+            if (err != error.FileNotFound) {
+                std.debug.print("Warning: Could not delete existing database: {}\n", .{err});
+            }
+        };
+    }
 
-    // Create database
-    var db = try Database.init(output_db);
+    // Phase timings for --stats-json start once the database is about to be opened
+    var run_timer = try std.time.Timer.start();
+
+    // Create database (schema and indexes use IF NOT EXISTS, so reopening is safe)
+    var db = Database.initWithSettings(db_path, db_settings) catch |err| switch (err) {
+        // DuckDB's message has already been printed
+        error.InvalidDatabaseSetting => std.process.exit(1),
+        else => return err,
+    };
     defer db.deinit();
 
-    try std.fs.File.stdout().writeAll("Database created successfully\n\n");
+    // Workers switch to these as their files require
+    for (routes.items) |route| try db.addTable(route.table);
+
+    // Rows already present, so --verify can check only what this run added
+    const rows_before = try countAllRows(&db, routes.items);
+
+    // Tag every row of this invocation so multiple runs can share one database
+    var run_id_buffer: [36]u8 = undefined;
+    db.run_id = run_id orelse utils.generateRunId(&run_id_buffer);
+    log.print("Run ID: {s}\n", .{db.run_id.?});
+    const started_at_us = std.time.microTimestamp();
+
+    if (!dry_run) {
+        try log.write(if (appending) "Database opened successfully\n\n" else "Database created successfully\n\n");
+    }
+
+    // Optional sinks that receive every row alongside the database
+    var extra_sinks = std.ArrayListUnmanaged(sink.SyscallSink){};
+    defer extra_sinks.deinit(allocator);
+
+    var jsonl_sink: ?*sink.JsonlSink = null;
+    defer if (jsonl_sink) |s| s.destroy();
+    if (jsonl_out) |path| {
+        jsonl_sink = try sink.JsonlSink.create(allocator, path);
+        try extra_sinks.append(allocator, jsonl_sink.?.sink());
+    }
+
+    var aggregating_sink = sink.AggregatingSink.init(allocator, &db);
+    defer aggregating_sink.deinit();
+    if (aggregate) {
+        try extra_sinks.append(allocator, aggregating_sink.sink());
+    }
+
+    var rejects_file: ?*rejects.RejectsFile = null;
+    defer if (rejects_file) |r| r.destroy();
+    if (rejects_out) |path| {
+        rejects_file = try rejects.RejectsFile.create(allocator, path);
+        process_options.rejects = rejects_file;
+    }
+
+    var file_log = report.FileLog.init(allocator);
+    defer file_log.deinit();
+    if (stats_json != null) process_options.file_log = &file_log;
+
+    var comm_lookup: ?proc.CommLookup = null;
+    defer if (comm_lookup) |*comms| comms.deinit();
+    if (proc_dir) |dir| {
+        comm_lookup = proc.CommLookup.init(allocator, dir);
+        process_options.comm_lookup = &comm_lookup.?;
+    }
 
     // Always use parallel processing (automatically uses 1 worker for single file)
-    const num_workers = @max(1, @min(try std.Thread.getCpuCount(), trace_files.items.len));
-    try std.fs.File.stdout().writeAll("Processing trace files...\n\n");
+    const max_workers = if (threads > 0) threads else try std.Thread.getCpuCount();
+    // Split files can keep every worker busy, so the pool caps workers at the number of ranges
+    const num_workers = if (process_options.split_chunk_size != null) @max(1, max_workers) else @max(1, @min(max_workers, input_files.len));
+    try log.write("Processing trace files...\n\n");
+    const open_s = @as(f64, @floatFromInt(run_timer.read())) / std.time.ns_per_s;
+    var timer = try std.time.Timer.start();
+
+    // Ctrl-C lets workers finish their current line and flush; --follow polls the flag itself
+    installInterruptHandler();
+    if (!follow) process_options.stop = &interrupted;
+
+    const stats = if (follow)
+        try followTrace(allocator, &db, input_files[0], process_options, extra_sinks.items)
+    else
+        try worker_pool.processFilesParallelWithOptions(
+            allocator,
+            &db,
+            input_files,
+            num_workers,
+            process_options,
+            extra_sinks.items,
+        );
+    restoreInterruptHandler();
+
+    const elapsed_s = @as(f64, @floatFromInt(timer.read())) / std.time.ns_per_s;
+
+    for (extra_sinks.items) |output| {
+        output.flush() catch |err| {
+            std.debug.print("Error: Failed to flush {s} sink: {}\n", .{ output.name, err });
+        };
+    }
+
+    // Duplicates are removed before anything is derived from the table
+    const duplicates_removed = if (dedupe) try db.removeDuplicateRows() else 0;
+
+    // Finalize: derived tables built from the complete syscalls table
+    if (process_tree) {
+        try db.buildProcessTree();
+    }
 
-    const stats = try worker_pool.processFilesParallel(
-        allocator,
-        &db,
-        trace_files.items,
-        num_workers,
-    );
+    // Provenance for this invocation; a bad run can be removed with DELETE ... WHERE run_id = ...
+    if (!dry_run) {
+        try db.recordRun(.{
+            .started_at_us = started_at_us,
+            .finished_at_us = std.time.microTimestamp(),
+            .tool_version = tool_version,
+            .file_count = stats.files_processed,
+            .total_lines = stats.total_lines,
+        });
+    }
 
-    try std.fs.File.stdout().writeAll("\n");
+    try log.write("\n");
 
     // Print summary
-    try std.fs.File.stdout().writeAll("\n=== Summary ===\n");
-    std.debug.print("Files processed: {}/{}\n", .{ stats.files_processed, trace_files.items.len });
-    std.debug.print("Total lines: {}\n", .{stats.total_lines});
-    std.debug.print("Total syscalls parsed: {}\n", .{stats.parsed_lines});
-    std.debug.print("Total failed lines: {}\n", .{stats.failed_lines});
-    std.debug.print("Database: {s}\n", .{output_db});
+    try log.write("\n=== Summary ===\n");
+    log.print("Files processed: {}/{}\n", .{ stats.files_processed, input_files.len });
+    if (stats.files_skipped > 0) {
+        log.print("Files skipped (over --max-file-size): {}\n", .{stats.files_skipped});
+    }
+    if (process_options.incremental) {
+        log.print("Files unchanged since a previous run (--incremental): {}\n", .{stats.files_unchanged});
+    }
+    log.print("Total lines: {}\n", .{stats.total_lines});
+    log.print("Total syscalls parsed: {}\n", .{stats.parsed_lines});
+    log.print("Total failed lines: {}\n", .{stats.failed_lines});
+    if (stats.filtered_lines > 0) {
+        log.print("Rows dropped by filters: {}\n", .{stats.filtered_lines});
+    }
+    if (stats.lossy_lines > 0) {
+        log.print("Lines with invalid UTF-8 (bytes replaced with U+FFFD): {}\n", .{stats.lossy_lines});
+    }
+    log.print("Elapsed: {d:.3}s ({d:.0} lines/s)\n", .{
+        elapsed_s,
+        if (elapsed_s > 0) @as(f64, @floatFromInt(stats.total_lines)) / elapsed_s else 0,
+    });
+    if (dry_run) {
+        const failed_ratio = if (stats.total_lines > 0) @as(f64, @floatFromInt(stats.failed_lines)) / @as(f64, @floatFromInt(stats.total_lines)) else 0;
+        log.print("Failed line ratio: {d:.2}%\n", .{failed_ratio * 100});
+        log.print("Database: none (dry run, DB insert time 0s)\n", .{});
+    } else {
+        log.print("Database: {s}\n", .{output_db});
+    }
+    if (jsonl_sink) |s| {
+        log.print("JSONL records: {} ({s})\n", .{ s.records, jsonl_out.? });
+    }
+    if (rejects_file) |r| {
+        log.print("Rejected lines: {} ({s})\n", .{ r.lines, rejects_out.? });
+    }
+    // With --quiet, a report on stdout is the only output there
+    const stats_to_stdout = if (stats_json) |path| std.mem.eql(u8, path, "-") else false;
+    if (!log.enabled(.normal) and !stats_to_stdout) {
+        // The one line --quiet keeps, on stdout for scripts
+        var count_buffer: [128]u8 = undefined;
+        try std.fs.File.stdout().writeAll(try std.fmt.bufPrint(&count_buffer, "{d} syscalls parsed ({d} failed lines)\n", .{ stats.parsed_lines, stats.failed_lines }));
+    }
+
+    if (stats.interrupted) {
+        std.debug.print("\nInterrupted: kept the {d} rows read before Ctrl-C; skipped exports and checks\n", .{stats.parsed_lines});
+        if (stats_json) |path| writeStatsJson(path, stats, phaseTimes(open_s, elapsed_s, run_timer.read()), &file_log, db.run_id, output_db);
+        // exit() skips defers, so close everything that buffers first
+        if (rejects_file) |r| r.destroy();
+        if (jsonl_sink) |s| s.destroy();
+        db.deinit();
+        std.process.exit(130);
+    }
+
+    if (dry_run) {
+        if (stats_json) |path| writeStatsJson(path, stats, phaseTimes(open_s, elapsed_s, run_timer.read()), &file_log, db.run_id, null);
+        try log.write("\nDry run complete\n");
+        return;
+    }
+
+    if (optimize) {
+        const size_before = databaseFileSize(output_db);
+        try db.checkpoint();
+        log.print("\nOptimized database: {d} -> {d} bytes (including write-ahead log)\n", .{ size_before, databaseFileSize(output_db) });
+    }
 
     // Database statistics
-    try std.fs.File.stdout().writeAll("\n=== Database Statistics ===\n");
+    try log.write("\n=== Database Statistics ===\n");
     const syscall_count = try db.getSyscallCount();
-    std.debug.print("Total syscalls in DB: {}\n", .{syscall_count});
+    log.print("Total syscalls in DB: {}\n", .{syscall_count});
+    for (routes.items, 0..) |route, index| {
+        if (isFirstRouteTo(routes.items, index, db.table)) {
+            log.print("Rows in routed table {s}: {}\n", .{ route.table, try db.countRowsIn(route.table) });
+        }
+    }
+    if (appending) {
+        log.print("Rows added by this run: {}\n", .{try countAllRows(&db, routes.items) - rows_before});
+    }
+    if (dedupe) {
+        log.print("Duplicate rows removed: {}\n", .{duplicates_removed});
+    }
 
     const unique_syscalls = try db.getUniqueSyscallCount();
-    std.debug.print("Unique syscalls: {}\n", .{unique_syscalls});
+    log.print("Unique syscalls: {}\n", .{unique_syscalls});
 
     const unique_pids = try db.getUniquePidCount();
-    std.debug.print("Unique PIDs: {}\n", .{unique_pids});
+    log.print("Unique PIDs: {}\n", .{unique_pids});
 
     const failed_syscalls = try db.getFailedSyscallCount();
-    std.debug.print("Failed syscalls: {}\n", .{failed_syscalls});
+    log.print("Failed syscalls: {}\n", .{failed_syscalls});
+
+    if (process_tree) {
+        log.print("Processes in process_tree: {}\n", .{try db.queryInt64("SELECT COUNT(*) FROM process_tree")});
+    }
+
+    if (summary_top) |top| {
+        try printSyscallCounts(allocator, &db, top);
+    }
+    if (time_summary_top) |top| {
+        try printTimeBySyscall(allocator, &db, top);
+    }
+
+    if (verify) {
+        // --aggregate-only stores no rows by design
+        const added_rows = if (process_options.store_rows) stats.parsed_lines else 0;
+        const expected_rows = rows_before + @as(i64, @intCast(added_rows)) - duplicates_removed;
+        const check = database.RowCountCheck{ .expected = expected_rows, .actual = try countAllRows(&db, routes.items) };
+        if (!check.passed()) {
+            std.debug.print("\nVerification FAILED: expected {d} rows, found {d} (delta {d})\n", .{
+                check.expected,
+                check.actual,
+                check.delta(),
+            });
+            std.process.exit(1);
+        }
+        log.print("\nVerification passed: {d} rows match parsed lines\n", .{check.actual});
+    }
+
+    if (export_parquet) |path| {
+        try exportRows(&db, path, "Parquet", Database.exportParquet);
+    }
+    if (export_csv) |path| {
+        try exportRows(&db, path, "CSV", Database.exportCsv);
+    }
+    if (export_jsonl) |path| {
+        try exportRows(&db, path, "JSON Lines", Database.exportJsonl);
+    }
+
+    if (stats_json) |path| writeStatsJson(path, stats, phaseTimes(open_s, elapsed_s, run_timer.read()), &file_log, db.run_id, output_db);
+
+    try log.write("\nSuccess!\n");
+}
+
+/// Set by the SIGINT handler: ends --follow, and stops workers otherwise
+var interrupted = std.atomic.Value(bool).init(false);
+
+fn handleInterrupt(_: i32) callconv(.c) void {
+    interrupted.store(true, .seq_cst);
+}
+
+/// Route Ctrl-C to `interrupted` instead of killing the process with unflushed appenders
+fn installInterruptHandler() void {
+    const action = std.posix.Sigaction{
+        .handler = .{ .handler = handleInterrupt },
+        .mask = std.posix.sigemptyset(),
+        // A second Ctrl-C kills the process as usual
+        .flags = std.posix.SA.RESETHAND,
+    };
+    std.posix.sigaction(std.posix.SIG.INT, &action, null);
+}
+
+/// Let Ctrl-C kill the process again once no appender is left to flush
+/// The finalize steps after ingest (dedupe, process tree, exports, ...) would otherwise
+/// swallow the first Ctrl-C, since nothing reads `interrupted` any more
+fn restoreInterruptHandler() void {
+    const action = std.posix.Sigaction{
+        .handler = .{ .handler = std.posix.SIG.DFL },
+        .mask = std.posix.sigemptyset(),
+        .flags = 0,
+    };
+    std.posix.sigaction(std.posix.SIG.INT, &action, null);
+}
+
+/// Ingest one growing trace file on the main connection until Ctrl-C (--follow)
+/// Rows become visible to queries as the file is read (see followFileToSinks)
+fn followTrace(
+    allocator: std.mem.Allocator,
+    db: *Database,
+    path: []const u8,
+    options: types.ProcessOptions,
+    extra_sinks: []const sink.SyscallSink,
+) !types.ParallelStats {
+    db.flush_every = options.flush_every;
+    // A matching --route takes the rows; the statistics afterwards are of the main table again
+    const main_table = db.table;
+    if (options.tableFor(path)) |table| db.table = table;
+    defer db.table = main_table;
+    if (options.store_rows) try db.beginAppend();
+    errdefer db.endAppend() catch {};
+
+    // The database first (unless rows are not stored), then any shared sinks
+    const db_sinks: usize = if (options.store_rows) 1 else 0;
+    const sinks = try allocator.alloc(sink.SyscallSink, db_sinks + extra_sinks.len);
+    defer allocator.free(sinks);
+    if (options.store_rows) sinks[0] = sink.databaseSink(db);
+    @memcpy(sinks[db_sinks..], extra_sinks);
+
+    log.print("Following {s}; press Ctrl-C to stop\n", .{path});
+    var timer = try std.time.Timer.start();
+    const stats = try file_processor.followFileToSinks(allocator, sinks, path, options, &interrupted);
+    try db.endAppend();
+    const duration_sec = @as(f64, @floatFromInt(timer.read())) / std.time.ns_per_s;
+    try db.recordFileStats(file_processor.traceFileName(path), stats, duration_sec);
+    if (options.file_log) |files| try files.record(path, stats, duration_sec);
+
+    return .{
+        .total_files = 1,
+        .files_processed = 1,
+        .total_lines = stats.total_lines,
+        .parsed_lines = stats.parsed_lines,
+        .failed_lines = stats.failed_lines,
+        .files_with_errors = 0,
+        .files_skipped = 0,
+        .lossy_lines = stats.lossy_lines,
+        .filtered_lines = stats.filtered_lines,
+    };
+}
+
+/// Load each input file into its own <dir>/<file name>.db (--split-output)
+/// Files are spread over worker threads; each worker opens the database of the file it
+/// takes and runs a pool over that file alone, so every instance still has a single owner
+/// (see CLAUDE.md on the concurrency model) and no two threads write the same file
+fn runSplitOutput(
+    allocator: std.mem.Allocator,
+    dir: []const u8,
+    input_files: []const []const u8,
+    settings: database.Settings,
+    base_options: types.ProcessOptions,
+    threads: usize,
+    run_id: ?[]const u8,
+) !void {
+    std.fs.cwd().makePath(dir) catch |err| {
+        std.debug.print("Error: Could not create --split-output directory {s}: {}\n", .{ dir, err });
+        std.process.exit(1);
+    };
+
+    const db_paths = try allocator.alloc([]u8, input_files.len);
+    var named: usize = 0;
+    defer {
+        for (db_paths[0..named]) |path| allocator.free(path);
+        allocator.free(db_paths);
+    }
+    for (input_files) |file| {
+        const name = file_processor.traceFileName(file);
+        const db_path = try std.fs.path.join(allocator, &.{ dir, name });
+        defer allocator.free(db_path);
+        db_paths[named] = try std.fmt.allocPrint(allocator, "{s}.db", .{db_path});
+        named += 1;
+
+        // Two inputs with one file name (a/trace.1, b/trace.1) would share a database
+        for (db_paths[0 .. named - 1]) |earlier| {
+            if (std.mem.eql(u8, earlier, db_paths[named - 1])) {
+                std.debug.print("Error: --split-output would write {s} for more than one input file\n", .{earlier});
+                std.process.exit(1);
+            }
+        }
+    }
+
+    // One invocation, so every database gets the same run_id
+    var run_id_buffer: [36]u8 = undefined;
+    const shared_run_id = run_id orelse utils.generateRunId(&run_id_buffer);
+    log.print("Run ID: {s}\n", .{shared_run_id});
+
+    installInterruptHandler();
+    var options = base_options;
+    options.stop = &interrupted;
+    // Several pools at once would draw over each other's progress bars
+    options.show_progress = false;
+
+    // Whole files go to the outer workers; CPUs left over split the ranges of one file
+    const max_workers = @max(1, if (threads > 0) threads else try std.Thread.getCpuCount());
+    const file_workers = @min(max_workers, input_files.len);
+    const range_workers = if (options.split_chunk_size != null) @max(1, max_workers / @max(1, file_workers)) else 1;
+
+    const results = try allocator.alloc(?types.ParallelStats, input_files.len);
+    defer allocator.free(results);
+    @memset(results, null);
+    const errors = try allocator.alloc(?anyerror, file_workers);
+    defer allocator.free(errors);
+    @memset(errors, null);
+
+    var next_file = std.atomic.Value(usize).init(0);
+    const workers = try allocator.alloc(std.Thread, file_workers);
+    defer allocator.free(workers);
+    for (workers, errors) |*worker, *error_slot| {
+        worker.* = try std.Thread.spawn(.{}, SplitOutputWorker.runWrapper, .{SplitOutputWorker{
+            .allocator = allocator,
+            .input_files = input_files,
+            .db_paths = db_paths,
+            .results = results,
+            .next_file = &next_file,
+            .settings = settings,
+            .options = options,
+            .num_workers = range_workers,
+            .run_id = shared_run_id,
+            .error_slot = error_slot,
+        }});
+    }
+    for (workers) |worker| worker.join();
+    restoreInterruptHandler();
+
+    for (errors) |maybe_err| {
+        if (maybe_err) |err| switch (err) {
+            // DuckDB's message has already been printed
+            error.InvalidDatabaseSetting => std.process.exit(1),
+            else => return err,
+        };
+    }
+
+    var totals = types.ParallelStats.init();
+    var written: usize = 0;
+    try log.write("\n=== Databases ===\n");
+    for (db_paths, results) |db_path, result| {
+        const stats = result orelse continue;
+        written += 1;
+        totals.files_processed += stats.files_processed;
+        totals.files_with_errors += stats.files_with_errors;
+        totals.total_lines += stats.total_lines;
+        totals.parsed_lines += stats.parsed_lines;
+        totals.failed_lines += stats.failed_lines;
+        try log.write(db_path);
+        try log.write("\n");
+    }
+    log.print("Files processed: {}/{}\n", .{ totals.files_processed, input_files.len });
+    log.print("Total syscalls parsed: {}\n", .{totals.parsed_lines});
+    log.print("Total failed lines: {}\n", .{totals.failed_lines});
+    if (!log.enabled(.normal)) {
+        // The one line --quiet keeps, on stdout for scripts
+        var count_buffer: [128]u8 = undefined;
+        try std.fs.File.stdout().writeAll(try std.fmt.bufPrint(&count_buffer, "{d} syscalls parsed ({d} failed lines) into {d} databases\n", .{ totals.parsed_lines, totals.failed_lines, written }));
+    }
+
+    if (options.stopRequested()) {
+        std.debug.print("\nInterrupted: {d} of {d} databases written; those being loaded at Ctrl-C hold the rows read before it\n", .{ written, input_files.len });
+        std.process.exit(130);
+    }
+}
 
-    try std.fs.File.stdout().writeAll("\nSuccess!\n");
+/// One --split-output thread: takes the next unclaimed file and loads it into its own database
+const SplitOutputWorker = struct {
+    allocator: std.mem.Allocator,
+    input_files: []const []const u8,
+    db_paths: []const []const u8,
+    /// One slot per input file, filled once its database is written
+    results: []?types.ParallelStats,
+    next_file: *std.atomic.Value(usize),
+    settings: database.Settings,
+    options: types.ProcessOptions,
+    /// Workers of the pool each file is loaded with (more than 1 only with --split-large-files)
+    num_workers: usize,
+    run_id: []const u8,
+    error_slot: *?anyerror,
+
+    fn runWrapper(self: SplitOutputWorker) void {
+        self.run() catch |err| {
+            self.error_slot.* = err;
+        };
+    }
+
+    fn run(self: SplitOutputWorker) !void {
+        while (!self.options.stopRequested()) {
+            const index = self.next_file.fetchAdd(1, .seq_cst);
+            if (index >= self.input_files.len) return;
+            const db_path = self.db_paths[index];
+
+            std.fs.cwd().deleteFile(db_path) catch |err| {
+                if (err != error.FileNotFound) {
+                    std.debug.print("Warning: Could not delete existing database: {}\n", .{err});
+                }
+            };
+
+            // The database is this thread's alone, so it owns the instance like main does in a normal run
+            var db = try Database.initWithSettings(db_path, self.settings);
+            defer db.deinit();
+            db.run_id = self.run_id;
+            for (self.options.routes) |route| try db.addTable(route.table);
+
+            const started_at_us = std.time.microTimestamp();
+            const files = [_][]const u8{self.input_files[index]};
+            const stats = try worker_pool.processFilesParallelWithOptions(self.allocator, &db, &files, self.num_workers, self.options, &.{});
+            try db.recordRun(.{
+                .started_at_us = started_at_us,
+                .finished_at_us = std.time.microTimestamp(),
+                .tool_version = tool_version,
+                .file_count = stats.files_processed,
+                .total_lines = stats.total_lines,
+            });
+            self.results[index] = stats;
+
+            log.print("{s}: {d} rows, {d} failed lines{s}\n", .{
+                db_path,
+                stats.parsed_lines,
+                stats.failed_lines,
+                if (stats.files_with_errors > 0) " (file could not be read)" else "",
+            });
+        }
+    }
+};
+
+/// Copy the rows table of each input database into output (--merge)
+/// Every input is schema-checked before anything is written, so a mismatch leaves output untouched
+fn runMerge(
+    allocator: std.mem.Allocator,
+    output: []const u8,
+    inputs: []const []const u8,
+    settings: database.Settings,
+    append: bool,
+) !void {
+    if (inputs.len == 0) {
+        try std.fs.File.stdout().writeAll("Error: --merge requires at least one input database\n");
+        std.process.exit(1);
+    }
+
+    for (inputs) |input| {
+        if (std.mem.eql(u8, input, output)) {
+            std.debug.print("Error: {s} is the --merge output and can't also be an input\n", .{input});
+            std.process.exit(1);
+        }
+        if (std.fs.cwd().access(input, .{})) |_| {} else |_| {
+            std.debug.print("Error: Database not found: {s}\n", .{input});
+            std.process.exit(1);
+        }
+        if (!try runSchemaCheck(allocator, input, settings.table, false)) {
+            std.debug.print("Error: Can't merge {s}: its {s} table doesn't match the current schema\n", .{ input, settings.table });
+            std.process.exit(1);
+        }
+    }
+
+    const output_exists = if (std.fs.cwd().access(output, .{})) |_| true else |_| false;
+    const appending = append and output_exists;
+    if (appending and !try runSchemaCheck(allocator, output, settings.table, true)) {
+        try std.fs.File.stdout().writeAll("Error: --append requires a database with the current schema\n");
+        std.process.exit(1);
+    }
+
+    // --route tables are merged along with the main table, so they are checked the same way
+    for (inputs) |input| {
+        const routed = try listRoutedTables(allocator, input, settings.table);
+        defer database.freeTableNames(allocator, routed);
+        for (routed) |table| {
+            if (!try runSchemaCheck(allocator, input, table, false)) {
+                std.debug.print("Error: Can't merge {s}: its {s} table doesn't match the current schema\n", .{ input, table });
+                std.process.exit(1);
+            }
+            if (appending and !try runSchemaCheck(allocator, output, table, true)) {
+                try std.fs.File.stdout().writeAll("Error: --append requires a database with the current schema\n");
+                std.process.exit(1);
+            }
+        }
+    }
+
+    if (!appending) {
+        std.fs.cwd().deleteFile(output) catch |err| {
+            if (err != error.FileNotFound) {
+                std.debug.print("Warning: Could not delete existing database: {}\n", .{err});
+            }
+        };
+    }
+
+    var db = Database.initWithSettings(output, settings) catch |err| switch (err) {
+        // DuckDB's message has already been printed
+        error.InvalidDatabaseSetting => std.process.exit(1),
+        else => return err,
+    };
+    defer db.deinit();
+
+    try log.write("\n=== Merging ===\n");
+    for (inputs) |input| {
+        const rows = db.mergeFrom(input) catch |err| {
+            std.debug.print("Error: Failed to merge {s}: {}\n", .{ input, err });
+            std.process.exit(1);
+        };
+        log.print("{s}: {d} rows\n", .{ input, rows });
+    }
+
+    const syscall_count = try db.getSyscallCount();
+    log.print("Databases merged: {d}\n", .{inputs.len});
+    log.print("Total syscalls in DB: {}\n", .{syscall_count});
+    const routed_tables = try db.routedTables(allocator);
+    defer database.freeTableNames(allocator, routed_tables);
+    for (routed_tables) |table| {
+        log.print("Rows in routed table {s}: {}\n", .{ table, try db.countRowsIn(table) });
+    }
+    if (!log.enabled(.normal)) {
+        // The one line --quiet keeps, on stdout for scripts; the table name may be up to max_table_name_len
+        var count_buffer: [64 + database.max_table_name_len]u8 = undefined;
+        try std.fs.File.stdout().writeAll(try std.fmt.bufPrint(&count_buffer, "{d} syscalls in {s}\n", .{ syscall_count, settings.table }));
+    }
+}
+
+/// The --route tables of an existing database (see Database.routedTables)
+fn listRoutedTables(allocator: std.mem.Allocator, db_path: []const u8, table: [:0]const u8) ![][:0]u8 {
+    var db = Database.openExisting(db_path) catch |err| {
+        std.debug.print("Error: Could not open database {s}: {}\n", .{ db_path, err });
+        std.process.exit(1);
+    };
+    defer db.deinit();
+    db.table = table;
+    return db.routedTables(allocator);
+}
+
+/// Rows in the main table plus each distinct --route table
+fn countAllRows(db: *Database, routes: []const types.Route) !i64 {
+    var total = try db.getSyscallCount();
+    for (routes, 0..) |route, index| {
+        if (isFirstRouteTo(routes, index, db.table)) total += try db.countRowsIn(route.table);
+    }
+    return total;
+}
+
+/// Whether routes[index] is the first route to its table, and that table isn't main_table
+/// Several patterns may share a table, which must only be counted once
+fn isFirstRouteTo(routes: []const types.Route, index: usize, main_table: []const u8) bool {
+    const table = routes[index].table;
+    if (std.ascii.eqlIgnoreCase(table, main_table)) return false;
+    for (routes[0..index]) |earlier| {
+        if (std.ascii.eqlIgnoreCase(earlier.table, table)) return false;
+    }
+    return true;
+}
+
+/// Phases of a run so far, from the open and ingest times and the run timer's total in nanoseconds
+fn phaseTimes(open_s: f64, ingest_s: f64, total_ns: u64) report.Phases {
+    const total_s = @as(f64, @floatFromInt(total_ns)) / std.time.ns_per_s;
+    return .{
+        .open_sec = open_s,
+        .ingest_sec = ingest_s,
+        .finalize_sec = @max(0, total_s - open_s - ingest_s),
+        .total_sec = total_s,
+    };
+}
+
+/// Write the --stats-json report to path ("-" for stdout)
+fn writeStatsJson(
+    path: []const u8,
+    stats: types.ParallelStats,
+    phases: report.Phases,
+    file_log: *report.FileLog,
+    run_id: ?[]const u8,
+    db_path: ?[]const u8,
+) void {
+    var run_report = report.RunReport.init(stats, phases, file_log.sorted());
+    run_report.run_id = run_id;
+    run_report.database = db_path;
+    run_report.writeToPath(path) catch |err| {
+        std.debug.print("Error: Writing --stats-json report to {s} failed: {}\n", .{ path, err });
+        std.process.exit(1);
+    };
+}
+
+/// Bytes used by a database file and its write-ahead log; missing files count as 0
+fn databaseFileSize(path: []const u8) u64 {
+    var wal_path_buffer: [std.fs.max_path_bytes]u8 = undefined;
+    const db_size = if (std.fs.cwd().statFile(path)) |stat| stat.size else |_| 0;
+    const wal_path = std.fmt.bufPrint(&wal_path_buffer, "{s}.wal", .{path}) catch return db_size;
+    const wal_size = if (std.fs.cwd().statFile(wal_path)) |stat| stat.size else |_| 0;
+    return db_size + wal_size;
+}
+
+/// Print the most frequent syscalls (--summary)
+fn printSyscallCounts(allocator: std.mem.Allocator, db: *Database, top: usize) !void {
+    const counts = try db.syscallCounts(allocator);
+    defer database.freeSyscallRows(allocator, counts);
+
+    std.debug.print("\n=== Top {d} Syscalls ===\n", .{top});
+    for (counts[0..@min(top, counts.len)]) |row| {
+        std.debug.print("  {s:<20} {d}\n", .{ row.syscall, row.count });
+    }
+}
+
+/// Print the syscalls with the most total time in the kernel (--time-summary)
+fn printTimeBySyscall(allocator: std.mem.Allocator, db: *Database, top: usize) !void {
+    const times = try db.timeBySyscall(allocator);
+    defer database.freeSyscallRows(allocator, times);
+
+    std.debug.print("\n=== Top {d} Syscalls by Time ===\n", .{top});
+    for (times[0..@min(top, times.len)]) |row| {
+        std.debug.print("  {s:<20} {d:>12.6}s {d:>10} calls\n", .{ row.syscall, row.total_duration, row.count });
+    }
+}
+
+/// Run one of the Database export methods, noting when an existing file is replaced
+fn exportRows(
+    db: *Database,
+    path: []const u8,
+    format_name: []const u8,
+    comptime exportFn: anytype,
+) !void {
+    if (std.fs.cwd().access(path, .{})) |_| {
+        log.print("\nReplacing existing file: {s}\n", .{path});
+    } else |_| {}
+
+    const rows = exportFn(db, path) catch |err| {
+        std.debug.print("Error: {s} export to {s} failed: {}\n", .{ format_name, path, err });
+        std.process.exit(1);
+    };
+    log.print("\nExported {d} rows to {s} ({s})\n", .{ rows, path, format_name });
+}
+
+/// Compare the columns of table in an existing database against the current schema and print the differences
+/// Returns true when the database is compatible; with allow_new_table, also when table doesn't exist yet
+fn runSchemaCheck(allocator: std.mem.Allocator, db_path: []const u8, table: [:0]const u8, allow_new_table: bool) !bool {
+    var db = Database.openExisting(db_path) catch |err| {
+        std.debug.print("Error: Could not open database {s}: {}\n", .{ db_path, err });
+        return false;
+    };
+    defer db.deinit();
+    db.table = table;
+
+    if (allow_new_table and !try db.tableExists()) {
+        log.print("Table {s} is new to {s} and will be created\n", .{ table, db_path });
+        return true;
+    }
+
+    var check = try db.checkSchema(allocator);
+    defer check.deinit();
+
+    std.debug.print("Schema check: {s} (table {s})\n", .{ db_path, table });
+    if (check.version) |version| {
+        std.debug.print("Schema version: {d} (current: {d})\n", .{ version, database.schema_version });
+    } else {
+        std.debug.print("Schema version: unversioned (current: {d})\n", .{database.schema_version});
+    }
+
+    for (check.missing) |name| {
+        std.debug.print("  missing column: {s}\n", .{name});
+    }
+    for (check.extra) |name| {
+        std.debug.print("  extra column: {s}\n", .{name});
+    }
+
+    if (check.passed()) {
+        std.debug.print("Schema compatible\n", .{});
+    } else {
+        std.debug.print("Schema INCOMPATIBLE: {d} missing, {d} extra\n", .{ check.missing.len, check.extra.len });
+    }
+    return check.passed();
+}
+
+/// Print the statistics and --summary/--time-summary reports of an existing database (--query-only)
+/// Returns false when the database could not be opened
+fn runQueryOnly(allocator: std.mem.Allocator, db_path: []const u8, table: [:0]const u8, summary_top: ?usize, time_summary_top: ?usize) !bool {
+    var db = Database.openReadOnly(db_path, table) catch |err| {
+        switch (err) {
+            error.DatabaseNotFound => std.debug.print("Error: Database not found: {s}\n", .{db_path}),
+            error.SyscallsTableMissing => std.debug.print("Error: {s} has no {s} table (not built by strace-to-duckdb, or a different --table?)\n", .{ db_path, table }),
+            else => std.debug.print("Error: Could not open database {s} read-only: {}\n", .{ db_path, err }),
+        }
+        return false;
+    };
+    defer db.deinit();
+
+    try std.fs.File.stdout().writeAll("=== Database Statistics ===\n");
+    std.debug.print("Database: {s} (read-only)\n", .{db_path});
+    std.debug.print("Total syscalls in DB: {}\n", .{try db.getSyscallCount()});
+    std.debug.print("Unique syscalls: {}\n", .{try db.getUniqueSyscallCount()});
+    std.debug.print("Unique PIDs: {}\n", .{try db.getUniquePidCount()});
+    std.debug.print("Failed syscalls: {}\n", .{try db.getFailedSyscallCount()});
+
+    if (summary_top) |top| {
+        try printSyscallCounts(allocator, &db, top);
+    }
+    if (time_summary_top) |top| {
+        try printTimeBySyscall(allocator, &db, top);
+    }
+    return true;
 }
 
 fn printUsage(program_name: []const u8) !void {
     const usage =
         \\Usage: {s} [OPTIONS] <trace_files...>
+        \\       {s} explain-line '<strace line>'
+        \\       {s} --merge <out.db> <in.db...>
         \\
         \\Parse strace output files and load them into a DuckDB database.
+        \\Directories are searched recursively; quoted globs ('traces/*.trace') are expanded.
         \\
         \\Options:
         \\  -o, --output <file>  Output database file (default: strace.db)
+        \\  --jsonl-out <file>   Also write every row as JSON Lines
+        \\  --export-parquet <f> Write the syscalls table to a Parquet file after ingest
+        \\  --export-csv <file>  Write the syscalls table to a CSV file (with header) after ingest
+        \\  --export-jsonl <f>   Write the syscalls table as JSON Lines after ingest
+        \\  --stats-json <file>  Write run statistics (counts, phase times, per-file stats) as JSON; - for stdout
+        \\  --split-output <dir> Write each input file to its own <dir>/<file name>.db instead of -o
+        \\  --merge <out.db>     Copy the rows of the databases given as arguments into out.db
+        \\  --rejects <file>     Write lines that fail to parse as <trace_file>:<line>: <text>
+        \\  --proc-dir <dir>     Fill command from <dir>/<pid>/comm (a /proc snapshot)
+        \\  --run-id <id>        Tag stored in run_id on every row (default: random UUID)
+        \\  --table <name>       Table rows are loaded into and queried from (default: syscalls)
+        \\  --route <pat>=<table> Load files matching pat (* and ? wildcards) into table instead (repeatable)
+        \\  --threads <n>        Worker threads (default: 0 = one per CPU)
+        \\  --db-memory-limit <s> DuckDB memory_limit, e.g. 4GB (default: DuckDB's own)
+        \\  --db-threads <n>     Threads DuckDB itself may use (default: DuckDB's own)
+        \\  --date <YYYY-MM-DD>  Base date for -t/-tt times; fills wall_time (rolls over at midnight)
+        \\  --flush-every <n>    Flush each worker's appender every n rows (default: only at the end)
+        \\  --split-large-files <size> Split plain files over <size> into line-aligned ranges across workers
+        \\  --max-file-size <n>  Skip files larger than n bytes (accepts K, MB, G suffixes)
+        \\  --partition-by hour  Store the hour-of-day of each timestamp in the hour column
+        \\  --time-format <fmt>  Leading time field: auto (default), clock (-t/-tt), relative (-r) or epoch (-ttt)
+        \\  --aggregate          Also write per-syscall totals to syscall_aggregates
+        \\  --aggregate-only     Write syscall_aggregates without storing any rows
+        \\  --process-tree       Build a process_tree table from clone/fork results after ingest
+        \\  --append             Add rows to an existing output database instead of replacing it
+        \\  --incremental        Like --append, but skip files already loaded with the same contents
+        \\  --follow             Keep reading one growing trace file (like tail -f) until Ctrl-C
+        \\  --dedupe             Remove rows identical in every column but run_id after loading
+        \\  --optimize           Checkpoint the database after loading (folds in the WAL; reports file size)
+        \\  --dry-run            Parse and report statistics without writing a database
+        \\  --summary <n>        Print the n most frequent syscalls after loading
+        \\  --time-summary <n>   Print the n syscalls with the most total duration after loading
+        \\  --verify             Check row count against parsed lines after ingest
+        \\  --dry-schema-check   Compare an existing output database against the current schema
+        \\  --query-only         Print statistics (and --summary/--time-summary) of an existing database, read-only
+        \\  --keep-raw           Store each unmodified source line in raw_line (larger database)
+        \\  --strip-ansi         Remove ANSI escape sequences before parsing
+        \\  --extract-signals    Decode target_pid/signal for kill, tkill, tgkill, rt_sigqueueinfo
+        \\  --extract-dup        Decode oldfd/newfd for dup, dup2, dup3
+        \\  --extract-accept     Decode peer_addr/peer_port for accept, accept4
+        \\  --extract-clock      Decode clock_value_ns/clock_id for clock_gettime, gettimeofday
+        \\  --extract-listen     Decode bind_addr/bind_port for bind and backlog for listen
+        \\  --extract-fd         Decode fd from a bare integer first argument
+        \\  --decode-mmap        Decode mmap_prot/mmap_flags for mmap, mmap2, mremap
+        \\  --decode-mode        Decode the octal mode of open, openat, creat, chmod, mkdir and friends
+        \\  --decode-sockaddr    Decode sa_family/sock_addr/sock_port for connect, bind, accept, accept4
+        \\  --args-json          Store the top-level arguments as a JSON array in args_json
+        \\  --duration-ns        Also store each duration as exact integer nanoseconds in duration_ns
+        \\  --max-args-len <n>   Store at most n bytes of args (accepts K, MB suffixes); sets args_truncated
+        \\  --normalize-hex-addrs Replace pointer-like hex values in args with 0x<ptr> (original in args_raw)
+        \\  --reassemble         Store each unfinished/resumed pair as one row (reassembled = true)
+        \\  --keep-fragments     With --reassemble, also keep the original unfinished/resumed rows
+        \\  --include-syscall <name> Keep only these syscalls (repeatable; * and ? wildcards)
+        \\  --exclude-syscall <name> Drop these syscalls (repeatable; ignored with --include-syscall)
+        \\  --min-duration <s>   Keep only syscalls taking at least s seconds (drops rows without -T durations)
+        \\  --only-unfinished    Keep only unfinished/resumed rows (debugging)
+        \\  --only-completed     Keep only rows completed on a single line (debugging)
+        \\  -, --stdin           Read a trace from standard input (trace_file = stdin, pid = 0)
+        \\  -q, --quiet          Print only the final row count (errors and warnings still shown)
+        \\  -v, --verbose        Also print samples of lines that did not parse
         \\  -h, --help           Show this help message
         \\
         \\Examples:
@@ -123,5 +1425,5 @@ fn printUsage(program_name: []const u8) !void {
         \\
     ;
 
-    std.debug.print(usage, .{ program_name, program_name, program_name, program_name });
+    std.debug.print(usage, .{ program_name, program_name, program_name, program_name, program_name, program_name });
 }
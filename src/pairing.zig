@@ -0,0 +1,227 @@
+const std = @import("std");
+const types = @import("types.zig");
+const parser = @import("parser.zig");
+const Syscall = types.Syscall;
+
+/// Unfinished call waiting for its resumed line
+/// The row is an owned copy because the line buffer is reused for every line
+pub const Pending = struct {
+    row: Syscall,
+    /// Backing storage for every string in row
+    bytes: []u8,
+
+    fn init(allocator: std.mem.Allocator, syscall: Syscall) !Pending {
+        var size: usize = 0;
+        inline for (std.meta.fields(Syscall)) |field| {
+            if (field.type == []const u8) {
+                size += @field(syscall, field.name).len;
+            } else if (field.type == ?[]const u8) {
+                if (@field(syscall, field.name)) |text| size += text.len;
+            }
+        }
+
+        const bytes = try allocator.alloc(u8, size);
+        var row = syscall;
+        var used: usize = 0;
+        inline for (std.meta.fields(Syscall)) |field| {
+            if (field.type == []const u8) {
+                @field(row, field.name) = copyInto(bytes, &used, @field(syscall, field.name));
+            } else if (field.type == ?[]const u8) {
+                if (@field(syscall, field.name)) |text| @field(row, field.name) = copyInto(bytes, &used, text);
+            }
+        }
+        return .{ .row = row, .bytes = bytes };
+    }
+
+    fn copyInto(bytes: []u8, used: *usize, text: []const u8) []const u8 {
+        const dest = bytes[used.*..][0..text.len];
+        @memcpy(dest, text);
+        used.* += text.len;
+        return dest;
+    }
+
+    pub fn deinit(self: Pending, allocator: std.mem.Allocator) void {
+        allocator.free(self.bytes);
+    }
+};
+
+/// Unfinished call that never saw its resumed line
+pub const Orphan = struct {
+    pid: i32,
+    syscall: []const u8,
+    pending: Pending,
+};
+
+/// Pairing key: a resumed line can only belong to an unfinished call
+/// of the same syscall on the same PID
+const Key = struct {
+    pid: i32,
+    name: []const u8,
+};
+
+const KeyContext = struct {
+    pub fn hash(_: KeyContext, key: Key) u64 {
+        var hasher = std.hash.Wyhash.init(0);
+        hasher.update(std.mem.asBytes(&key.pid));
+        hasher.update(key.name);
+        return hasher.final();
+    }
+
+    pub fn eql(_: KeyContext, a: Key, b: Key) bool {
+        return a.pid == b.pid and std.mem.eql(u8, a.name, b.name);
+    }
+};
+
+/// Matches `<unfinished ...>` lines with their `<... resumed>` continuation
+///
+/// Heuristic:
+/// - Pairing is keyed strictly on (pid, syscall name), never on line order alone.
+///   A single LIFO stack would pair `<... read resumed>` with whatever call was
+///   interrupted last (e.g. a futex from another thread), corrupting both rows.
+/// - Within one key, pending calls resume in FIFO order.
+/// - `restart_syscall` never takes part in pairing: the kernel restarts an earlier
+///   interrupted call under a different name, so its unfinished/resumed lines
+///   are left unpaired rather than attached to an unrelated pending call.
+pub const Pairer = struct {
+    allocator: std.mem.Allocator,
+    pending: std.HashMapUnmanaged(Key, std.ArrayListUnmanaged(Pending), KeyContext, std.hash_map.default_max_load_percentage) = .{},
+
+    pub fn init(allocator: std.mem.Allocator) Pairer {
+        return .{ .allocator = allocator };
+    }
+
+    pub fn deinit(self: *Pairer) void {
+        var it = self.pending.iterator();
+        while (it.next()) |entry| {
+            for (entry.value_ptr.items) |pending| pending.deinit(self.allocator);
+            entry.value_ptr.deinit(self.allocator);
+            self.allocator.free(entry.key_ptr.name);
+        }
+        self.pending.deinit(self.allocator);
+    }
+
+    /// Record an unfinished call; other lines are ignored
+    pub fn open(self: *Pairer, pid: i32, syscall: Syscall) !void {
+        if (!syscall.unfinished or isUnpaired(syscall.syscall)) return;
+
+        const key = Key{ .pid = pid, .name = syscall.syscall };
+        const list = self.pending.getPtr(key) orelse blk: {
+            const name = try self.allocator.dupe(u8, syscall.syscall);
+            errdefer self.allocator.free(name);
+            try self.pending.put(self.allocator, .{ .pid = pid, .name = name }, .{});
+            break :blk self.pending.getPtr(key).?;
+        };
+
+        const pending = try Pending.init(self.allocator, syscall);
+        errdefer pending.deinit(self.allocator);
+        try list.append(self.allocator, pending);
+    }
+
+    /// Take the unfinished call a resumed line continues, if any
+    /// Caller owns the returned Pending and must call deinit on it
+    pub fn close(self: *Pairer, pid: i32, syscall: Syscall) ?Pending {
+        if (!syscall.resumed or isUnpaired(syscall.syscall)) return null;
+
+        const list = self.pending.getPtr(.{ .pid = pid, .name = syscall.syscall }) orelse return null;
+        if (list.items.len == 0) return null;
+        return list.orderedRemove(0);
+    }
+
+    /// Remove every call still waiting for a resumed line (e.g. at end of file)
+    /// Caller owns the list and each Pending in it; syscall names stay valid until deinit
+    pub fn drain(self: *Pairer) !std.ArrayListUnmanaged(Orphan) {
+        var orphans = std.ArrayListUnmanaged(Orphan){};
+        try orphans.ensureTotalCapacity(self.allocator, self.pendingCount());
+
+        var it = self.pending.iterator();
+        while (it.next()) |entry| {
+            for (entry.value_ptr.items) |pending| {
+                orphans.appendAssumeCapacity(.{ .pid = entry.key_ptr.pid, .syscall = entry.key_ptr.name, .pending = pending });
+            }
+            entry.value_ptr.clearRetainingCapacity();
+        }
+        return orphans;
+    }
+
+    /// Number of unfinished calls still waiting for a resumed line
+    pub fn pendingCount(self: *const Pairer) usize {
+        var count: usize = 0;
+        var it = self.pending.valueIterator();
+        while (it.next()) |list| count += list.items.len;
+        return count;
+    }
+};
+
+/// Syscalls excluded from pairing (see Pairer)
+pub fn isUnpaired(name: []const u8) bool {
+    return std.mem.eql(u8, name, "restart_syscall");
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+fn parseTestLine(line: []const u8) !Syscall {
+    return (try parser.parseLine(std.testing.allocator, line)).?;
+}
+
+test "pair interleaved calls by syscall name, not line order" {
+    var pairer = Pairer.init(std.testing.allocator);
+    defer pairer.deinit();
+
+    // A naive LIFO would pair the read resumption with the futex
+    try pairer.open(1, try parseTestLine("10:00:00.000001 read(3, <unfinished ...>"));
+    try pairer.open(1, try parseTestLine("10:00:00.000002 futex(0x7f00, FUTEX_WAIT_PRIVATE, 0, NULL <unfinished ...>"));
+
+    const read = pairer.close(1, try parseTestLine("10:00:00.000003 <... read resumed>\"data\", 100) = 4")).?;
+    defer read.deinit(std.testing.allocator);
+    try std.testing.expectEqualStrings("10:00:00.000001", read.row.timestamp);
+
+    const futex = pairer.close(1, try parseTestLine("10:00:00.000004 <... futex resumed>) = 0")).?;
+    defer futex.deinit(std.testing.allocator);
+    try std.testing.expectEqualStrings("10:00:00.000002", futex.row.timestamp);
+
+    try std.testing.expectEqual(@as(usize, 0), pairer.pendingCount());
+}
+
+test "pairing is scoped to the pid" {
+    var pairer = Pairer.init(std.testing.allocator);
+    defer pairer.deinit();
+
+    try pairer.open(1, try parseTestLine("10:00:00.000001 read(3, <unfinished ...>"));
+    try std.testing.expect(pairer.close(2, try parseTestLine("10:00:00.000002 <... read resumed>\"\", 100) = 0")) == null);
+    try std.testing.expectEqual(@as(usize, 1), pairer.pendingCount());
+}
+
+test "restart_syscall is never paired" {
+    var pairer = Pairer.init(std.testing.allocator);
+    defer pairer.deinit();
+
+    try pairer.open(1, try parseTestLine("10:00:00.000001 nanosleep({tv_sec=5, tv_nsec=0}, <unfinished ...>"));
+    try pairer.open(1, try parseTestLine("10:00:00.000002 restart_syscall(<... resuming interrupted nanosleep ...> <unfinished ...>"));
+
+    try std.testing.expect(pairer.close(1, try parseTestLine("10:00:00.000003 <... restart_syscall resumed>) = 0")) == null);
+
+    // The interrupted nanosleep is still waiting for its own resumption
+    try std.testing.expectEqual(@as(usize, 1), pairer.pendingCount());
+    const nanosleep = pairer.close(1, try parseTestLine("10:00:00.000004 <... nanosleep resumed>0x7ffd) = 0")).?;
+    defer nanosleep.deinit(std.testing.allocator);
+    try std.testing.expectEqualStrings("{tv_sec=5, tv_nsec=0}, ", nanosleep.row.args);
+}
+
+test "drain hands back calls that were never resumed" {
+    var pairer = Pairer.init(std.testing.allocator);
+    defer pairer.deinit();
+
+    try pairer.open(7, try parseTestLine("10:00:00.000001 wait4(-1, <unfinished ...>"));
+
+    var orphans = try pairer.drain();
+    defer orphans.deinit(std.testing.allocator);
+    defer for (orphans.items) |orphan| orphan.pending.deinit(std.testing.allocator);
+
+    try std.testing.expectEqual(@as(usize, 1), orphans.items.len);
+    try std.testing.expectEqual(@as(i32, 7), orphans.items[0].pid);
+    try std.testing.expectEqualStrings("wait4", orphans.items[0].syscall);
+    try std.testing.expectEqualStrings("-1, ", orphans.items[0].pending.row.args);
+    try std.testing.expectEqual(@as(usize, 0), pairer.pendingCount());
+}
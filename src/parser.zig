@@ -1,13 +1,94 @@
 const std = @import("std");
+const errno = @import("errno.zig");
 const types = @import("types.zig");
 const Syscall = types.Syscall;
+const TimeFormat = types.TimeFormat;
 
 /// Result of timestamp extraction
 const TimestampResult = struct {
     timestamp: []const u8,
     rest_start: usize,
+    /// Which reading applied (never .auto)
+    format: TimeFormat = .clock,
 };
 
+/// Smallest whole-second count .auto reads as an epoch time (-ttt) rather than a delta (-r):
+/// 1e8 seconds is March 1973 as a date, and over three years as a gap between two syscalls
+const min_auto_epoch_seconds = 100_000_000;
+
+/// Extract the leading time field in the given format
+/// .auto takes a field with colons as a clock time, and a bare SECONDS.FRACTION as an epoch
+/// time when it is at least min_auto_epoch_seconds and as relative otherwise;
+/// only the first token decides, since later arguments may contain colons of their own
+fn extractTimestampAs(line: []const u8, format: TimeFormat) ?TimestampResult {
+    return switch (format) {
+        .clock => extractTimestamp(line),
+        .relative => extractSeconds(line, .relative),
+        .epoch => extractSeconds(line, .epoch),
+        .auto => {
+            const token_end = std.mem.indexOfAny(u8, line, " \t") orelse line.len;
+            if (std.mem.indexOfScalar(u8, line[0..token_end], ':') != null) return extractTimestamp(line);
+            var result = extractSeconds(line, .relative) orelse return null;
+            const whole = result.timestamp[0..std.mem.indexOfScalar(u8, result.timestamp, '.').?];
+            const seconds = std.fmt.parseInt(u64, whole, 10) catch std.math.maxInt(u64);
+            if (seconds >= min_auto_epoch_seconds) result.format = .epoch;
+            return result;
+        },
+    };
+}
+
+/// Convert an epoch SECONDS.FRACTION timestamp (-ttt) to microseconds since the epoch
+/// Fraction digits past the sixth are truncated, as in parseTimeOfDay
+pub fn parseEpochMicros(timestamp: []const u8) ?i64 {
+    if (timestamp.len == 0 or !std.ascii.isDigit(timestamp[0])) return null;
+    const dot = std.mem.indexOfScalar(u8, timestamp, '.') orelse timestamp.len;
+    const seconds = std.fmt.parseInt(i64, timestamp[0..dot], 10) catch return null;
+
+    var micros: i64 = 0;
+    if (dot < timestamp.len) {
+        var scale: i64 = 100_000;
+        for (timestamp[dot + 1 ..]) |c| {
+            if (!std.ascii.isDigit(c)) return null;
+            micros += @as(i64, c - '0') * scale;
+            scale = @divTrunc(scale, 10);
+        }
+    }
+    const whole = std.math.mul(i64, seconds, std.time.us_per_s) catch return null;
+    return std.math.add(i64, whole, micros) catch null;
+}
+
+/// Convert a SECONDS.FRACTION duration (-T) to whole nanoseconds without going through f64
+/// Fraction digits past the ninth are truncated
+pub fn parseDurationNs(text: []const u8) ?i64 {
+    if (text.len == 0 or !std.ascii.isDigit(text[0])) return null;
+    const dot = std.mem.indexOfScalar(u8, text, '.') orelse text.len;
+    const seconds = std.fmt.parseInt(i64, text[0..dot], 10) catch return null;
+
+    var nanos: i64 = 0;
+    if (dot < text.len) {
+        var scale: i64 = 100_000_000;
+        for (text[dot + 1 ..]) |c| {
+            if (!std.ascii.isDigit(c)) return null;
+            nanos += @as(i64, c - '0') * scale;
+            scale = @divTrunc(scale, 10);
+        }
+    }
+    const whole = std.math.mul(i64, seconds, std.time.ns_per_s) catch return null;
+    return std.math.add(i64, whole, nanos) catch null;
+}
+
+/// Extract a SECONDS.FRACTION field (-r); the line is already trimmed of leading padding
+fn extractSeconds(line: []const u8, format: TimeFormat) ?TimestampResult {
+    const end = std.mem.indexOfAny(u8, line, " \t") orelse return null;
+    const token = line[0..end];
+    const dot = std.mem.indexOfScalar(u8, token, '.') orelse return null;
+    if (dot == 0 or dot == token.len - 1) return null;
+    for (token, 0..) |ch, i| {
+        if (i != dot and !std.ascii.isDigit(ch)) return null;
+    }
+    return .{ .timestamp = token, .rest_start = end, .format = format };
+}
+
 /// Extract timestamp from the beginning of a line
 /// Returns timestamp and position where the rest of the line starts
 fn extractTimestamp(line: []const u8) ?TimestampResult {
@@ -29,17 +110,34 @@ fn extractTimestamp(line: []const u8) ?TimestampResult {
 }
 
 /// Find the position of closing ')' that matches the opening '('
-/// This handles nested parentheses in syscall arguments
+/// Scans byte by byte, tracking nesting depth and double-quoted strings, so
+/// parentheses inside string arguments (e.g. write(1, "done)\n", 6)) are ignored.
+/// Backslash escapes inside strings (\" and \\) are skipped as a unit
+/// All parsing is on bytes: every delimiter is ASCII and UTF-8 never uses bytes below 0x80
+/// inside a multi-byte character, so an offset found this way, and the one after it, are
+/// always character boundaries
 /// Starts with depth 1 (assumes we already passed the opening '(')
 /// Returns the position of ')' or null if not found
 fn findClosingParen(line: []const u8) ?usize {
-    var depth: i32 = 1; // Start at 1 since we already passed the opening '('
+    var depth: usize = 1; // Start at 1 since we already passed the opening '('
+    var in_string = false;
     var i: usize = 0;
 
     while (i < line.len) : (i += 1) {
         const c = line[i];
 
-        if (c == '(') {
+        if (in_string) {
+            if (c == '\\') {
+                i += 1; // skip escaped character
+            } else if (c == '"') {
+                in_string = false;
+            }
+            continue;
+        }
+
+        if (c == '"') {
+            in_string = true;
+        } else if (c == '(') {
             depth += 1;
         } else if (c == ')') {
             depth -= 1;
@@ -52,20 +150,394 @@ fn findClosingParen(line: []const u8) ?usize {
     return null;
 }
 
-/// Parse a single line of strace output
+/// Fields parsed from everything after the '=' of a completed syscall
+const ReturnFields = struct {
+    return_value: ?i64 = null,
+    return_value_raw: ?[]const u8 = null,
+    error_code: ?[]const u8 = null,
+    error_message: ?[]const u8 = null,
+    return_annotation: ?[]const u8 = null,
+    duration: ?f64 = null,
+    /// The same duration read exactly from its digits
+    duration_ns: ?i64 = null,
+    /// The return was the literal `?` (the call never returned, e.g. exit_group)
+    return_unknown: bool = false,
+    /// Unsigned value of a hex return, so addresses with the high bit set stay positive
+    return_addr: ?u64 = null,
+};
+
+/// Find the end of the return value token
+/// Bracketed/braced returns ({...} or [...]) may contain spaces, so match their closing bracket
+fn returnTokenEnd(rest: []const u8) usize {
+    if (rest.len > 0 and (rest[0] == '{' or rest[0] == '[')) {
+        var depth: usize = 0;
+        for (rest, 0..) |ch, i| {
+            switch (ch) {
+                '{', '[' => depth += 1,
+                '}', ']' => {
+                    depth -= 1;
+                    if (depth == 0) return i + 1;
+                },
+                else => {},
+            }
+        }
+        return rest.len;
+    }
+    return std.mem.indexOfAny(u8, rest, " <") orelse rest.len;
+}
+
+/// Whether the text after the return value starts with a kernel restart code
+/// (ERESTARTSYS, ERESTARTNOINTR, ERESTARTNOHAND, ERESTART_RESTARTBLOCK)
+fn isRestartCode(rest: []const u8) bool {
+    return std.mem.startsWith(u8, rest, "ERESTART");
+}
+
+/// Parse the tail of a completed syscall:
+/// return_value [ERROR (msg)] <duration>
+/// Shared by the regular and resumed parsers
+fn parseReturn(tail: []const u8) ?ReturnFields {
+    var fields = ReturnFields{};
+    var rest = std.mem.trimLeft(u8, tail, " \t");
+
+    // Parse return value (can be decimal, hex, or ?)
+    const ret_end = returnTokenEnd(rest);
+    const ret_str = rest[0..ret_end];
+
+    if (std.mem.eql(u8, ret_str, "?")) {
+        fields.return_value = null;
+        fields.return_unknown = true;
+    } else if (std.mem.startsWith(u8, ret_str, "0x")) {
+        fields.return_addr = std.fmt.parseInt(u64, ret_str[2..], 16) catch null;
+        fields.return_value = std.fmt.parseInt(i64, ret_str[2..], 16) catch null;
+    } else {
+        fields.return_value = std.fmt.parseInt(i64, ret_str, 10) catch null;
+    }
+
+    // Anything that isn't a plain integer/hex (structs, lists, overflowing values) is kept verbatim
+    // A hex address above i64 max is covered by return_addr instead
+    if (fields.return_value == null and fields.return_addr == null and !std.mem.eql(u8, ret_str, "?")) {
+        if (ret_str.len == 0) return null;
+        fields.return_value_raw = ret_str;
+    }
+
+    rest = if (ret_end < rest.len) rest[ret_end..] else "";
+    rest = std.mem.trimLeft(u8, rest, " ");
+
+    // Check for error code (uppercase word followed by '(')
+    // Error codes ONLY appear when the syscall failed (return_value < 0),
+    // or after a `?` when a signal interrupted it: "= ? ERESTARTSYS (To be restarted ...)"
+    const is_failure = if (fields.return_value) |val| val < 0 else fields.return_unknown and isRestartCode(rest);
+    if (is_failure and rest.len > 0 and rest[0] != '<') {
+        // Should be error code; the message may be missing
+        const error_end = std.mem.indexOfScalar(u8, rest, ' ') orelse rest.len;
+        fields.error_code = rest[0..error_end];
+        rest = std.mem.trimLeft(u8, rest[error_end..], " ");
+
+        // Parse error message between '(' and ')'
+        if (rest.len > 0 and rest[0] == '(') {
+            // Matched like the args, so a parenthetical inside the message doesn't cut it short
+            const msg_end = (findClosingParen(rest[1..]) orelse return null) + 1;
+            fields.error_message = rest[1..msg_end]; // skip '('
+            rest = rest[msg_end + 1 ..]; // skip ')'
+            rest = std.mem.trimLeft(u8, rest, " ");
+        }
+    }
+
+    // Successful calls may carry a parenthetical annotation, e.g. "= 0 (Timeout)" or
+    // "= 1 ([{fd=3, revents=POLLIN}])". It is not an error, so keep it separate
+    if (!is_failure and rest.len > 0 and rest[0] == '(') {
+        const annotation_end = findClosingParen(rest[1..]) orelse return null;
+        fields.return_annotation = rest[1 .. annotation_end + 1];
+        rest = std.mem.trimLeft(u8, rest[annotation_end + 2 ..], " ");
+    }
+
+    // Check for duration <seconds>
+    if (rest.len > 0 and rest[0] == '<') {
+        const duration_end = std.mem.indexOfScalar(u8, rest, '>') orelse return null;
+        const duration_str = rest[1..duration_end];
+        fields.duration = std.fmt.parseFloat(f64, duration_str) catch return null;
+        fields.duration_ns = parseDurationNs(duration_str);
+    }
+
+    return fields;
+}
+
+/// Iterates over top-level, comma-separated syscall arguments
+/// Commas inside string literals, brackets, braces, or parentheses do not split
+pub const ArgIterator = struct {
+    args: []const u8,
+    index: usize = 0,
+    done: bool = false,
+
+    pub fn init(args: []const u8) ArgIterator {
+        return .{ .args = args };
+    }
+
+    /// Return the next argument (whitespace trimmed), or null when exhausted
+    pub fn next(self: *ArgIterator) ?[]const u8 {
+        if (self.done) return null;
+
+        const start = self.index;
+        var depth: usize = 0;
+        var in_string = false;
+        var i = start;
+        while (i < self.args.len) : (i += 1) {
+            const ch = self.args[i];
+            if (in_string) {
+                if (ch == '\\') {
+                    i += 1; // skip escaped character
+                } else if (ch == '"') {
+                    in_string = false;
+                }
+                continue;
+            }
+            switch (ch) {
+                '"' => in_string = true,
+                '(', '[', '{' => depth += 1,
+                ')', ']', '}' => depth -|= 1,
+                ',' => if (depth == 0) {
+                    self.index = i + 1;
+                    return std.mem.trim(u8, self.args[start..i], " \t");
+                },
+                else => {},
+            }
+        }
+
+        self.done = true;
+        const last = std.mem.trim(u8, self.args[start..], " \t");
+        // An empty argument list has no arguments at all
+        if (start == 0 and last.len == 0) return null;
+        return last;
+    }
+};
+
+/// Number of top-level arguments: 0 for `brk()`, 1 for `brk(NULL)`
+pub fn argCount(args: []const u8) i32 {
+    var it = ArgIterator.init(args);
+    var count: i32 = 0;
+    while (it.next()) |_| count += 1;
+    return count;
+}
+
+/// Write the top-level arguments as a JSON array of strings, e.g. ["3","\"abc\"","3"]
+/// Arguments are split by ArgIterator, so each element is the argument exactly as strace printed it
+pub fn writeArgsJson(writer: *std.Io.Writer, args: []const u8) !void {
+    var json: std.json.Stringify = .{ .writer = writer };
+    try json.beginArray();
+    var it = ArgIterator.init(args);
+    while (it.next()) |arg| try json.write(arg);
+    try json.endArray();
+}
+
+/// Return the nth (0-based) top-level argument, or null if there are fewer arguments
+pub fn nthArg(args: []const u8, n: usize) ?[]const u8 {
+    var it = ArgIterator.init(args);
+    var i: usize = 0;
+    while (it.next()) |arg| : (i += 1) {
+        if (i == n) return arg;
+    }
+    return null;
+}
+
+/// True when strace cut a string argument short: a closing quote followed by `...`
+/// e.g. write(1, "very long string"..., 4096). Dots inside strings don't count
+pub fn hasTruncatedString(args: []const u8) bool {
+    var in_string = false;
+    var i: usize = 0;
+    while (i < args.len) : (i += 1) {
+        const ch = args[i];
+        if (in_string) {
+            if (ch == '\\') {
+                i += 1; // skip escaped character
+            } else if (ch == '"') {
+                in_string = false;
+                if (std.mem.startsWith(u8, args[i + 1 ..], "...")) return true;
+            }
+            continue;
+        }
+        if (ch == '"') in_string = true;
+    }
+    return false;
+}
+
+/// First argument as a file descriptor, when it is a bare non-negative integer
+/// Symbolic values such as AT_FDCWD or NULL yield null
+pub fn leadingFd(args: []const u8) ?i32 {
+    const first = nthArg(args, 0) orelse return null;
+    if (first.len == 0) return null;
+    for (first) |ch| {
+        if (!std.ascii.isDigit(ch)) return null;
+    }
+    return std.fmt.parseInt(i32, first, 10) catch null;
+}
+
+/// Remove a trailing analyst comment (` # ...`) that is outside any quoted string
+/// A '#' only starts a comment when preceded by whitespace, so `#` inside args is kept
+fn stripTrailingComment(line: []const u8) []const u8 {
+    var in_string = false;
+    var i: usize = 0;
+    while (i < line.len) : (i += 1) {
+        const ch = line[i];
+        if (in_string) {
+            if (ch == '\\') {
+                i += 1; // skip escaped character
+            } else if (ch == '"') {
+                in_string = false;
+            }
+            continue;
+        }
+        if (ch == '"') {
+            in_string = true;
+        } else if (ch == '#' and i > 0 and (line[i - 1] == ' ' or line[i - 1] == '\t')) {
+            return std.mem.trimRight(u8, line[0..i], " \t");
+        }
+    }
+    return line;
+}
+
+/// Parse a single line of strace output, detecting the time format
 /// Caller owns the returned Syscall strings (they reference the input line)
 pub fn parseLine(allocator: std.mem.Allocator, line: []const u8) !?Syscall {
-    const trimmed = std.mem.trim(u8, line, " \t\r\n");
+    return parseLineAs(allocator, line, .auto);
+}
+
+/// Parse a single line of strace output with the given time format (--time-format)
+pub fn parseLineAs(allocator: std.mem.Allocator, line: []const u8, format: TimeFormat) !?Syscall {
+    const trimmed = stripTrailingComment(std.mem.trim(u8, line, " \t\r\n"));
 
     // Empty line
     if (trimmed.len == 0) {
         return null;
     }
 
-    // Extract timestamp ONCE - all strace lines start with a timestamp
-    const ts_result = extractTimestamp(trimmed) orelse return null;
-    const timestamp = ts_result.timestamp;
-    const rest = std.mem.trimLeft(u8, trimmed[ts_result.rest_start..], " ");
+    // Strip the PID prefix ONCE - every sub-parser sees the line from the timestamp on
+    const prefix = stripPidPrefix(trimmed);
+    var syscall = (try parseWithoutPrefix(allocator, prefix.rest, format)) orelse return null;
+    syscall.pid = prefix.pid;
+    syscall.time_of_day_us = parseTimeOfDay(syscall.timestamp);
+    if (syscall.time_of_day_us == null) {
+        // An epoch time (-ttt) is a full timestamp on its own; its time of day is UTC
+        if (syscall.wall_time_us) |epoch_us| syscall.time_of_day_us = @mod(epoch_us, std.time.us_per_day);
+    }
+    if (std.mem.eql(u8, syscall.syscall, "execve") or std.mem.eql(u8, syscall.syscall, "execveat")) {
+        syscall.env_count = envCount(syscall.args);
+    }
+    if (syscall.error_code) |code| syscall.errno = errno.errnoNumber(code);
+    syscall.truncated = hasTruncatedString(syscall.args);
+    // Half of a split call has only some of its arguments
+    if (syscall.kind == .syscall and !syscall.unfinished and !syscall.resumed) {
+        syscall.arg_count = argCount(syscall.args);
+    }
+    return syscall;
+}
+
+/// Environment size from strace's abbreviated envp, e.g. 0x7ffe... /* 166 vars */
+/// Returns null when the comment is absent (envp printed in full, or not reached yet)
+fn envCount(args: []const u8) ?i32 {
+    const suffix = " vars */";
+    const end = std.mem.lastIndexOf(u8, args, suffix) orelse return null;
+    const open = std.mem.lastIndexOf(u8, args[0..end], "/* ") orelse return null;
+    return std.fmt.parseInt(i32, args[open + "/* ".len .. end], 10) catch null;
+}
+
+/// Convert an HH:MM:SS[.fraction] timestamp to microseconds since midnight
+/// The fraction may have any width: ".524" is 524000us, digits past the sixth are truncated
+/// Returns null for anything else (e.g. epoch timestamps)
+pub fn parseTimeOfDay(timestamp: []const u8) ?i64 {
+    if (timestamp.len < 8 or timestamp[2] != ':' or timestamp[5] != ':') return null;
+    // parseInt would take a sign ("-1:00:00"), so check the digits first
+    for ([_]usize{ 0, 1, 3, 4, 6, 7 }) |i| {
+        if (!std.ascii.isDigit(timestamp[i])) return null;
+    }
+
+    const hours = std.fmt.parseInt(i64, timestamp[0..2], 10) catch return null;
+    const minutes = std.fmt.parseInt(i64, timestamp[3..5], 10) catch return null;
+    const seconds = std.fmt.parseInt(i64, timestamp[6..8], 10) catch return null;
+    // 60 allows a leap second
+    if (hours > 23 or minutes > 59 or seconds > 60) return null;
+
+    var micros: i64 = 0;
+    if (timestamp.len > 8) {
+        if (timestamp[8] != '.' or timestamp.len == 9) return null;
+        var scale: i64 = 100_000;
+        for (timestamp[9..]) |c| {
+            if (!std.ascii.isDigit(c)) return null;
+            micros += @as(i64, c - '0') * scale;
+            scale = @divTrunc(scale, 10);
+        }
+    }
+
+    return ((hours * 60 + minutes) * 60 + seconds) * std.time.us_per_s + micros;
+}
+
+/// Result of stripping a PID prefix from a line
+const PidPrefix = struct {
+    pid: ?i32 = null,
+    rest: []const u8,
+};
+
+/// Strip the PID that strace -f writes before the timestamp, either bare or bracketed:
+///   "1387679 22:21:11.524449 brk(NULL) = ..."
+///   "[pid  1387679] 22:21:11.524449 openat(...) = 3"
+/// Only an all-digit first token counts; timestamps always contain ':' or '.'
+fn stripPidPrefix(line: []const u8) PidPrefix {
+    if (std.mem.startsWith(u8, line, "[pid ")) {
+        const close = std.mem.indexOfScalar(u8, line, ']') orelse return .{ .rest = line };
+        const number = std.mem.trim(u8, line["[pid ".len..close], " \t");
+        const pid = parsePidToken(number) orelse return .{ .rest = line };
+        return .{ .pid = pid, .rest = std.mem.trimLeft(u8, line[close + 1 ..], " \t") };
+    }
+
+    const token_end = std.mem.indexOfAny(u8, line, " \t") orelse return .{ .rest = line };
+    const pid = parsePidToken(line[0..token_end]) orelse return .{ .rest = line };
+    return .{ .pid = pid, .rest = std.mem.trimLeft(u8, line[token_end..], " \t") };
+}
+
+/// Parse an all-digit PID token (no sign, no fraction)
+fn parsePidToken(token: []const u8) ?i32 {
+    if (token.len == 0) return null;
+    for (token) |ch| {
+        if (!std.ascii.isDigit(ch)) return null;
+    }
+    return std.fmt.parseInt(i32, token, 10) catch null;
+}
+
+/// Parse a trimmed line that starts at the timestamp
+fn parseWithoutPrefix(allocator: std.mem.Allocator, line: []const u8, format: TimeFormat) !?Syscall {
+    // Some strace configurations print resumed lines with no time field at all;
+    // timestamp stays empty (--reassemble takes the unfinished line's)
+    if (std.mem.startsWith(u8, line, "<... ")) return parseResumedWithTimestamp(allocator, "", line);
+
+    // Extract timestamp ONCE - every other strace line starts with a timestamp
+    const ts_result = extractTimestampAs(line, format) orelse return null;
+    const rest = std.mem.trimLeft(u8, line[ts_result.rest_start..], " \t");
+
+    // A relative delta is not a point in time, so it goes to rel_time and timestamp stays empty
+    if (ts_result.format == .relative) {
+        var syscall = (try parseBody(allocator, "", rest)) orelse return null;
+        syscall.rel_time = std.fmt.parseFloat(f64, ts_result.timestamp) catch null;
+        return syscall;
+    }
+    var syscall = (try parseBody(allocator, ts_result.timestamp, rest)) orelse return null;
+    if (ts_result.format == .epoch) syscall.wall_time_us = parseEpochMicros(ts_result.timestamp);
+    return syscall;
+}
+
+/// Parse everything after the time field
+fn parseBody(allocator: std.mem.Allocator, timestamp: []const u8, rest: []const u8) !?Syscall {
+
+    // Signal deliveries are framed by "---", so they can't match the syscall patterns
+    if (try parseSignalWithTimestamp(allocator, timestamp, rest)) |signal| {
+        return signal;
+    }
+
+    if (try parseExitWithTimestamp(allocator, timestamp, rest)) |exit| {
+        return exit;
+    }
+
+    if (try parseKilledWithTimestamp(allocator, timestamp, rest)) |killed| {
+        return killed;
+    }
 
     // Try regular pattern first
     if (try parseRegularWithTimestamp(allocator, timestamp, rest)) |syscall| {
@@ -86,6 +558,144 @@ pub fn parseLine(allocator: std.mem.Allocator, line: []const u8) !?Syscall {
     return null;
 }
 
+/// Reasons a line is rejected, reported by parseLineVerbose
+pub const ParseError = error{
+    EmptyLine,
+    MissingTimestamp,
+    MissingSyscallName,
+    MissingClosingParen,
+    MissingReturn,
+    InvalidReturn,
+};
+
+/// Detail filled in by parseLineVerbose when a line is rejected
+pub const Diagnostic = struct {
+    /// Part of the line the failing stage was looking at
+    slice: []const u8 = "",
+};
+
+/// Parse a line like parseLine, but report why a rejected line failed
+/// On error, diagnostic.slice points at the offending part of the line
+pub fn parseLineVerbose(allocator: std.mem.Allocator, line: []const u8, diagnostic: *Diagnostic) !Syscall {
+    if (try parseLine(allocator, line)) |syscall| return syscall;
+    return diagnose(line, diagnostic);
+}
+
+/// Walk the same stages as parseLine to find the first one that fails
+/// Only called for lines parseLine already rejected
+fn diagnose(line: []const u8, diagnostic: *Diagnostic) ParseError {
+    const stripped = stripTrailingComment(std.mem.trim(u8, line, " \t\r\n"));
+    diagnostic.slice = stripped;
+    if (stripped.len == 0) return error.EmptyLine;
+
+    const trimmed = stripPidPrefix(stripped).rest;
+
+    // Resumed lines may have no time field (see parseWithoutPrefix)
+    const rest_start = if (std.mem.startsWith(u8, trimmed, "<... ")) 0 else if (extractTimestampAs(trimmed, .auto)) |ts_result| ts_result.rest_start else {
+        diagnostic.slice = trimmed[0 .. std.mem.indexOfScalar(u8, trimmed, ' ') orelse trimmed.len];
+        return error.MissingTimestamp;
+    };
+    var rest = std.mem.trimLeft(u8, trimmed[rest_start..], " ");
+    diagnostic.slice = rest;
+
+    if (std.mem.startsWith(u8, rest, "<... ")) {
+        const resumed_pos = std.mem.indexOf(u8, rest, " resumed>") orelse return error.MissingSyscallName;
+        rest = rest[resumed_pos + 9 ..]; // skip ' resumed>'
+    } else {
+        const syscall_end = std.mem.indexOfScalar(u8, rest, '(') orelse return error.MissingSyscallName;
+        if (syscall_end == 0) return error.MissingSyscallName;
+        rest = rest[syscall_end + 1 ..]; // skip '('
+    }
+
+    diagnostic.slice = rest;
+    const close_paren = findClosingParen(rest) orelse return error.MissingClosingParen;
+
+    const after_paren = std.mem.trimLeft(u8, rest[close_paren + 1 ..], " \t");
+    diagnostic.slice = after_paren;
+    if (after_paren.len == 0 or after_paren[0] != '=') return error.MissingReturn;
+
+    diagnostic.slice = std.mem.trim(u8, after_paren[1..], " \t");
+    return error.InvalidReturn;
+}
+
+/// Parse signal delivery format:
+/// --- SIGNAL {siginfo} ---
+/// The signal name goes in `syscall` (and `signal`); the siginfo is kept verbatim as args
+/// Timestamp is pre-extracted and passed in
+fn parseSignalWithTimestamp(allocator: std.mem.Allocator, timestamp: []const u8, line: []const u8) !?Syscall {
+    _ = allocator;
+
+    if (!std.mem.startsWith(u8, line, "--- ") or !std.mem.endsWith(u8, line, " ---")) return null;
+    // "--- ---" matches both ends with the space shared
+    if (line.len < "--- ".len + " ---".len) return null;
+    const inner = std.mem.trim(u8, line[4 .. line.len - 4], " ");
+
+    // "--- stopped by SIGSTOP ---" has no siginfo
+    const name_start: usize = if (std.mem.startsWith(u8, inner, "stopped by ")) "stopped by ".len else 0;
+    const name_end = std.mem.indexOfScalarPos(u8, inner, name_start, ' ') orelse inner.len;
+    const name = inner[name_start..name_end];
+    if (!std.mem.startsWith(u8, name, "SIG")) return null;
+
+    var result = Syscall.init(
+        timestamp,
+        name,
+        std.mem.trimLeft(u8, inner[name_end..], " "),
+        null,
+        null,
+        null,
+        null,
+        false,
+        false,
+    );
+    result.kind = .signal;
+    result.signal = name;
+    return result;
+}
+
+/// Parse process exit format:
+/// +++ exited with N +++
+/// Stored as a synthetic `__exit` call with the exit code as its return value
+/// Timestamp is pre-extracted and passed in
+fn parseExitWithTimestamp(allocator: std.mem.Allocator, timestamp: []const u8, line: []const u8) !?Syscall {
+    _ = allocator;
+
+    const prefix = "+++ exited with ";
+    const suffix = " +++";
+    if (!std.mem.startsWith(u8, line, prefix) or !std.mem.endsWith(u8, line, suffix)) return null;
+    if (line.len < prefix.len + suffix.len) return null;
+
+    const code_str = std.mem.trim(u8, line[prefix.len .. line.len - suffix.len], " ");
+    const code = std.fmt.parseInt(i64, code_str, 10) catch return null;
+
+    var result = Syscall.init(timestamp, "__exit", "", code, null, null, null, false, false);
+    result.kind = .exit;
+    return result;
+}
+
+/// Parse process termination by signal format:
+/// +++ killed by SIGNAL [(core dumped)] +++
+/// Stored as a synthetic `__killed` call with the signal name in `signal`
+/// Timestamp is pre-extracted and passed in
+fn parseKilledWithTimestamp(allocator: std.mem.Allocator, timestamp: []const u8, line: []const u8) !?Syscall {
+    _ = allocator;
+
+    const prefix = "+++ killed by ";
+    const suffix = " +++";
+    if (!std.mem.startsWith(u8, line, prefix) or !std.mem.endsWith(u8, line, suffix)) return null;
+    if (line.len < prefix.len + suffix.len) return null;
+
+    var inner = std.mem.trim(u8, line[prefix.len .. line.len - suffix.len], " ");
+    const core_dumped = std.mem.endsWith(u8, inner, "(core dumped)");
+    if (core_dumped) inner = std.mem.trimRight(u8, inner[0 .. inner.len - "(core dumped)".len], " ");
+    if (!std.mem.startsWith(u8, inner, "SIG") or std.mem.indexOfScalar(u8, inner, ' ') != null) return null;
+
+    var result = Syscall.init(timestamp, "__killed", "", null, null, null, null, false, false);
+    result.kind = .killed;
+    result.signal = inner;
+    result.core_dumped = core_dumped;
+    return result;
+}
+
 /// Parse regular syscall format:
 /// syscall(args) = return_value [ERROR (msg)] <duration>
 /// Timestamp is pre-extracted and passed in
@@ -104,7 +714,7 @@ fn parseRegularWithTimestamp(allocator: std.mem.Allocator, timestamp: []const u8
     const syscall = rest[0..syscall_end];
     rest = rest[syscall_end + 1 ..]; // skip '('
 
-    // Find the closing ')' by counting backwards from the end
+    // Find the top-level ')' matching the syscall's '(' by depth, skipping quoted strings
     // This handles nested parentheses in syscall arguments
     const close_paren = findClosingParen(rest) orelse return null;
 
@@ -113,65 +723,25 @@ fn parseRegularWithTimestamp(allocator: std.mem.Allocator, timestamp: []const u8
     if (after_paren.len == 0 or after_paren[0] != '=') return null;
 
     const args = rest[0..close_paren];
-    rest = std.mem.trimLeft(u8, after_paren[1..], " \t"); // skip '=' and whitespace
-
-    // Parse return value (can be decimal, hex, or ?)
-    var return_value: ?i64 = null;
-    var error_code: ?[]const u8 = null;
-    var error_message: ?[]const u8 = null;
-    var duration: ?f64 = null;
-
-    // Find next space or end of line
-    const ret_end = std.mem.indexOfAny(u8, rest, " <") orelse rest.len;
-    const ret_str = rest[0..ret_end];
-
-    if (std.mem.eql(u8, ret_str, "?")) {
-        return_value = null;
-    } else if (std.mem.startsWith(u8, ret_str, "0x")) {
-        return_value = std.fmt.parseInt(i64, ret_str[2..], 16) catch return null;
-    } else {
-        return_value = std.fmt.parseInt(i64, ret_str, 10) catch return null;
-    }
-
-    rest = if (ret_end < rest.len) rest[ret_end..] else "";
-    rest = std.mem.trimLeft(u8, rest, " ");
+    const ret = parseReturn(after_paren[1..]) orelse return null; // skip '='
 
-    // Check for error code (uppercase word followed by '(')
-    // Error codes ONLY appear when the syscall failed (return_value < 0)
-    const is_failure = if (return_value) |val| val < 0 else false;
-    if (is_failure and rest.len > 0 and rest[0] != '<') {
-        // Should be error code
-        const error_end = std.mem.indexOfScalar(u8, rest, ' ') orelse return null;
-        error_code = rest[0..error_end];
-        rest = std.mem.trimLeft(u8, rest[error_end..], " ");
-
-        // Parse error message between '(' and ')'
-        if (rest.len > 0 and rest[0] == '(') {
-            const msg_end = std.mem.indexOfScalar(u8, rest, ')') orelse return null;
-            error_message = rest[1..msg_end]; // skip '('
-            rest = rest[msg_end + 1 ..]; // skip ')'
-            rest = std.mem.trimLeft(u8, rest, " ");
-        }
-    }
-
-    // Check for duration <seconds>
-    if (rest.len > 0 and rest[0] == '<') {
-        const duration_end = std.mem.indexOfScalar(u8, rest, '>') orelse return null;
-        const duration_str = rest[1..duration_end];
-        duration = std.fmt.parseFloat(f64, duration_str) catch return null;
-    }
-
-    return Syscall.init(
+    var result = Syscall.init(
         timestamp,
         syscall,
         args,
-        return_value,
-        error_code,
-        error_message,
-        duration,
+        ret.return_value,
+        ret.error_code,
+        ret.error_message,
+        ret.duration,
         false,
         false,
     );
+    result.return_value_raw = ret.return_value_raw;
+    result.return_annotation = ret.return_annotation;
+    result.return_unknown = ret.return_unknown;
+    result.return_addr = ret.return_addr;
+    result.duration_ns = ret.duration_ns;
+    return result;
 }
 
 /// Parse unfinished syscall format:
@@ -197,7 +767,7 @@ fn parseUnfinishedWithTimestamp(allocator: std.mem.Allocator, timestamp: []const
     const marker_pos = std.mem.indexOf(u8, rest, unfinished_marker) orelse return null;
     const args = rest[0..marker_pos];
 
-    return Syscall.init(
+    var result = Syscall.init(
         timestamp,
         syscall,
         args,
@@ -208,6 +778,10 @@ fn parseUnfinishedWithTimestamp(allocator: std.mem.Allocator, timestamp: []const
         true,
         false,
     );
+    // No return has been printed yet; `unfinished` says so, and the resumed line
+    // carries the return (or `= ?`)
+    result.return_unknown = false;
+    return result;
 }
 
 /// Parse resumed syscall format:
@@ -232,7 +806,7 @@ fn parseResumedWithTimestamp(allocator: std.mem.Allocator, timestamp: []const u8
     const syscall = rest[0..resumed_pos];
     rest = rest[resumed_pos + 9 ..]; // skip ' resumed>'
 
-    // Find the closing ')' by counting backwards from the end
+    // Find the top-level ')' matching the syscall's '(' by depth, skipping quoted strings
     // This handles nested parentheses in syscall arguments
     const close_paren = findClosingParen(rest) orelse return null;
 
@@ -241,62 +815,25 @@ fn parseResumedWithTimestamp(allocator: std.mem.Allocator, timestamp: []const u8
     if (after_paren.len == 0 or after_paren[0] != '=') return null;
 
     const args = rest[0..close_paren];
-    rest = std.mem.trimLeft(u8, after_paren[1..], " \t"); // skip '=' and whitespace
-
-    // Parse return value
-    var return_value: ?i64 = null;
-    var error_code: ?[]const u8 = null;
-    var error_message: ?[]const u8 = null;
-    var duration: ?f64 = null;
-
-    const ret_end = std.mem.indexOfAny(u8, rest, " <") orelse rest.len;
-    const ret_str = rest[0..ret_end];
-
-    if (std.mem.eql(u8, ret_str, "?")) {
-        return_value = null;
-    } else if (std.mem.startsWith(u8, ret_str, "0x")) {
-        return_value = std.fmt.parseInt(i64, ret_str[2..], 16) catch return null;
-    } else {
-        return_value = std.fmt.parseInt(i64, ret_str, 10) catch return null;
-    }
-
-    rest = if (ret_end < rest.len) rest[ret_end..] else "";
-    rest = std.mem.trimLeft(u8, rest, " ");
-
-    // Check for error code
-    // Error codes ONLY appear when the syscall failed (return_value < 0)
-    const is_failure = if (return_value) |val| val < 0 else false;
-    if (is_failure and rest.len > 0 and rest[0] != '<') {
-        const error_end = std.mem.indexOfScalar(u8, rest, ' ') orelse return null;
-        error_code = rest[0..error_end];
-        rest = std.mem.trimLeft(u8, rest[error_end..], " ");
+    const ret = parseReturn(after_paren[1..]) orelse return null; // skip '='
 
-        if (rest.len > 0 and rest[0] == '(') {
-            const msg_end = std.mem.indexOfScalar(u8, rest, ')') orelse return null;
-            error_message = rest[1..msg_end];
-            rest = rest[msg_end + 1 ..];
-            rest = std.mem.trimLeft(u8, rest, " ");
-        }
-    }
-
-    // Check for duration
-    if (rest.len > 0 and rest[0] == '<') {
-        const duration_end = std.mem.indexOfScalar(u8, rest, '>') orelse return null;
-        const duration_str = rest[1..duration_end];
-        duration = std.fmt.parseFloat(f64, duration_str) catch return null;
-    }
-
-    return Syscall.init(
+    var result = Syscall.init(
         timestamp,
         syscall,
         args,
-        return_value,
-        error_code,
-        error_message,
-        duration,
+        ret.return_value,
+        ret.error_code,
+        ret.error_message,
+        ret.duration,
         false,
         true,
     );
+    result.return_value_raw = ret.return_value_raw;
+    result.return_annotation = ret.return_annotation;
+    result.return_unknown = ret.return_unknown;
+    result.return_addr = ret.return_addr;
+    result.duration_ns = ret.duration_ns;
+    return result;
 }
 
 // ============================================================================
@@ -647,3 +1184,762 @@ test "parse resumed syscall with variable whitespace" {
     try std.testing.expectEqual(@as(?i64, 0x55555557b000), syscall.return_value);
     try std.testing.expectEqual(true, syscall.resumed);
 }
+
+test "ArgIterator splits top-level arguments" {
+    var it = ArgIterator.init("AT_FDCWD, \"/tmp/a,b\", {st_mode=S_IFDIR|0555, st_size=(11)}, [1, 2], 0");
+    try std.testing.expectEqualStrings("AT_FDCWD", it.next().?);
+    try std.testing.expectEqualStrings("\"/tmp/a,b\"", it.next().?);
+    try std.testing.expectEqualStrings("{st_mode=S_IFDIR|0555, st_size=(11)}", it.next().?);
+    try std.testing.expectEqualStrings("[1, 2]", it.next().?);
+    try std.testing.expectEqualStrings("0", it.next().?);
+    try std.testing.expectEqual(@as(?[]const u8, null), it.next());
+}
+
+test "ArgIterator handles empty and escaped arguments" {
+    var empty = ArgIterator.init("");
+    try std.testing.expectEqual(@as(?[]const u8, null), empty.next());
+
+    try std.testing.expectEqualStrings("\"a\\\",b\"", nthArg("1, \"a\\\",b\", 2", 1).?);
+    try std.testing.expectEqualStrings("2", nthArg("1, \"a\\\",b\", 2", 2).?);
+    try std.testing.expectEqual(@as(?[]const u8, null), nthArg("NULL", 1));
+}
+
+test "braced return value is kept raw instead of failing the line" {
+    const allocator = std.testing.allocator;
+    const line = "10:23:45.123456 getrlimit(RLIMIT_STACK) = {rlim_cur=8192*1024, rlim_max=RLIM64_INFINITY} <0.000005>";
+    const result = try parseLine(allocator, line);
+
+    try std.testing.expect(result != null);
+    const syscall = result.?;
+    try std.testing.expectEqualStrings("getrlimit", syscall.syscall);
+    try std.testing.expectEqual(@as(?i64, null), syscall.return_value);
+    try std.testing.expectEqualStrings("{rlim_cur=8192*1024, rlim_max=RLIM64_INFINITY}", syscall.return_value_raw.?);
+    try std.testing.expectEqual(@as(?[]const u8, null), syscall.error_code);
+    try std.testing.expectEqual(@as(?f64, 0.000005), syscall.duration);
+}
+
+test "bracketed return value is kept raw" {
+    const allocator = std.testing.allocator;
+    const line = "10:23:45.123456 <... pipe2 resumed>) = [3, 4]";
+    const result = try parseLine(allocator, line);
+
+    try std.testing.expect(result != null);
+    const syscall = result.?;
+    try std.testing.expectEqualStrings("pipe2", syscall.syscall);
+    try std.testing.expectEqual(@as(?i64, null), syscall.return_value);
+    try std.testing.expectEqualStrings("[3, 4]", syscall.return_value_raw.?);
+    try std.testing.expectEqual(true, syscall.resumed);
+}
+
+test "integer return value has no raw token" {
+    const allocator = std.testing.allocator;
+    const result = try parseLine(allocator, "10:23:45.123456 close(3) = 0 <0.000010>");
+
+    try std.testing.expect(result != null);
+    try std.testing.expectEqual(@as(?[]const u8, null), result.?.return_value_raw);
+}
+
+test "parseLineVerbose returns the syscall for a good line" {
+    const allocator = std.testing.allocator;
+    var diagnostic = Diagnostic{};
+    const syscall = try parseLineVerbose(allocator, "10:23:45.123456 close(3) = 0 <0.000010>", &diagnostic);
+    try std.testing.expectEqualStrings("close", syscall.syscall);
+}
+
+test "parseLineVerbose reports the failing stage and slice" {
+    const allocator = std.testing.allocator;
+    var diagnostic = Diagnostic{};
+
+    try std.testing.expectError(error.EmptyLine, parseLineVerbose(allocator, "   ", &diagnostic));
+
+    try std.testing.expectError(error.MissingTimestamp, parseLineVerbose(allocator, "close(3) = 0", &diagnostic));
+    try std.testing.expectEqualStrings("close(3)", diagnostic.slice);
+
+    try std.testing.expectError(error.MissingSyscallName, parseLineVerbose(allocator, "10:23:45.123456 garbage", &diagnostic));
+    try std.testing.expectEqualStrings("garbage", diagnostic.slice);
+
+    try std.testing.expectError(error.MissingClosingParen, parseLineVerbose(allocator, "10:23:45.123456 read(3, \"abc\", 3", &diagnostic));
+    try std.testing.expectEqualStrings("3, \"abc\", 3", diagnostic.slice);
+
+    try std.testing.expectError(error.MissingReturn, parseLineVerbose(allocator, "10:23:45.123456 read(3) junk", &diagnostic));
+    try std.testing.expectEqualStrings("junk", diagnostic.slice);
+
+    try std.testing.expectError(error.InvalidReturn, parseLineVerbose(allocator, "10:23:45.123456 read(3) =", &diagnostic));
+}
+
+test "trailing comment does not leak into duration" {
+    const allocator = std.testing.allocator;
+    const syscall = (try parseLine(allocator, "10:23:45.123456 brk(NULL) = 0x55edad95f000 <0.000004>  # allocated heap")).?;
+
+    try std.testing.expectEqual(@as(?i64, 0x55edad95f000), syscall.return_value);
+    try std.testing.expectEqual(@as(?f64, 0.000004), syscall.duration);
+}
+
+test "trailing comment does not leak into error message" {
+    const allocator = std.testing.allocator;
+    const syscall = (try parseLine(allocator, "10:23:45.123456 open(\"/etc/missing\", O_RDONLY) = -1 ENOENT (No such file or directory) # expected (probe)")).?;
+
+    try std.testing.expectEqualStrings("ENOENT", syscall.error_code.?);
+    try std.testing.expectEqualStrings("No such file or directory", syscall.error_message.?);
+    try std.testing.expectEqual(@as(?f64, null), syscall.duration);
+}
+
+test "hash inside a quoted argument is not a comment" {
+    const allocator = std.testing.allocator;
+    const syscall = (try parseLine(allocator, "10:23:45.123456 write(1, \"a # b \\\" # c\", 12) = 12 <0.000010>")).?;
+
+    try std.testing.expectEqualStrings("1, \"a # b \\\" # c\", 12", syscall.args);
+    try std.testing.expectEqual(@as(?f64, 0.00001), syscall.duration);
+}
+
+test "timeout annotation on a successful return is not an error" {
+    const allocator = std.testing.allocator;
+    const syscall = (try parseLine(allocator, "10:23:45.123456 poll([{fd=3, events=POLLIN}], 1, 1000) = 0 (Timeout) <1.001000>")).?;
+
+    try std.testing.expectEqual(@as(?i64, 0), syscall.return_value);
+    try std.testing.expectEqualStrings("Timeout", syscall.return_annotation.?);
+    try std.testing.expectEqual(@as(?[]const u8, null), syscall.error_code);
+    try std.testing.expectEqual(@as(?[]const u8, null), syscall.error_message);
+    try std.testing.expectEqual(@as(?f64, 1.001), syscall.duration);
+}
+
+test "select timeout and ioctl annotations go to return_annotation" {
+    const allocator = std.testing.allocator;
+
+    const timeout = (try parseLine(allocator, "10:23:45.123456 select(4, [3], NULL, NULL, {tv_sec=1, tv_usec=0}) = 0 (Timeout) <1.000100>")).?;
+    try std.testing.expectEqualStrings("select", timeout.syscall);
+    try std.testing.expectEqual(@as(?i64, 0), timeout.return_value);
+    try std.testing.expectEqualStrings("Timeout", timeout.return_annotation.?);
+    try std.testing.expectEqual(@as(?[]const u8, null), timeout.error_code);
+    try std.testing.expectEqual(@as(?i32, null), timeout.errno);
+
+    // A ready set with the remaining time, as strace prints it for select
+    const ready = (try parseLine(allocator, "10:23:45.123457 select(4, [3], NULL, NULL, {tv_sec=1, tv_usec=0}) = 1 (in [3], left {tv_sec=0, tv_usec=999000}) <0.001000>")).?;
+    try std.testing.expectEqualStrings("in [3], left {tv_sec=0, tv_usec=999000}", ready.return_annotation.?);
+    try std.testing.expectEqual(@as(?f64, 0.001), ready.duration);
+
+    const ioctl = (try parseLine(allocator, "10:23:45.123458 ioctl(3, SNDCTL_TMR_TIMEBASE or TCGETS, {B38400 opost isig icanon echo ...}) = 0 (SUCCESS)")).?;
+    try std.testing.expectEqualStrings("ioctl", ioctl.syscall);
+    try std.testing.expectEqual(@as(?i64, 0), ioctl.return_value);
+    try std.testing.expectEqualStrings("SUCCESS", ioctl.return_annotation.?);
+    try std.testing.expectEqual(@as(?[]const u8, null), ioctl.error_code);
+    try std.testing.expectEqual(@as(?[]const u8, null), ioctl.error_message);
+    try std.testing.expectEqual(@as(?f64, null), ioctl.duration);
+}
+
+test "real error keeps its message out of the annotation" {
+    const allocator = std.testing.allocator;
+    const syscall = (try parseLine(allocator, "10:23:45.123456 select(4, [3], NULL, NULL, NULL) = -1 EINTR (Interrupted system call) <0.500000>")).?;
+
+    try std.testing.expectEqualStrings("EINTR", syscall.error_code.?);
+    try std.testing.expectEqualStrings("Interrupted system call", syscall.error_message.?);
+    try std.testing.expectEqual(@as(?[]const u8, null), syscall.return_annotation);
+    try std.testing.expectEqual(@as(?f64, 0.5), syscall.duration);
+}
+
+test "nested annotation keeps the duration" {
+    const allocator = std.testing.allocator;
+    const syscall = (try parseLine(allocator, "10:23:45.123456 poll([{fd=3, events=POLLIN}], 1, -1) = 1 ([{fd=3, revents=POLLIN}]) <0.000100>")).?;
+
+    try std.testing.expectEqualStrings("[{fd=3, revents=POLLIN}]", syscall.return_annotation.?);
+    try std.testing.expectEqual(@as(?f64, 0.0001), syscall.duration);
+}
+
+test "parse strace -f PID prefix" {
+    const allocator = std.testing.allocator;
+    const syscall = (try parseLine(allocator, "1387679 22:21:11.524449 brk(NULL) = 0x55edad95f000 <0.000004>")).?;
+
+    try std.testing.expectEqual(@as(?i32, 1387679), syscall.pid);
+    try std.testing.expectEqualStrings("22:21:11.524449", syscall.timestamp);
+    try std.testing.expectEqualStrings("brk", syscall.syscall);
+    try std.testing.expectEqual(@as(?f64, 0.000004), syscall.duration);
+}
+
+test "lines without a PID prefix are unchanged" {
+    const allocator = std.testing.allocator;
+    const plain = (try parseLine(allocator, "22:21:11.524449 brk(NULL) = 0x55edad95f000")).?;
+    try std.testing.expectEqual(@as(?i32, null), plain.pid);
+    try std.testing.expectEqualStrings("22:21:11.524449", plain.timestamp);
+
+    // -ttt epoch timestamps are not mistaken for PIDs
+    const epoch = (try parseLine(allocator, "1700000000.123456 close(3) = 0")).?;
+    try std.testing.expectEqual(@as(?i32, null), epoch.pid);
+    try std.testing.expectEqualStrings("1700000000.123456", epoch.timestamp);
+}
+
+test "parse bracketed [pid N] prefix with single and multiple spaces" {
+    const allocator = std.testing.allocator;
+
+    const single = (try parseLine(allocator, "[pid 1] 22:21:11.524449 getpid() = 1")).?;
+    try std.testing.expectEqual(@as(?i32, 1), single.pid);
+    try std.testing.expectEqualStrings("getpid", single.syscall);
+
+    const padded = (try parseLine(allocator, "[pid  1387679] 22:21:11.524449 openat(AT_FDCWD, \"/etc/hosts\", O_RDONLY) = 3 <0.000010>")).?;
+    try std.testing.expectEqual(@as(?i32, 1387679), padded.pid);
+    try std.testing.expectEqualStrings("22:21:11.524449", padded.timestamp);
+    try std.testing.expectEqualStrings("openat", padded.syscall);
+    try std.testing.expectEqual(@as(?i64, 3), padded.return_value);
+}
+
+test "malformed bracketed prefix is not a PID" {
+    const allocator = std.testing.allocator;
+    try std.testing.expect((try parseLine(allocator, "[pid abc] 22:21:11.524449 getpid() = 1")) == null);
+}
+
+test "PID prefixes work with unfinished and resumed lines" {
+    const allocator = std.testing.allocator;
+
+    const unfinished = (try parseLine(allocator, "[pid 123] 10:23:45.123456 read(3, <unfinished ...>")).?;
+    try std.testing.expectEqual(@as(?i32, 123), unfinished.pid);
+    try std.testing.expectEqualStrings("10:23:45.123456", unfinished.timestamp);
+    try std.testing.expectEqualStrings("read", unfinished.syscall);
+    try std.testing.expectEqualStrings("3, ", unfinished.args);
+    try std.testing.expect(unfinished.unfinished);
+
+    const resumed = (try parseLine(allocator, "[pid  123] 10:23:45.123460 <... read resumed>\"data\", 100) = 4 <0.000042>")).?;
+    try std.testing.expectEqual(@as(?i32, 123), resumed.pid);
+    try std.testing.expectEqualStrings("10:23:45.123460", resumed.timestamp);
+    try std.testing.expectEqualStrings("read", resumed.syscall);
+    try std.testing.expectEqual(@as(?i64, 4), resumed.return_value);
+    try std.testing.expect(resumed.resumed);
+
+    // The bare form that strace -f writes to a single output file
+    const bare_unfinished = (try parseLine(allocator, "123 10:23:45.123456 futex(0x7f00, FUTEX_WAIT_PRIVATE, 0, NULL <unfinished ...>) = ?")).?;
+    try std.testing.expectEqual(@as(?i32, 123), bare_unfinished.pid);
+    try std.testing.expectEqualStrings("futex", bare_unfinished.syscall);
+    try std.testing.expect(bare_unfinished.unfinished);
+
+    const bare_resumed = (try parseLine(allocator, "123 10:23:45.123470 <... futex resumed>) = -1 EAGAIN (Resource temporarily unavailable)")).?;
+    try std.testing.expectEqual(@as(?i32, 123), bare_resumed.pid);
+    try std.testing.expectEqualStrings("EAGAIN", bare_resumed.error_code.?);
+    try std.testing.expect(bare_resumed.resumed);
+}
+
+test "parseLineVerbose reports a bad resumed line after its PID prefix" {
+    const allocator = std.testing.allocator;
+    var diagnostic = Diagnostic{};
+
+    try std.testing.expectError(error.MissingClosingParen, parseLineVerbose(allocator, "[pid 123] 10:23:45.123460 <... read resumed>\"data\", 100 = 4", &diagnostic));
+    // The prefix, time field and resumed marker are not part of the reported slice
+    try std.testing.expectEqualStrings("\"data\", 100 = 4", diagnostic.slice);
+}
+
+test "parse signal delivery line" {
+    const allocator = std.testing.allocator;
+    const syscall = (try parseLine(allocator, "22:21:24.927885 --- SIGCHLD {si_signo=SIGCHLD, si_code=CLD_EXITED, si_pid=1387721, si_uid=1000, si_status=0, si_utime=0, si_stime=0} ---")).?;
+
+    try std.testing.expectEqual(types.LineKind.signal, syscall.kind);
+    try std.testing.expectEqualStrings("22:21:24.927885", syscall.timestamp);
+    try std.testing.expectEqualStrings("SIGCHLD", syscall.syscall);
+    try std.testing.expectEqualStrings("SIGCHLD", syscall.signal.?);
+    try std.testing.expectEqualStrings("{si_signo=SIGCHLD, si_code=CLD_EXITED, si_pid=1387721, si_uid=1000, si_status=0, si_utime=0, si_stime=0}", syscall.args);
+    try std.testing.expectEqual(@as(?i64, null), syscall.return_value);
+}
+
+test "parse stopped-by signal line and regular syscalls keep their kind" {
+    const allocator = std.testing.allocator;
+
+    const stopped = (try parseLine(allocator, "[pid 7] 22:21:24.927885 --- stopped by SIGSTOP ---")).?;
+    try std.testing.expectEqual(types.LineKind.signal, stopped.kind);
+    try std.testing.expectEqual(@as(?i32, 7), stopped.pid);
+    try std.testing.expectEqualStrings("SIGSTOP", stopped.syscall);
+    try std.testing.expectEqualStrings("", stopped.args);
+
+    const regular = (try parseLine(allocator, "22:21:24.927886 close(3) = 0")).?;
+    try std.testing.expectEqual(types.LineKind.syscall, regular.kind);
+}
+
+test "parse process exit line" {
+    const allocator = std.testing.allocator;
+    const syscall = (try parseLine(allocator, "22:21:24.930000 +++ exited with 3 +++")).?;
+
+    try std.testing.expectEqual(types.LineKind.exit, syscall.kind);
+    try std.testing.expectEqualStrings("22:21:24.930000", syscall.timestamp);
+    try std.testing.expectEqualStrings("__exit", syscall.syscall);
+    try std.testing.expectEqualStrings("", syscall.args);
+    try std.testing.expectEqual(@as(?i64, 3), syscall.return_value);
+    try std.testing.expectEqual(@as(?[]const u8, null), syscall.error_code);
+
+    try std.testing.expect((try parseLine(allocator, "22:21:24.930000 +++ exited with x +++")) == null);
+}
+
+test "parse killed-by-signal lines" {
+    const allocator = std.testing.allocator;
+
+    const killed = (try parseLine(allocator, "22:21:24.931000 +++ killed by SIGKILL +++")).?;
+    try std.testing.expectEqual(types.LineKind.killed, killed.kind);
+    try std.testing.expectEqualStrings("__killed", killed.syscall);
+    try std.testing.expectEqualStrings("SIGKILL", killed.signal.?);
+    try std.testing.expect(!killed.core_dumped);
+    try std.testing.expectEqual(@as(?i64, null), killed.return_value);
+
+    const dumped = (try parseLine(allocator, "22:21:24.932000 +++ killed by SIGSEGV (core dumped) +++")).?;
+    try std.testing.expectEqual(types.LineKind.killed, dumped.kind);
+    try std.testing.expectEqualStrings("SIGSEGV", dumped.signal.?);
+    try std.testing.expect(dumped.core_dumped);
+}
+
+test "parseTimeOfDay handles fractions of any width" {
+    try std.testing.expectEqual(@as(?i64, 80471524449), parseTimeOfDay("22:21:11.524449"));
+    try std.testing.expectEqual(@as(?i64, 80471524000), parseTimeOfDay("22:21:11.524"));
+    try std.testing.expectEqual(@as(?i64, 80471524449), parseTimeOfDay("22:21:11.524449123"));
+    try std.testing.expectEqual(@as(?i64, 80471000000), parseTimeOfDay("22:21:11"));
+    try std.testing.expectEqual(@as(?i64, 0), parseTimeOfDay("00:00:00.000000"));
+
+    try std.testing.expectEqual(@as(?i64, null), parseTimeOfDay("1700000000.123456"));
+    try std.testing.expectEqual(@as(?i64, null), parseTimeOfDay("24:00:00.000000"));
+    try std.testing.expectEqual(@as(?i64, null), parseTimeOfDay("22:21:11."));
+    try std.testing.expectEqual(@as(?i64, null), parseTimeOfDay("22:21:11.5x"));
+}
+
+test "parseLine fills time_of_day_us from the timestamp" {
+    const syscall = (try parseLine(std.testing.allocator, "22:21:11.524449 close(3) = 0")).?;
+    try std.testing.expectEqualStrings("22:21:11.524449", syscall.timestamp);
+    try std.testing.expectEqual(@as(?i64, 80471524449), syscall.time_of_day_us);
+}
+
+test "execve environment count is extracted from the vars comment" {
+    const allocator = std.testing.allocator;
+
+    const execve = (try parseLine(allocator, "22:21:11.524449 execve(\"/usr/bin/ls\", [\"ls\"], 0x7ffe3c0a1b28 /* 166 vars */) = 0")).?;
+    try std.testing.expectEqual(@as(?i32, 166), execve.env_count);
+
+    const full_env = (try parseLine(allocator, "22:21:11.524449 execve(\"/usr/bin/ls\", [\"ls\"], [\"HOME=/root\"]) = 0")).?;
+    try std.testing.expectEqual(@as(?i32, null), full_env.env_count);
+
+    const other = (try parseLine(allocator, "22:21:11.524449 write(1, \"/* 3 vars */\", 12) = 12")).?;
+    try std.testing.expectEqual(@as(?i32, null), other.env_count);
+}
+
+test "parseLine maps error_code to errno" {
+    const allocator = std.testing.allocator;
+
+    const missing = (try parseLine(allocator, "10:23:45.123456 open(\"/missing\", O_RDONLY) = -1 ENOENT (No such file or directory)")).?;
+    try std.testing.expectEqual(@as(?i32, 2), missing.errno);
+
+    const unknown = (try parseLine(allocator, "10:23:45.123456 open(\"/x\", O_RDONLY) = -1 EMADEUP (Made up)")).?;
+    try std.testing.expectEqualStrings("EMADEUP", unknown.error_code.?);
+    try std.testing.expectEqual(@as(?i32, null), unknown.errno);
+
+    const ok = (try parseLine(allocator, "10:23:45.123456 close(3) = 0")).?;
+    try std.testing.expectEqual(@as(?i32, null), ok.errno);
+}
+
+test "leadingFd takes a bare integer first argument only" {
+    const allocator = std.testing.allocator;
+
+    const read = (try parseLine(allocator, "10:23:45.123456 read(3, \"abc\", 3) = 3")).?;
+    try std.testing.expectEqual(@as(?i32, 3), leadingFd(read.args));
+
+    const close = (try parseLine(allocator, "10:23:45.123456 close(12) = 0")).?;
+    try std.testing.expectEqual(@as(?i32, 12), leadingFd(close.args));
+
+    const openat = (try parseLine(allocator, "10:23:45.123456 openat(AT_FDCWD, \"/etc/passwd\", O_RDONLY) = 3")).?;
+    try std.testing.expectEqual(@as(?i32, null), leadingFd(openat.args));
+
+    const brk = (try parseLine(allocator, "10:23:45.123456 brk(NULL) = 0x55d0c0a8e000")).?;
+    try std.testing.expectEqual(@as(?i32, null), leadingFd(brk.args));
+
+    const getpid = (try parseLine(allocator, "10:23:45.123456 getpid() = 1234")).?;
+    try std.testing.expectEqual(@as(?i32, null), leadingFd(getpid.args));
+}
+
+test "closing paren inside a string argument does not end the args" {
+    const allocator = std.testing.allocator;
+
+    const write = (try parseLine(allocator, "10:23:45.123456 write(1, \"done)\\n\", 6) = 6")).?;
+    try std.testing.expectEqualStrings("1, \"done)\\n\", 6", write.args);
+    try std.testing.expectEqual(@as(?i64, 6), write.return_value);
+
+    const open_paren = (try parseLine(allocator, "10:23:45.123456 write(1, \"(\", 1) = 1")).?;
+    try std.testing.expectEqualStrings("1, \"(\", 1", open_paren.args);
+}
+
+test "escaped quotes and backslashes inside string arguments" {
+    const allocator = std.testing.allocator;
+
+    // \" does not close the string, so the ) after it is still inside
+    const quoted = (try parseLine(allocator, "10:23:45.123456 write(1, \"say \\\"hi)\\\"\", 9) = 9")).?;
+    try std.testing.expectEqualStrings("1, \"say \\\"hi)\\\"\", 9", quoted.args);
+
+    // \\ is one escaped backslash, so the quote after it does close the string
+    const backslash = (try parseLine(allocator, "10:23:45.123456 write(1, \"C:\\\\\", 3) = 3")).?;
+    try std.testing.expectEqualStrings("1, \"C:\\\\\", 3", backslash.args);
+    try std.testing.expectEqual(@as(?i64, 3), backslash.return_value);
+}
+
+test "nested braces and parentheses stay inside the args" {
+    const allocator = std.testing.allocator;
+
+    const stat = (try parseLine(allocator, "10:23:45.123456 newfstatat(AT_FDCWD, \"x\", {st_mode=S_IFDIR|0555, st_size=(11)}, 0) = 0")).?;
+    try std.testing.expectEqualStrings("newfstatat", stat.syscall);
+    try std.testing.expectEqualStrings("AT_FDCWD, \"x\", {st_mode=S_IFDIR|0555, st_size=(11)}, 0", stat.args);
+    try std.testing.expectEqual(@as(?i64, 0), stat.return_value);
+
+    const resumed = (try parseLine(allocator, "10:23:45.123456 <... wait4 resumed>[{WIFEXITED(s) && WEXITSTATUS(s) == 0}], 0, NULL) = 4242")).?;
+    try std.testing.expectEqualStrings("[{WIFEXITED(s) && WEXITSTATUS(s) == 0}], 0, NULL", resumed.args);
+    try std.testing.expectEqual(@as(?i64, 4242), resumed.return_value);
+}
+
+test "parentheses in the error message do not affect the args" {
+    const allocator = std.testing.allocator;
+
+    const connect = (try parseLine(allocator, "10:23:45.123456 connect(3, {sa_family=AF_UNIX, sun_path=\"/run/x\"}, 110) = -1 ECONNREFUSED (Connection refused) <0.000012>")).?;
+    try std.testing.expectEqualStrings("3, {sa_family=AF_UNIX, sun_path=\"/run/x\"}, 110", connect.args);
+    try std.testing.expectEqualStrings("Connection refused", connect.error_message.?);
+    try std.testing.expectEqual(@as(?f64, 0.000012), connect.duration);
+
+    const nested = (try parseLine(allocator, "10:23:45.123456 ioctl(3, TCGETS, 0x7ffd) = -1 ENOTTY (Inappropriate ioctl for device (tty))")).?;
+    try std.testing.expectEqualStrings("3, TCGETS, 0x7ffd", nested.args);
+    try std.testing.expectEqualStrings("Inappropriate ioctl for device (tty)", nested.error_message.?);
+}
+
+test "parseLine flags truncated string arguments" {
+    const allocator = std.testing.allocator;
+
+    const long = (try parseLine(allocator, "10:23:45.123456 write(1, \"very long string\"..., 4096) = 4096")).?;
+    try std.testing.expect(long.truncated);
+
+    const dots = (try parseLine(allocator, "10:23:45.123456 write(1, \"wait...\", 7) = 7")).?;
+    try std.testing.expect(!dots.truncated);
+
+    const escaped = (try parseLine(allocator, "10:23:45.123456 write(1, \"a\\\"...\", 5) = 5")).?;
+    try std.testing.expect(!escaped.truncated);
+
+    const unfinished = (try parseLine(allocator, "10:23:45.123456 read(3, \"partial\"..., 8192 <unfinished ...>")).?;
+    try std.testing.expect(unfinished.truncated);
+}
+
+test "return_unknown is set only for a literal ? return" {
+    const allocator = std.testing.allocator;
+
+    const exit_group = (try parseLine(allocator, "10:23:45.123456 exit_group(0) = ?")).?;
+    try std.testing.expectEqual(@as(?i64, null), exit_group.return_value);
+    try std.testing.expect(exit_group.return_unknown);
+
+    const resumed = (try parseLine(allocator, "10:23:45.123456 <... futex resumed>) = ?")).?;
+    try std.testing.expect(resumed.return_unknown);
+
+    const unfinished = (try parseLine(allocator, "10:23:45.123456 futex(0x7f00, FUTEX_WAIT_PRIVATE, 0, NULL <unfinished ...>")).?;
+    try std.testing.expect(!unfinished.return_unknown);
+
+    // A struct return is unparsed as a number but is not unknown
+    const structured = (try parseLine(allocator, "10:23:45.123456 pipe2([3, 4], 0) = 0")).?;
+    try std.testing.expect(!structured.return_unknown);
+}
+
+test "restart_syscall keeps its <...> argument" {
+    const allocator = std.testing.allocator;
+
+    const syscall = (try parseLine(allocator, "10:23:45.123456 restart_syscall(<... resuming interrupted read ...>) = 0 <0.500000>")).?;
+    try std.testing.expectEqualStrings("restart_syscall", syscall.syscall);
+    try std.testing.expectEqualStrings("<... resuming interrupted read ...>", syscall.args);
+    try std.testing.expectEqual(@as(?i64, 0), syscall.return_value);
+    try std.testing.expectEqual(@as(?[]const u8, null), syscall.error_code);
+    try std.testing.expectEqual(@as(?f64, 0.5), syscall.duration);
+
+    const unfinished = (try parseLine(allocator, "[pid  42] 10:23:45.123456 restart_syscall(<... resuming interrupted futex ...> <unfinished ...>")).?;
+    try std.testing.expectEqualStrings("restart_syscall", unfinished.syscall);
+    try std.testing.expect(unfinished.unfinished);
+}
+
+test "ERESTART* after a ? return is the error code" {
+    const allocator = std.testing.allocator;
+
+    const read = (try parseLine(allocator, "10:23:45.123456 read(0, 0x7ffd5c3e1a2f, 1024) = ? ERESTARTSYS (To be restarted if SA_RESTART is set) <1.250000>")).?;
+    try std.testing.expect(read.return_unknown);
+    try std.testing.expectEqual(@as(?i64, null), read.return_value);
+    try std.testing.expectEqualStrings("ERESTARTSYS", read.error_code.?);
+    try std.testing.expectEqualStrings("To be restarted if SA_RESTART is set", read.error_message.?);
+    try std.testing.expectEqual(@as(?[]const u8, null), read.return_annotation);
+    try std.testing.expectEqual(@as(?i32, 512), read.errno);
+    try std.testing.expectEqual(@as(?f64, 1.25), read.duration);
+
+    const resumed = (try parseLine(allocator, "10:23:45.123456 <... nanosleep resumed>0x7ffd5c3e1a40) = ? ERESTART_RESTARTBLOCK (Interrupted by signal)")).?;
+    try std.testing.expect(resumed.resumed);
+    try std.testing.expectEqualStrings("ERESTART_RESTARTBLOCK", resumed.error_code.?);
+    try std.testing.expectEqualStrings("Interrupted by signal", resumed.error_message.?);
+
+    // Without a message the row is still kept
+    const bare = (try parseLine(allocator, "10:23:45.123456 pause() = ? ERESTARTNOHAND")).?;
+    try std.testing.expectEqualStrings("ERESTARTNOHAND", bare.error_code.?);
+    try std.testing.expectEqual(@as(?[]const u8, null), bare.error_message);
+}
+
+test "hex returns fill return_addr even above i64 max" {
+    const allocator = std.testing.allocator;
+
+    const brk = (try parseLine(allocator, "10:23:45.123456 brk(NULL) = 0x55edad95f000 <0.000004>")).?;
+    try std.testing.expectEqual(@as(?u64, 0x55edad95f000), brk.return_addr);
+    try std.testing.expectEqual(@as(?i64, 0x55edad95f000), brk.return_value);
+
+    // The high bit is set: no signed value, but the address is kept as is
+    const high = (try parseLine(allocator, "10:23:45.123456 <... mmap resumed>) = 0xffffffffff600000")).?;
+    try std.testing.expectEqual(@as(?u64, 0xffffffffff600000), high.return_addr);
+    try std.testing.expectEqual(@as(?i64, null), high.return_value);
+    try std.testing.expectEqual(@as(?[]const u8, null), high.return_value_raw);
+
+    const decimal = (try parseLine(allocator, "10:23:45.123456 read(3, \"abc\", 3) = 3")).?;
+    try std.testing.expectEqual(@as(?u64, null), decimal.return_addr);
+}
+
+test "relative timestamps from -r go to rel_time" {
+    const allocator = std.testing.allocator;
+
+    const syscall = (try parseLine(allocator, "     0.000102 brk(NULL)               = 0x55555557b000")).?;
+    try std.testing.expectEqualStrings("", syscall.timestamp);
+    try std.testing.expectEqual(@as(?f64, 0.000102), syscall.rel_time);
+    try std.testing.expectEqualStrings("brk", syscall.syscall);
+    try std.testing.expectEqual(@as(?i64, null), syscall.time_of_day_us);
+
+    // With -f the PID prefix comes first, then the padded delta
+    const prefixed = (try parseLine(allocator, "[pid  4242]      1.250000 close(3) = 0 <0.000004>")).?;
+    try std.testing.expectEqual(@as(?i32, 4242), prefixed.pid);
+    try std.testing.expectEqual(@as(?f64, 1.25), prefixed.rel_time);
+
+    // Colons later in the line don't make the delta a clock time
+    const connect = (try parseLine(allocator, "0.000031 connect(3, {sa_family=AF_INET6, sin6_addr=inet_pton(AF_INET6, \"::1\")}, 28) = 0")).?;
+    try std.testing.expectEqualStrings("connect", connect.syscall);
+    try std.testing.expectEqual(@as(?f64, 0.000031), connect.rel_time);
+
+    const bare = (try parseLine(allocator, "4242      0.000010 <... read resumed>\"x\", 1) = 1")).?;
+    try std.testing.expectEqual(@as(?i32, 4242), bare.pid);
+    try std.testing.expectEqual(@as(?f64, 0.00001), bare.rel_time);
+    try std.testing.expect(bare.resumed);
+}
+
+test "a forced time format rejects the other kind of field" {
+    const allocator = std.testing.allocator;
+
+    try std.testing.expect((try parseLineAs(allocator, "0.000102 brk(NULL) = 0x1000", .clock)) == null);
+    try std.testing.expect((try parseLineAs(allocator, "10:23:45.123456 brk(NULL) = 0x1000", .relative)) == null);
+
+    const clock = (try parseLineAs(allocator, "10:23:45.123456 brk(NULL) = 0x1000", .clock)).?;
+    try std.testing.expectEqualStrings("10:23:45.123456", clock.timestamp);
+    try std.testing.expectEqual(@as(?f64, null), clock.rel_time);
+}
+
+test "epoch timestamps from -ttt fill wall_time and time_of_day" {
+    const allocator = std.testing.allocator;
+
+    const syscall = (try parseLine(allocator, "1731276071.524449 brk(NULL) = 0x55555557b000 <0.000010>")).?;
+    try std.testing.expectEqualStrings("1731276071.524449", syscall.timestamp);
+    try std.testing.expectEqual(@as(?i64, 1731276071_524449), syscall.wall_time_us);
+    // 1731276071 is 2024-11-10 22:01:11 UTC
+    try std.testing.expectEqual(@as(?i64, ((22 * 60 + 1) * 60 + 11) * std.time.us_per_s + 524449), syscall.time_of_day_us);
+    try std.testing.expectEqual(@as(?f64, null), syscall.rel_time);
+
+    const prefixed = (try parseLine(allocator, "[pid  77] 1731276071.5 close(3) = 0")).?;
+    try std.testing.expectEqual(@as(?i32, 77), prefixed.pid);
+    try std.testing.expectEqual(@as(?i64, 1731276071_500000), prefixed.wall_time_us);
+
+    // The clock path is unchanged: colons mean -t/-tt
+    const clock = (try parseLine(allocator, "22:01:11.524449 brk(NULL) = 0x55555557b000")).?;
+    try std.testing.expectEqualStrings("22:01:11.524449", clock.timestamp);
+    try std.testing.expectEqual(@as(?i64, null), clock.wall_time_us);
+    try std.testing.expectEqual(@as(?i64, ((22 * 60 + 1) * 60 + 11) * std.time.us_per_s + 524449), clock.time_of_day_us);
+}
+
+test "forcing --time-format epoch or relative overrides detection" {
+    const allocator = std.testing.allocator;
+
+    // Small values auto-detect as -r deltas, but can be forced to epoch times
+    const forced = (try parseLineAs(allocator, "5.000001 getpid() = 42", .epoch)).?;
+    try std.testing.expectEqual(@as(?i64, 5_000001), forced.wall_time_us);
+    try std.testing.expectEqual(@as(?f64, null), forced.rel_time);
+
+    const relative = (try parseLineAs(allocator, "1731276071.524449 getpid() = 42", .relative)).?;
+    try std.testing.expectEqual(@as(?i64, null), relative.wall_time_us);
+    try std.testing.expectEqual(@as(?f64, 1731276071.524449), relative.rel_time);
+
+    try std.testing.expectEqual(@as(?i64, null), parseEpochMicros("abc.5"));
+    try std.testing.expectEqual(@as(?i64, 12_340000), parseEpochMicros("12.34"));
+}
+
+test "argCount counts top-level arguments only" {
+    try std.testing.expectEqual(@as(i32, 0), argCount(""));
+    try std.testing.expectEqual(@as(i32, 1), argCount("NULL"));
+    try std.testing.expectEqual(@as(i32, 4), argCount("AT_FDCWD, \"/etc/ld.so.cache\", {st_mode=S_IFREG|0644, st_size=94423, ...}, AT_EMPTY_PATH"));
+    try std.testing.expectEqual(@as(i32, 3), argCount("3, \"a, b\", 4"));
+    try std.testing.expectEqual(@as(i32, 2), argCount("[{fd=3, events=POLLIN}, {fd=4, events=POLLOUT}], 2"));
+}
+
+test "arg_count is set for complete calls but not for split halves" {
+    const allocator = std.testing.allocator;
+
+    const brk_empty = (try parseLine(allocator, "10:23:45.123456 brk() = 0x1000")).?;
+    try std.testing.expectEqual(@as(?i32, 0), brk_empty.arg_count);
+
+    const brk = (try parseLine(allocator, "10:23:45.123456 brk(NULL) = 0x1000")).?;
+    try std.testing.expectEqual(@as(?i32, 1), brk.arg_count);
+
+    const stat = (try parseLine(allocator, "10:23:45.123456 newfstatat(AT_FDCWD, \"/etc/ld.so.cache\", {st_mode=S_IFREG|0644, st_size=94423, ...}, AT_EMPTY_PATH) = 0")).?;
+    try std.testing.expectEqual(@as(?i32, 4), stat.arg_count);
+
+    const unfinished = (try parseLine(allocator, "10:23:45.123456 read(3, <unfinished ...>")).?;
+    try std.testing.expectEqual(@as(?i32, null), unfinished.arg_count);
+
+    const signal = (try parseLine(allocator, "10:23:45.123456 --- SIGCHLD {si_signo=SIGCHLD, si_code=CLD_EXITED} ---")).?;
+    try std.testing.expectEqual(@as(?i32, null), signal.arg_count);
+}
+
+test "writeArgsJson emits one string per top-level argument" {
+    var buffer: [512]u8 = undefined;
+    var writer = std.Io.Writer.fixed(&buffer);
+
+    try writeArgsJson(&writer, "AT_FDCWD, \"/tmp/a,b\", {st_mode=S_IFDIR|0555, st_size=(11)}, [1, 2]");
+    try std.testing.expectEqualStrings(
+        \\["AT_FDCWD","\"/tmp/a,b\"","{st_mode=S_IFDIR|0555, st_size=(11)}","[1, 2]"]
+    , writer.buffered());
+
+    var empty_buffer: [8]u8 = undefined;
+    var empty = std.Io.Writer.fixed(&empty_buffer);
+    try writeArgsJson(&empty, "");
+    try std.testing.expectEqualStrings("[]", empty.buffered());
+}
+
+test "resumed lines without a timestamp still parse" {
+    const allocator = std.testing.allocator;
+
+    const resumed = (try parseLine(allocator, "<... read resumed>\"data\", 100) = 4 <0.000003>")).?;
+    try std.testing.expectEqualStrings("", resumed.timestamp);
+    try std.testing.expectEqualStrings("read", resumed.syscall);
+    try std.testing.expectEqualStrings("\"data\", 100", resumed.args);
+    try std.testing.expectEqual(@as(?i64, 4), resumed.return_value);
+    try std.testing.expect(resumed.resumed);
+
+    // The PID prefix is still taken off first
+    const prefixed = (try parseLine(allocator, "[pid  812] <... futex resumed>) = 0")).?;
+    try std.testing.expectEqual(@as(?i32, 812), prefixed.pid);
+    try std.testing.expectEqualStrings("", prefixed.timestamp);
+    try std.testing.expectEqualStrings("futex", prefixed.syscall);
+
+    var diagnostic = Diagnostic{};
+    try std.testing.expectError(error.MissingReturn, parseLineVerbose(allocator, "<... read resumed>\"data\", 100)", &diagnostic));
+}
+
+test "malformed lines that share delimiters or overflow are rejected" {
+    const allocator = std.testing.allocator;
+
+    // The signal frame's "--- " and " ---" overlap
+    try std.testing.expectEqual(@as(?Syscall, null), try parseLine(allocator, "10:00:00.000001 --- ---"));
+    try std.testing.expectEqual(@as(?Syscall, null), try parseLine(allocator, "10:00:00.000001 --- SIG"));
+
+    // Seconds that fit in i64 microseconds only without the fraction
+    try std.testing.expectEqual(@as(?i64, null), parseEpochMicros("9223372036854.999999"));
+    try std.testing.expectEqual(@as(?i64, null), parseTimeOfDay("-1:00:00.000001"));
+    try std.testing.expectEqual(@as(?i64, null), parseTimeOfDay("+1:00:00"));
+}
+
+test "every prefix of a valid line parses or is rejected without panicking" {
+    const allocator = std.testing.allocator;
+    const lines = [_][]const u8{
+        "[pid  812] 22:21:11.675759 access(\"/etc/ld-nix.so.preload\", R_OK) = -1 ENOENT (No such file or directory) <0.000006>",
+        "1700000000.123456 fstat(3, {st_mode=S_IFREG|0644, st_size=4037, ...}) = 0 (Timeout) <0.000005>",
+        "22:21:24.927885 poll([{fd=8, events=POLLIN}], 2, -1 <unfinished ...>",
+        "<... poll resumed>) = 1 ([{fd=8, revents=POLLIN}]) <0.000115>",
+        "0.000123 --- SIGCHLD {si_signo=SIGCHLD, si_code=CLD_EXITED} ---",
+        "22:21:24.990000 +++ killed by SIGSEGV (core dumped) +++",
+        "10:00:00.000001 read(3, \"caf\xc3\xa9 \\\"x)\", 5) = ? ERESTARTSYS (To be restarted) # note",
+    };
+
+    var diagnostic = Diagnostic{};
+    for (lines) |line| {
+        for (0..line.len + 1) |end| {
+            _ = try parseLine(allocator, line[0..end]);
+            _ = parseLineVerbose(allocator, line[0..end], &diagnostic) catch {};
+            // The tail alone, as a line cut at the start would look
+            _ = try parseLine(allocator, line[end..]);
+        }
+    }
+}
+
+test "random bytes never make parseLine panic" {
+    const allocator = std.testing.allocator;
+    var prng = std.Random.DefaultPrng.init(0x5eed);
+    const random = prng.random();
+
+    // Mostly strace punctuation, so the inputs get past the first checks
+    const alphabet = "0123456789:. ()[]{}<>=-+?\"\\,#xSIGERESTART_resumed unfinished...pid\t\r\x00\xc3\xa9\xff";
+    var buffer: [256]u8 = undefined;
+    var diagnostic = Diagnostic{};
+    for (0..20_000) |_| {
+        const len = random.uintLessThan(usize, buffer.len);
+        for (buffer[0..len]) |*byte| {
+            byte.* = if (random.uintLessThan(u8, 8) == 0) random.int(u8) else alphabet[random.uintLessThan(usize, alphabet.len)];
+        }
+        _ = try parseLine(allocator, buffer[0..len]);
+        _ = parseLineVerbose(allocator, buffer[0..len], &diagnostic) catch {};
+    }
+}
+
+test "fuzz parseLine" {
+    // zig build test --fuzz explores from these; a normal test run tries each once
+    try std.testing.fuzz({}, struct {
+        fn testOne(_: void, input: []const u8) anyerror!void {
+            if (try parseLine(std.testing.allocator, input)) |syscall| {
+                std.mem.doNotOptimizeAway(syscall);
+            }
+        }
+    }.testOne, .{ .corpus = &.{
+        "22:21:11.675122 set_robust_list(0x7fa8e531c4a0, 24) = 0 <0.000009>",
+        "[pid 7] 22:21:24.927885 poll([{fd=8, events=POLLIN}], 2, -1 <unfinished ...>",
+        "<... poll resumed>) = 1 ([{fd=8, revents=POLLIN}]) <0.000115>",
+        "1700000000.123456 --- SIGCHLD {si_signo=SIGCHLD} ---",
+    } });
+}
+
+test "a trailing carriage return doesn't reach the duration" {
+    const allocator = std.testing.allocator;
+
+    const syscall = (try parseLine(allocator, "10:23:45.123456 open(\"/tmp/file\", O_RDONLY) = -1 ENOENT (No such file or directory) <0.000042>\r")).?;
+    try std.testing.expectEqualStrings("ENOENT", syscall.error_code.?);
+    try std.testing.expectEqual(@as(?f64, 0.000042), syscall.duration);
+
+    const no_duration = (try parseLine(allocator, "10:23:45.123456 getpid() = 12345\r\n")).?;
+    try std.testing.expectEqual(@as(?i64, 12345), no_duration.return_value);
+}
+
+test "multi-byte UTF-8 in args, messages and names stays whole" {
+    const allocator = std.testing.allocator;
+
+    const open = (try parseLine(allocator, "10:00:00.000001 openat(AT_FDCWD, \"/tmp/café.txt\", O_RDONLY) = 3 <0.000010>")).?;
+    try std.testing.expectEqualStrings("openat", open.syscall);
+    try std.testing.expectEqualStrings("AT_FDCWD, \"/tmp/café.txt\", O_RDONLY", open.args);
+    try std.testing.expectEqualStrings("\"/tmp/café.txt\"", nthArg(open.args, 1).?);
+    try std.testing.expectEqual(@as(?f64, 0.000010), open.duration);
+    try std.testing.expect(std.unicode.utf8ValidateSlice(open.args));
+
+    // A character right before the closing paren and the delimiters after it
+    const tail = (try parseLine(allocator, "10:00:00.000001 mkdir(\"/srv/日本語\", 0755) = -1 EEXIST (File exists 🙂) <0.000002>")).?;
+    try std.testing.expectEqualStrings("\"/srv/日本語\", 0755", tail.args);
+    try std.testing.expectEqualStrings("File exists 🙂", tail.error_message.?);
+
+    const resumed = (try parseLine(allocator, "10:00:00.000002 <... read resumed>\"naïve\", 6) = 6 (ok ü) <0.000001>")).?;
+    try std.testing.expectEqualStrings("\"naïve\", 6", resumed.args);
+    try std.testing.expectEqualStrings("ok ü", resumed.return_annotation.?);
+
+    // Truncated strings and JSON args keep the characters intact
+    const truncated = (try parseLine(allocator, "10:00:00.000003 write(1, \"ñññ\"..., 4096) = 4096")).?;
+    try std.testing.expect(truncated.truncated);
+    var json_buffer: [128]u8 = undefined;
+    var writer = std.Io.Writer.fixed(&json_buffer);
+    try writeArgsJson(&writer, truncated.args);
+    try std.testing.expectEqualStrings("[\"1\",\"\\\"ñññ\\\"...\",\"4096\"]", writer.buffered());
+}
+
+test "parseDurationNs reads the digits exactly" {
+    try std.testing.expectEqual(@as(?i64, 42_000), parseDurationNs("0.000042"));
+    try std.testing.expectEqual(@as(?i64, 1_001_000_000), parseDurationNs("1.001000"));
+    try std.testing.expectEqual(@as(?i64, 123_456_789), parseDurationNs("0.1234567891"));
+    try std.testing.expectEqual(@as(?i64, 3_000_000_000), parseDurationNs("3"));
+    try std.testing.expectEqual(@as(?i64, null), parseDurationNs("-0.5"));
+    try std.testing.expectEqual(@as(?i64, null), parseDurationNs("0.5s"));
+    try std.testing.expectEqual(@as(?i64, null), parseDurationNs("99999999999.0"));
+
+    // 0.000042 is not exact as a double, but duration_ns is
+    const syscall = (try parseLine(std.testing.allocator, "10:23:45.123456 read(3, \"\", 1) = 0 <0.000042>")).?;
+    try std.testing.expectEqual(@as(?i64, 42_000), syscall.duration_ns);
+    const resumed = (try parseLine(std.testing.allocator, "10:23:45.123457 <... read resumed>\"\", 1) = 0 <1.500000>")).?;
+    try std.testing.expectEqual(@as(?i64, 1_500_000_000), resumed.duration_ns);
+}
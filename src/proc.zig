@@ -0,0 +1,76 @@
+const std = @import("std");
+
+/// Lazy PID -> command name lookup backed by a /proc snapshot
+/// Reads <proc_dir>/<pid>/comm on first sight of a PID and caches the result,
+/// including misses. Shared by all workers, so lookups take a mutex
+pub const CommLookup = struct {
+    allocator: std.mem.Allocator,
+    proc_dir: []const u8,
+    mutex: std.Thread.Mutex = .{},
+    cache: std.AutoHashMapUnmanaged(i32, ?[]const u8) = .{},
+
+    pub fn init(allocator: std.mem.Allocator, proc_dir: []const u8) CommLookup {
+        return .{ .allocator = allocator, .proc_dir = proc_dir };
+    }
+
+    pub fn deinit(self: *CommLookup) void {
+        var it = self.cache.valueIterator();
+        while (it.next()) |comm| {
+            if (comm.*) |name| self.allocator.free(name);
+        }
+        self.cache.deinit(self.allocator);
+    }
+
+    /// Command name for pid, or null if the snapshot has no readable comm file
+    /// The returned slice is owned by the cache and valid until deinit
+    pub fn lookup(self: *CommLookup, pid: i32) !?[]const u8 {
+        self.mutex.lock();
+        defer self.mutex.unlock();
+
+        const entry = try self.cache.getOrPut(self.allocator, pid);
+        if (!entry.found_existing) {
+            // Never leave the slot undefined if the read fails
+            entry.value_ptr.* = null;
+            entry.value_ptr.* = try self.readComm(pid);
+        }
+        return entry.value_ptr.*;
+    }
+
+    fn readComm(self: *CommLookup, pid: i32) !?[]const u8 {
+        var path_buffer: [std.fs.max_path_bytes]u8 = undefined;
+        const path = try std.fmt.bufPrint(&path_buffer, "{s}/{d}/comm", .{ self.proc_dir, pid });
+
+        // The kernel caps comm at 16 bytes; anything unreadable is treated as missing
+        var contents_buffer: [256]u8 = undefined;
+        const contents = std.fs.cwd().readFile(path, &contents_buffer) catch return null;
+        const name = std.mem.trimRight(u8, contents, "\n");
+        if (name.len == 0) return null;
+        return try self.allocator.dupe(u8, name);
+    }
+};
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+test "comm lookup reads and caches fake proc entries" {
+    const test_dir = "zig-cache/test-proc";
+    try std.fs.cwd().makePath(test_dir ++ "/100");
+    try std.fs.cwd().makePath(test_dir ++ "/200");
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    try std.fs.cwd().writeFile(.{ .sub_path = test_dir ++ "/100/comm", .data = "nginx\n" });
+    try std.fs.cwd().writeFile(.{ .sub_path = test_dir ++ "/200/comm", .data = "bash\n" });
+
+    var comms = CommLookup.init(std.testing.allocator, test_dir);
+    defer comms.deinit();
+
+    try std.testing.expectEqualStrings("nginx", (try comms.lookup(100)).?);
+    try std.testing.expectEqualStrings("bash", (try comms.lookup(200)).?);
+    try std.testing.expectEqual(@as(?[]const u8, null), try comms.lookup(300));
+
+    // Second lookup is served from the cache, even after the file is gone
+    try std.fs.cwd().deleteFile(test_dir ++ "/100/comm");
+    try std.testing.expectEqualStrings("nginx", (try comms.lookup(100)).?);
+    try std.testing.expectEqual(@as(u32, 3), comms.cache.count());
+}
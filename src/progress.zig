@@ -1,17 +1,22 @@
 const std = @import("std");
+const log = @import("log.zig");
 
 /// Aggregate progress bar for parallel processing
+/// Shows files done and, because a few huge files would otherwise stall it, bytes read
 pub const AggregateProgress = struct {
     total_files: usize,
+    /// Sum of the input sizes, known before any worker starts (0 hides the byte bar)
+    total_bytes: u64,
     start_time: i64,
     enabled: bool,
 
-    pub fn init(total_files: usize) AggregateProgress {
+    pub fn init(total_files: usize, total_bytes: u64) AggregateProgress {
         const stdout = std.fs.File.stdout();
-        const enabled = stdout.isTty();
+        const enabled = stdout.isTty() and log.enabled(.normal);
 
         return AggregateProgress{
             .total_files = total_files,
+            .total_bytes = total_bytes,
             .start_time = std.time.milliTimestamp(),
             .enabled = enabled,
         };
@@ -22,6 +27,7 @@ pub const AggregateProgress = struct {
         self: *AggregateProgress,
         files_complete: usize,
         lines_processed: usize,
+        bytes_read: u64,
     ) !void {
         if (!self.enabled) return;
 
@@ -37,14 +43,21 @@ pub const AggregateProgress = struct {
         else
             0;
 
-        // Format: [8/10] 80% | 125483 lines | 112500 lines/s
+        // Format: [8/10] 80% | 812.4/1024.0 MiB 79% | 125483 lines | 112500 lines/s
         var buffer: [256]u8 = undefined;
-        const msg = try std.fmt.bufPrint(
-            &buffer,
-            "\r[{d}/{d}] {d}% | {d} lines | {d:.0} lines/s    ",
-            .{ files_complete, self.total_files, percent, lines_processed, rate },
-        );
-        try std.fs.File.stdout().writeAll(msg);
+        var writer = std.Io.Writer.fixed(&buffer);
+        try writer.print("\r[{d}/{d}] {d}% | ", .{ files_complete, self.total_files, percent });
+        if (self.total_bytes > 0) {
+            // Clamped: a last line without a newline is counted as if it had one
+            const shown = @min(bytes_read, self.total_bytes);
+            try writer.print("{d:.1}/{d:.1} MiB {d}% | ", .{ mebibytes(shown), mebibytes(self.total_bytes), (shown * 100) / self.total_bytes });
+        }
+        try writer.print("{d} lines | {d:.0} lines/s    ", .{ lines_processed, rate });
+        try std.fs.File.stdout().writeAll(writer.buffered());
+    }
+
+    fn mebibytes(bytes: u64) f64 {
+        return @as(f64, @floatFromInt(bytes)) / (1024 * 1024);
     }
 
     /// Finish and print newline
@@ -64,23 +77,24 @@ pub const AggregateProgress = struct {
 // ============================================================================
 
 test "aggregate progress init" {
-    const progress = AggregateProgress.init(10);
+    const progress = AggregateProgress.init(10, 4096);
     try std.testing.expectEqual(@as(usize, 10), progress.total_files);
+    try std.testing.expectEqual(@as(u64, 4096), progress.total_bytes);
 }
 
 test "aggregate progress render disabled" {
-    var progress = AggregateProgress.init(10);
+    var progress = AggregateProgress.init(10, 4096);
     progress.enabled = false; // Disable for testing
 
     // Should not error when disabled
-    try progress.render(5, 1000);
+    try progress.render(5, 1000, 2048);
     try progress.finish();
 }
 
 test "aggregate progress deinit is safe" {
-    var progress = AggregateProgress.init(10);
+    var progress = AggregateProgress.init(10, 0);
     progress.enabled = false;
     defer progress.deinit(); // Should work with defer
 
-    try progress.render(5, 1000);
+    try progress.render(5, 1000, 0);
 }
@@ -0,0 +1,73 @@
+const std = @import("std");
+
+/// Sidecar file collecting lines that did not parse (--rejects)
+/// Each line is written as "<trace_file>:<line_number>: <line>"
+/// Shared by all workers, so writes are serialized with a mutex
+pub const RejectsFile = struct {
+    allocator: std.mem.Allocator,
+    file: std.fs.File,
+    file_writer: std.fs.File.Writer,
+    buffer: [64 * 1024]u8,
+    mutex: std.Thread.Mutex = .{},
+    lines: usize = 0,
+
+    /// Create (or truncate) the rejects file
+    /// Heap-allocated because the writer points into the struct's own buffer
+    pub fn create(allocator: std.mem.Allocator, path: []const u8) !*RejectsFile {
+        const self = try allocator.create(RejectsFile);
+        errdefer allocator.destroy(self);
+
+        const file = try std.fs.cwd().createFile(path, .{});
+        self.* = .{
+            .allocator = allocator,
+            .file = file,
+            .file_writer = undefined,
+            .buffer = undefined,
+        };
+        self.file_writer = file.writer(&self.buffer);
+        return self;
+    }
+
+    /// Flush remaining output and close the file (best-effort, for use with defer)
+    pub fn destroy(self: *RejectsFile) void {
+        self.file_writer.interface.flush() catch {};
+        self.file.close();
+        self.allocator.destroy(self);
+    }
+
+    pub fn write(self: *RejectsFile, trace_file: []const u8, line_number: usize, line: []const u8) !void {
+        self.mutex.lock();
+        defer self.mutex.unlock();
+
+        try self.file_writer.interface.print("{s}:{d}: {s}\n", .{ trace_file, line_number, line });
+        self.lines += 1;
+    }
+
+    pub fn flush(self: *RejectsFile) !void {
+        self.mutex.lock();
+        defer self.mutex.unlock();
+
+        try self.file_writer.interface.flush();
+    }
+};
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+test "rejects file prefixes each line with its source" {
+    const test_dir = "zig-cache/test-rejects";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    const path = "zig-cache/test-rejects/rejects.txt";
+    const rejects = try RejectsFile.create(std.testing.allocator, path);
+    try rejects.write("trace.1", 3, "garbage");
+    try rejects.write("trace.2", 10, "10:00:00.000001 read(3");
+    try std.testing.expectEqual(@as(usize, 2), rejects.lines);
+    rejects.destroy();
+
+    const contents = try std.fs.cwd().readFileAlloc(std.testing.allocator, path, 1024);
+    defer std.testing.allocator.free(contents);
+    try std.testing.expectEqualStrings("trace.1:3: garbage\ntrace.2:10: 10:00:00.000001 read(3\n", contents);
+}
@@ -0,0 +1,180 @@
+const std = @import("std");
+const types = @import("types.zig");
+const FileStats = types.FileStats;
+
+/// Results of one processed file (or one range of a split file) in the --stats-json report
+pub const FileEntry = struct {
+    trace_file: []const u8,
+    total_lines: usize,
+    parsed_lines: usize,
+    failed_lines: usize,
+    filtered_lines: usize,
+    lossy_lines: usize,
+    skipped: bool,
+    duration_sec: f64,
+};
+
+/// Per-file results gathered from every worker for --stats-json
+/// Shared by all workers, so records are serialized with a mutex
+pub const FileLog = struct {
+    allocator: std.mem.Allocator,
+    mutex: std.Thread.Mutex = .{},
+    entries: std.ArrayListUnmanaged(FileEntry) = .{},
+
+    pub fn init(allocator: std.mem.Allocator) FileLog {
+        return .{ .allocator = allocator };
+    }
+
+    pub fn deinit(self: *FileLog) void {
+        self.entries.deinit(self.allocator);
+    }
+
+    /// Add one file's results; trace_file is not copied and must outlive the log
+    pub fn record(self: *FileLog, trace_file: []const u8, stats: FileStats, duration_sec: f64) !void {
+        self.mutex.lock();
+        defer self.mutex.unlock();
+
+        try self.entries.append(self.allocator, .{
+            .trace_file = trace_file,
+            .total_lines = stats.total_lines,
+            .parsed_lines = stats.parsed_lines,
+            .failed_lines = stats.failed_lines,
+            .filtered_lines = stats.filtered_lines,
+            .lossy_lines = stats.lossy_lines,
+            .skipped = stats.skipped,
+            .duration_sec = duration_sec,
+        });
+    }
+
+    /// Entries ordered by path, so reports are stable whichever worker finished first
+    /// Call once the workers are done
+    pub fn sorted(self: *FileLog) []const FileEntry {
+        std.mem.sort(FileEntry, self.entries.items, {}, entryLessThan);
+        return self.entries.items;
+    }
+
+    fn entryLessThan(_: void, a: FileEntry, b: FileEntry) bool {
+        return std.mem.lessThan(u8, a.trace_file, b.trace_file);
+    }
+};
+
+/// Wall-clock seconds spent in each part of a run
+pub const Phases = struct {
+    /// Opening (or creating) the database and its schema
+    open_sec: f64,
+    /// Reading and parsing every trace file, including the appends
+    ingest_sec: f64,
+    /// Everything after ingest: --dedupe, --process-tree, --optimize, --verify, exports
+    finalize_sec: f64,
+    total_sec: f64,
+};
+
+/// Machine-readable run summary written by --stats-json
+/// Field names are part of the output format; add fields rather than renaming them
+pub const RunReport = struct {
+    run_id: ?[]const u8,
+    /// Null for --dry-run
+    database: ?[]const u8,
+    total_files: usize,
+    files_processed: usize,
+    files_with_errors: usize,
+    files_skipped: usize,
+    files_unchanged: usize,
+    total_lines: usize,
+    parsed_lines: usize,
+    failed_lines: usize,
+    filtered_lines: usize,
+    lossy_lines: usize,
+    /// failed_lines / total_lines (0 for an empty run)
+    failure_rate: f64,
+    /// total_lines / phases.ingest_sec
+    lines_per_sec: f64,
+    interrupted: bool,
+    phases: Phases,
+    files: []const FileEntry,
+
+    /// Fill the counters and rates from a run's statistics
+    pub fn init(stats: types.ParallelStats, phases: Phases, files: []const FileEntry) RunReport {
+        const lines: f64 = @floatFromInt(stats.total_lines);
+        return .{
+            .run_id = null,
+            .database = null,
+            .total_files = stats.total_files,
+            .files_processed = stats.files_processed,
+            .files_with_errors = stats.files_with_errors,
+            .files_skipped = stats.files_skipped,
+            .files_unchanged = stats.files_unchanged,
+            .total_lines = stats.total_lines,
+            .parsed_lines = stats.parsed_lines,
+            .failed_lines = stats.failed_lines,
+            .filtered_lines = stats.filtered_lines,
+            .lossy_lines = stats.lossy_lines,
+            .failure_rate = if (stats.total_lines > 0) @as(f64, @floatFromInt(stats.failed_lines)) / lines else 0,
+            .lines_per_sec = if (phases.ingest_sec > 0) lines / phases.ingest_sec else 0,
+            .interrupted = stats.interrupted,
+            .phases = phases,
+            .files = files,
+        };
+    }
+
+    /// Write the report as one indented JSON object followed by a newline
+    pub fn write(self: RunReport, writer: *std.Io.Writer) !void {
+        try std.json.Stringify.value(self, .{ .whitespace = .indent_2 }, writer);
+        try writer.writeByte('\n');
+    }
+
+    /// Write the report to path, or to stdout when path is "-"
+    pub fn writeToPath(self: RunReport, path: []const u8) !void {
+        const to_stdout = std.mem.eql(u8, path, "-");
+        const file = if (to_stdout) std.fs.File.stdout() else try std.fs.cwd().createFile(path, .{});
+        defer if (!to_stdout) file.close();
+
+        var buffer: [4096]u8 = undefined;
+        var file_writer = file.writer(&buffer);
+        try self.write(&file_writer.interface);
+        try file_writer.interface.flush();
+    }
+};
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+test "run report serializes counters, phases and sorted files" {
+    const allocator = std.testing.allocator;
+
+    var files = FileLog.init(allocator);
+    defer files.deinit();
+    try files.record("traces/trace.2", .{ .total_lines = 4, .parsed_lines = 3, .failed_lines = 1, .skipped = false }, 0.5);
+    try files.record("traces/trace.1", .{ .total_lines = 6, .parsed_lines = 6, .failed_lines = 0, .skipped = false }, 0.25);
+
+    var stats = types.ParallelStats.init();
+    stats.total_files = 2;
+    stats.files_processed = 2;
+    stats.total_lines = 10;
+    stats.parsed_lines = 9;
+    stats.failed_lines = 1;
+
+    var report = RunReport.init(stats, .{ .open_sec = 0.1, .ingest_sec = 0.8, .finalize_sec = 0.1, .total_sec = 1.0 }, files.sorted());
+    report.run_id = "run-1";
+
+    var out = std.Io.Writer.Allocating.init(allocator);
+    defer out.deinit();
+    try report.write(&out.writer);
+
+    const parsed = try std.json.parseFromSlice(std.json.Value, allocator, out.written(), .{});
+    defer parsed.deinit();
+    const root = parsed.value.object;
+
+    try std.testing.expectEqualStrings("run-1", root.get("run_id").?.string);
+    try std.testing.expect(root.get("database").? == .null);
+    try std.testing.expectEqual(@as(i64, 9), root.get("parsed_lines").?.integer);
+    try std.testing.expectApproxEqAbs(@as(f64, 0.1), root.get("failure_rate").?.float, 1e-9);
+    try std.testing.expectApproxEqAbs(@as(f64, 12.5), root.get("lines_per_sec").?.float, 1e-9);
+    try std.testing.expectApproxEqAbs(@as(f64, 0.8), root.get("phases").?.object.get("ingest_sec").?.float, 1e-9);
+
+    const entries = root.get("files").?.array.items;
+    try std.testing.expectEqual(@as(usize, 2), entries.len);
+    try std.testing.expectEqualStrings("traces/trace.1", entries[0].object.get("trace_file").?.string);
+    try std.testing.expectEqual(@as(i64, 1), entries[1].object.get("failed_lines").?.integer);
+}
@@ -0,0 +1,351 @@
+const std = @import("std");
+const database = @import("database.zig");
+const Database = database.Database;
+const types = @import("types.zig");
+const Syscall = types.Syscall;
+
+/// Destination for parsed syscalls
+/// The processor fans each parsed line out to every sink it is given
+pub const SyscallSink = struct {
+    ptr: *anyopaque,
+    vtable: *const VTable,
+    /// Short name used to attribute append errors to a sink
+    name: []const u8,
+
+    pub const VTable = struct {
+        append: *const fn (ptr: *anyopaque, trace_file: []const u8, pid: i32, syscall: Syscall) anyerror!void,
+        flush: *const fn (ptr: *anyopaque) anyerror!void,
+    };
+
+    pub fn append(self: SyscallSink, trace_file: []const u8, pid: i32, syscall: Syscall) !void {
+        return self.vtable.append(self.ptr, trace_file, pid, syscall);
+    }
+
+    pub fn flush(self: SyscallSink) !void {
+        return self.vtable.flush(self.ptr);
+    }
+};
+
+/// Sink that appends to a database connection
+/// Caller must call db.beginAppend() first and db.endAppend() when done
+pub fn databaseSink(db: *Database) SyscallSink {
+    return .{ .ptr = db, .vtable = &database_vtable, .name = "duckdb" };
+}
+
+const database_vtable = SyscallSink.VTable{
+    .append = databaseAppend,
+    .flush = databaseFlush,
+};
+
+fn databaseAppend(ptr: *anyopaque, trace_file: []const u8, pid: i32, syscall: Syscall) anyerror!void {
+    const db: *Database = @ptrCast(@alignCast(ptr));
+    return db.appendSyscall(trace_file, pid, syscall);
+}
+
+fn databaseFlush(ptr: *anyopaque) anyerror!void {
+    const db: *Database = @ptrCast(@alignCast(ptr));
+    return db.flushAppend();
+}
+
+/// Sink that writes one JSON object per line
+/// Shared by all workers, so appends are serialized with a mutex
+pub const JsonlSink = struct {
+    allocator: std.mem.Allocator,
+    file: std.fs.File,
+    file_writer: std.fs.File.Writer,
+    buffer: [64 * 1024]u8,
+    mutex: std.Thread.Mutex = .{},
+    records: usize = 0,
+
+    /// Create (or truncate) the output file
+    /// Heap-allocated because the writer points into the sink's own buffer
+    pub fn create(allocator: std.mem.Allocator, path: []const u8) !*JsonlSink {
+        const self = try allocator.create(JsonlSink);
+        errdefer allocator.destroy(self);
+
+        const file = try std.fs.cwd().createFile(path, .{});
+        self.* = .{
+            .allocator = allocator,
+            .file = file,
+            .file_writer = undefined,
+            .buffer = undefined,
+        };
+        self.file_writer = file.writer(&self.buffer);
+        return self;
+    }
+
+    /// Flush remaining output and close the file (best-effort, for use with defer)
+    pub fn destroy(self: *JsonlSink) void {
+        self.file_writer.interface.flush() catch {};
+        self.file.close();
+        self.allocator.destroy(self);
+    }
+
+    pub fn sink(self: *JsonlSink) SyscallSink {
+        return .{ .ptr = self, .vtable = &vtable, .name = "jsonl" };
+    }
+
+    const vtable = SyscallSink.VTable{
+        .append = append,
+        .flush = flush,
+    };
+
+    fn append(ptr: *anyopaque, trace_file: []const u8, pid: i32, syscall: Syscall) anyerror!void {
+        const self: *JsonlSink = @ptrCast(@alignCast(ptr));
+        self.mutex.lock();
+        defer self.mutex.unlock();
+
+        const writer = &self.file_writer.interface;
+        try writeRecord(writer, trace_file, pid, syscall);
+        try writer.writeByte('\n');
+        self.records += 1;
+    }
+
+    fn flush(ptr: *anyopaque) anyerror!void {
+        const self: *JsonlSink = @ptrCast(@alignCast(ptr));
+        self.mutex.lock();
+        defer self.mutex.unlock();
+
+        try self.file_writer.interface.flush();
+    }
+};
+
+/// Sink that keeps per-syscall counts, error counts and duration sums in memory
+/// Rows themselves are never stored; flush replaces the syscall_aggregates table
+/// Shared by all workers, so appends are serialized with a mutex
+pub const AggregatingSink = struct {
+    allocator: std.mem.Allocator,
+    db: *Database,
+    mutex: std.Thread.Mutex = .{},
+    totals: std.StringHashMapUnmanaged(Totals) = .{},
+
+    const Totals = struct {
+        count: i64 = 0,
+        error_count: i64 = 0,
+        total_duration: f64 = 0,
+    };
+
+    /// Aggregates are written through db, which must outlive the sink
+    pub fn init(allocator: std.mem.Allocator, db: *Database) AggregatingSink {
+        return .{ .allocator = allocator, .db = db };
+    }
+
+    pub fn deinit(self: *AggregatingSink) void {
+        var it = self.totals.keyIterator();
+        while (it.next()) |name| self.allocator.free(name.*);
+        self.totals.deinit(self.allocator);
+    }
+
+    pub fn sink(self: *AggregatingSink) SyscallSink {
+        return .{ .ptr = self, .vtable = &vtable, .name = "aggregate" };
+    }
+
+    const vtable = SyscallSink.VTable{
+        .append = append,
+        .flush = flush,
+    };
+
+    fn append(ptr: *anyopaque, trace_file: []const u8, pid: i32, syscall: Syscall) anyerror!void {
+        _ = trace_file;
+        _ = pid;
+        const self: *AggregatingSink = @ptrCast(@alignCast(ptr));
+        self.mutex.lock();
+        defer self.mutex.unlock();
+
+        const entry = try self.totals.getOrPut(self.allocator, syscall.syscall);
+        if (!entry.found_existing) {
+            // The key must outlive the line buffer it points into
+            entry.key_ptr.* = self.allocator.dupe(u8, syscall.syscall) catch |err| {
+                self.totals.removeByPtr(entry.key_ptr);
+                return err;
+            };
+            entry.value_ptr.* = .{};
+        }
+
+        const totals = entry.value_ptr;
+        totals.count += 1;
+        if (syscall.error_code != null) totals.error_count += 1;
+        totals.total_duration += syscall.duration orelse 0;
+    }
+
+    fn flush(ptr: *anyopaque) anyerror!void {
+        const self: *AggregatingSink = @ptrCast(@alignCast(ptr));
+        self.mutex.lock();
+        defer self.mutex.unlock();
+
+        const rows = try self.allocator.alloc(database.SyscallAggregate, self.totals.count());
+        defer self.allocator.free(rows);
+
+        var it = self.totals.iterator();
+        var i: usize = 0;
+        while (it.next()) |entry| : (i += 1) {
+            rows[i] = .{
+                .syscall = entry.key_ptr.*,
+                .count = entry.value_ptr.count,
+                .error_count = entry.value_ptr.error_count,
+                .total_duration = entry.value_ptr.total_duration,
+            };
+        }
+        std.mem.sort(database.SyscallAggregate, rows, {}, aggregateLessThan);
+
+        try self.db.writeAggregates(rows);
+    }
+
+    fn aggregateLessThan(_: void, a: database.SyscallAggregate, b: database.SyscallAggregate) bool {
+        return std.mem.lessThan(u8, a.syscall, b.syscall);
+    }
+};
+
+/// Sink that keeps every row in memory (see file_processor.parseFileToList)
+/// Strings are copied into the arena because the processor reuses its line buffers;
+/// each row's pid is set to the effective row PID. Not for use by several workers
+pub const CollectingSink = struct {
+    arena: std.mem.Allocator,
+    rows: std.ArrayListUnmanaged(Syscall) = .{},
+
+    pub fn init(arena: std.mem.Allocator) CollectingSink {
+        return .{ .arena = arena };
+    }
+
+    pub fn sink(self: *CollectingSink) SyscallSink {
+        return .{ .ptr = self, .vtable = &vtable, .name = "memory" };
+    }
+
+    const vtable = SyscallSink.VTable{
+        .append = append,
+        .flush = flush,
+    };
+
+    fn append(ptr: *anyopaque, trace_file: []const u8, pid: i32, syscall: Syscall) anyerror!void {
+        _ = trace_file;
+        const self: *CollectingSink = @ptrCast(@alignCast(ptr));
+
+        var row = syscall;
+        inline for (std.meta.fields(Syscall)) |field| {
+            if (field.type == []const u8) {
+                @field(row, field.name) = try self.arena.dupe(u8, @field(syscall, field.name));
+            } else if (field.type == ?[]const u8) {
+                if (@field(syscall, field.name)) |text| @field(row, field.name) = try self.arena.dupe(u8, text);
+            }
+        }
+        row.pid = pid;
+        try self.rows.append(self.arena, row);
+    }
+
+    fn flush(ptr: *anyopaque) anyerror!void {
+        _ = ptr;
+    }
+};
+
+/// Serialize one row as a flat JSON object: trace_file, pid, then every Syscall field
+/// pid is the effective row PID, so the parsed prefix PID field is not repeated
+pub fn writeRecord(writer: *std.Io.Writer, trace_file: []const u8, pid: i32, syscall: Syscall) !void {
+    var json: std.json.Stringify = .{ .writer = writer };
+    try json.beginObject();
+    try json.objectField("trace_file");
+    try json.write(trace_file);
+    try json.objectField("pid");
+    try json.write(pid);
+    inline for (std.meta.fields(Syscall)) |field| {
+        if (comptime std.mem.eql(u8, field.name, "pid")) continue;
+        try json.objectField(field.name);
+        try json.write(@field(syscall, field.name));
+    }
+    try json.endObject();
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+test "writeRecord emits a flat JSON object" {
+    var buffer: [2048]u8 = undefined;
+    var writer = std.Io.Writer.fixed(&buffer);
+
+    const syscall = Syscall.init("10:23:45.123456", "open", "\"/tmp/file\", O_RDONLY", -1, "ENOENT", "No such file", 0.5, false, false);
+    try writeRecord(&writer, "trace.1234", 1234, syscall);
+
+    const json = writer.buffered();
+    try std.testing.expect(std.mem.startsWith(u8, json, "{\"trace_file\":\"trace.1234\",\"pid\":1234,\"timestamp\":\"10:23:45.123456\""));
+    try std.testing.expect(std.mem.indexOf(u8, json, "\"args\":\"\\\"/tmp/file\\\", O_RDONLY\"") != null);
+    try std.testing.expect(std.mem.indexOf(u8, json, "\"error_code\":\"ENOENT\"") != null);
+    try std.testing.expect(std.mem.indexOf(u8, json, "\"duration\":0.5") != null);
+}
+
+test "database and jsonl sinks receive the same rows" {
+    const test_dir = "zig-cache/test-sinks";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    const jsonl_path = "zig-cache/test-sinks/out.jsonl";
+    const jsonl = try JsonlSink.create(std.testing.allocator, jsonl_path);
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+    try db.beginAppend();
+
+    const sinks = [_]SyscallSink{ databaseSink(&db), jsonl.sink() };
+    const syscall = Syscall.init("10:00:00.000001", "close", "3", 0, null, null, 0.001, false, false);
+    for (0..3) |_| {
+        for (sinks) |s| try s.append("trace.1", 1, syscall);
+    }
+    for (sinks) |s| try s.flush();
+
+    try db.endAppend();
+    try std.testing.expectEqual(@as(usize, 3), jsonl.records);
+    jsonl.destroy();
+
+    const contents = try std.fs.cwd().readFileAlloc(std.testing.allocator, jsonl_path, 1024 * 1024);
+    defer std.testing.allocator.free(contents);
+    try std.testing.expectEqual(@as(usize, 3), std.mem.count(u8, contents, "\n"));
+    try std.testing.expectEqual(@as(i64, 3), try db.getSyscallCount());
+}
+
+test "collecting sink keeps copies of the rows" {
+    var arena = std.heap.ArenaAllocator.init(std.testing.allocator);
+    defer arena.deinit();
+    var collected = CollectingSink.init(arena.allocator());
+
+    var args = "3, \"x\", 1".*;
+    try collected.sink().append("trace.1", 7, Syscall.init("10:00:00.000001", "read", &args, 1, null, null, 0.5, false, false));
+    // The processor overwrites its line buffer with the next line
+    @memset(&args, ' ');
+
+    try std.testing.expectEqual(@as(usize, 1), collected.rows.items.len);
+    try std.testing.expectEqualStrings("3, \"x\", 1", collected.rows.items[0].args);
+    try std.testing.expectEqual(@as(?i32, 7), collected.rows.items[0].pid);
+}
+
+test "aggregating sink matches a full ingest summary" {
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+    try db.beginAppend();
+
+    var aggregates = AggregatingSink.init(std.testing.allocator, &db);
+    defer aggregates.deinit();
+
+    const sinks = [_]SyscallSink{ databaseSink(&db), aggregates.sink() };
+    const rows = [_]Syscall{
+        Syscall.init("10:00:00.000001", "open", "\"/a\", O_RDONLY", 3, null, null, 0.25, false, false),
+        Syscall.init("10:00:00.000002", "open", "\"/b\", O_RDONLY", -1, "ENOENT", "No such file", 0.5, false, false),
+        Syscall.init("10:00:00.000003", "read", "3, \"x\", 1", 1, null, null, 0.125, false, false),
+        Syscall.init("10:00:00.000004", "read", "3, ", null, null, null, null, true, false),
+        Syscall.init("10:00:00.000005", "close", "3", 0, null, null, 1.0, false, false),
+    };
+    for (rows) |row| {
+        for (sinks) |s| try s.append("trace.1", 1, row);
+    }
+    try db.endAppend();
+    try aggregates.sink().flush();
+
+    try std.testing.expectEqual(@as(i64, 3), try db.queryInt64("SELECT COUNT(*) FROM syscall_aggregates"));
+    const matching = try db.queryInt64(
+        \\SELECT COUNT(*) FROM syscall_aggregates a
+        \\JOIN (
+        \\    SELECT syscall, COUNT(*) AS n, COUNT(error_code) AS e, COALESCE(SUM(duration), 0) AS d
+        \\    FROM syscalls GROUP BY syscall
+        \\) s USING (syscall)
+        \\WHERE a.count = s.n AND a.error_count = s.e AND abs(a.total_duration - s.d) < 1e-9
+    );
+    try std.testing.expectEqual(@as(i64, 3), matching);
+}
@@ -0,0 +1,130 @@
+const std = @import("std");
+const types = @import("types.zig");
+const parser = @import("parser.zig");
+const Syscall = types.Syscall;
+
+/// Maps thread IDs to the process (thread group leader) they belong to
+///
+/// With `strace -f` the `[pid N]` prefix is a TID. A thread is only linked to its
+/// process when the trace shows the clone/clone3 that created it with CLONE_THREAD;
+/// any other ID is its own process, so single-threaded traces keep pid == tid.
+/// Lines a new thread prints before its creating clone returns keep their TID as pid.
+pub const ThreadGroups = struct {
+    allocator: std.mem.Allocator,
+    leaders: std.AutoHashMapUnmanaged(i32, i32) = .{},
+    /// TIDs with an unfinished CLONE_THREAD clone, whose flags are not repeated on the resumed line
+    pending_clones: std.AutoHashMapUnmanaged(i32, void) = .{},
+
+    pub fn init(allocator: std.mem.Allocator) ThreadGroups {
+        return .{ .allocator = allocator };
+    }
+
+    pub fn deinit(self: *ThreadGroups) void {
+        self.leaders.deinit(self.allocator);
+        self.pending_clones.deinit(self.allocator);
+    }
+
+    /// Learn from a row issued by tid; only thread-creating clones change anything
+    pub fn observe(self: *ThreadGroups, tid: i32, syscall: Syscall) !void {
+        if (!isClone(syscall.syscall)) return;
+
+        const creates_thread = std.mem.indexOf(u8, syscall.args, "CLONE_THREAD") != null;
+        if (syscall.unfinished) {
+            if (creates_thread) try self.pending_clones.put(self.allocator, tid, {});
+            return;
+        }
+        const pending = syscall.resumed and self.pending_clones.remove(tid);
+
+        const child = syscall.return_value orelse return;
+        if (child <= 0 or !(creates_thread or pending)) return;
+        const child_tid = std.math.cast(i32, child) orelse return;
+        try self.leaders.put(self.allocator, child_tid, self.leaderOf(tid));
+    }
+
+    /// Every thread linked to its process so far; caller owns the slice
+    pub fn links(self: *const ThreadGroups, allocator: std.mem.Allocator) ![]Link {
+        const known = try allocator.alloc(Link, self.leaders.count());
+        var it = self.leaders.iterator();
+        var i: usize = 0;
+        while (it.next()) |entry| : (i += 1) known[i] = .{ .tid = entry.key_ptr.*, .leader = entry.value_ptr.* };
+        return known;
+    }
+
+    /// Start from links learned elsewhere, e.g. before the start of a split range
+    pub fn seed(self: *ThreadGroups, known: []const Link) !void {
+        for (known) |link| try self.leaders.put(self.allocator, link.tid, link.leader);
+    }
+
+    /// Process ID for a thread ID; unknown IDs are their own leader
+    pub fn leaderOf(self: *const ThreadGroups, tid: i32) i32 {
+        return self.leaders.get(tid) orelse tid;
+    }
+};
+
+/// A thread and the process it belongs to
+pub const Link = struct {
+    tid: i32,
+    leader: i32,
+};
+
+fn isClone(name: []const u8) bool {
+    return std.mem.eql(u8, name, "clone") or std.mem.eql(u8, name, "clone3");
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+fn parseTestLine(line: []const u8) !Syscall {
+    return (try parser.parseLine(std.testing.allocator, line)).?;
+}
+
+test "threads created with CLONE_THREAD map to the creating process" {
+    var groups = ThreadGroups.init(std.testing.allocator);
+    defer groups.deinit();
+
+    try groups.observe(100, try parseTestLine("10:00:00.000001 clone3({flags=CLONE_VM|CLONE_FS|CLONE_FILES|CLONE_SIGHAND|CLONE_THREAD|CLONE_SYSVSEM, exit_signal=0}, 88) = 101"));
+    // A thread started by a thread still belongs to the original process
+    try groups.observe(101, try parseTestLine("10:00:00.000002 clone(child_stack=0x7f00, flags=CLONE_VM|CLONE_THREAD|CLONE_SIGHAND) = 102"));
+
+    try std.testing.expectEqual(@as(i32, 100), groups.leaderOf(100));
+    try std.testing.expectEqual(@as(i32, 100), groups.leaderOf(101));
+    try std.testing.expectEqual(@as(i32, 100), groups.leaderOf(102));
+}
+
+test "forked children and unknown IDs are their own process" {
+    var groups = ThreadGroups.init(std.testing.allocator);
+    defer groups.deinit();
+
+    try groups.observe(100, try parseTestLine("10:00:00.000001 clone(child_stack=NULL, flags=CLONE_CHILD_CLEARTID|CLONE_CHILD_SETTID|SIGCHLD) = 200"));
+    try groups.observe(100, try parseTestLine("10:00:00.000002 clone3({flags=CLONE_THREAD, exit_signal=0}, 88) = -1 EAGAIN (Resource temporarily unavailable)"));
+
+    try std.testing.expectEqual(@as(i32, 200), groups.leaderOf(200));
+    try std.testing.expectEqual(@as(i32, 7), groups.leaderOf(7));
+}
+
+test "split clone lines carry CLONE_THREAD from the unfinished half" {
+    var groups = ThreadGroups.init(std.testing.allocator);
+    defer groups.deinit();
+
+    try groups.observe(100, try parseTestLine("10:00:00.000001 clone(child_stack=0x7f00, flags=CLONE_VM|CLONE_THREAD <unfinished ...>"));
+    try groups.observe(100, try parseTestLine("10:00:00.000002 <... clone resumed>, parent_tid=[101], tls=0x7f01) = 101"));
+
+    try std.testing.expectEqual(@as(i32, 100), groups.leaderOf(101));
+    try std.testing.expectEqual(@as(usize, 0), groups.pending_clones.count());
+}
+
+test "seeded links carry over to a fresh set of groups" {
+    var groups = ThreadGroups.init(std.testing.allocator);
+    defer groups.deinit();
+    try groups.observe(100, try parseTestLine("10:00:00.000001 clone3({flags=CLONE_VM|CLONE_THREAD, exit_signal=0}, 88) = 101"));
+
+    const known = try groups.links(std.testing.allocator);
+    defer std.testing.allocator.free(known);
+
+    var later = ThreadGroups.init(std.testing.allocator);
+    defer later.deinit();
+    try later.seed(known);
+    try std.testing.expectEqual(@as(i32, 100), later.leaderOf(101));
+    try std.testing.expectEqual(@as(i32, 7), later.leaderOf(7));
+}
@@ -1,16 +1,133 @@
 const std = @import("std");
+const inputs = @import("inputs.zig");
+const proc = @import("proc.zig");
+const rejects = @import("rejects.zig");
+const report = @import("report.zig");
+
+/// What a trace line records
+pub const LineKind = enum {
+    syscall,
+    /// Signal delivery: --- SIGCHLD {si_signo=SIGCHLD, ...} ---
+    signal,
+    /// Process exit: +++ exited with 0 +++
+    exit,
+    /// Process termination by signal: +++ killed by SIGKILL +++
+    killed,
+};
 
 /// Represents a parsed system call from strace output
 pub const Syscall = struct {
+    // PID from a per-line prefix (strace -f); overrides the filename PID when set
+    pid: ?i32 = null,
     timestamp: []const u8,
+    // Microseconds since midnight parsed from timestamp (stored as TIME)
+    time_of_day_us: ?i64 = null,
+    // Microseconds since the epoch, from an epoch timestamp or --date plus time_of_day_us (stored as TIMESTAMP)
+    wall_time_us: ?i64 = null,
     syscall: []const u8,
     args: []const u8,
     return_value: ?i64, // null for "?"
+    // Return token that isn't a plain integer/hex, e.g. "{...}" or "[3, 4]"
+    return_value_raw: ?[]const u8 = null,
+    // Unsigned value of a hex return (addresses from mmap/brk); return_value is the signed reading
+    return_addr: ?u64 = null,
     error_code: ?[]const u8,
     error_message: ?[]const u8,
+    // Parenthetical after a successful return, e.g. "Timeout" from "= 0 (Timeout)"
+    return_annotation: ?[]const u8 = null,
     duration: ?f64, // in seconds
     unfinished: bool = false,
     resumed: bool = false,
+    // Non-syscall lines reuse the row shape: signal rows carry the signal name in `syscall`
+    kind: LineKind = .syscall,
+    // Set on killed rows for "+++ killed by SIGSEGV (core dumped) +++"
+    core_dumped: bool = false,
+
+    // Combined row built from an unfinished line and its resumed line (--reassemble)
+    reassembled: bool = false,
+    // The return was printed as `?`, so the call never returned (distinct from a value we couldn't parse)
+    return_unknown: bool = false,
+    // A string argument was cut short by strace (`"..."...`); rerun with a larger -s
+    truncated: bool = false,
+
+    // Decoded from args by --extract-signals (kill/tkill/tgkill/rt_sigqueueinfo)
+    target_pid: ?i64 = null,
+    signal: ?[]const u8 = null,
+
+    // Decoded from args by --extract-dup (dup/dup2/dup3)
+    oldfd: ?i32 = null,
+    newfd: ?i32 = null,
+
+    // Decoded from args by --extract-accept (accept/accept4)
+    peer_addr: ?[]const u8 = null,
+    peer_port: ?u16 = null,
+
+    // Decoded from args by --extract-clock (clock_gettime/gettimeofday)
+    clock_value_ns: ?i64 = null,
+    clock_id: ?[]const u8 = null,
+
+    // Decoded from args by --extract-listen (bind/listen)
+    bind_addr: ?[]const u8 = null,
+    bind_port: ?u16 = null,
+    backlog: ?i32 = null,
+
+    // Original args when --normalize-hex-addrs rewrote them
+    args_raw: ?[]const u8 = null,
+
+    // 1-based line in the source trace
+    line_number: ?i32 = null,
+
+    // Unmodified source line, set by --keep-raw
+    raw_line: ?[]const u8 = null,
+
+    // Environment size from the "/* N vars */" comment in execve/execveat args
+    env_count: ?i32 = null,
+
+    // Numeric value of error_code (see errno.zig); null for unknown names
+    errno: ?i32 = null,
+
+    // Leading integer argument, set by --extract-fd
+    fd: ?i32 = null,
+
+    // Global order within a run: (index of the trace in the sorted input list << 32) | line_number
+    seq: ?i64 = null,
+
+    // Seconds since the previous syscall, from strace -r (timestamp is then empty)
+    rel_time: ?f64 = null,
+
+    // Number of top-level arguments; null for unfinished/resumed halves and non-syscall rows
+    arg_count: ?i32 = null,
+
+    // Top-level arguments as a JSON array of strings, set by --args-json
+    args_json: ?[]const u8 = null,
+
+    // Thread that issued the call: the per-line prefix, or the filename ID (the row pid is its process)
+    tid: ?i32 = null,
+
+    // PROT_ and MAP_ (or MREMAP_) flag text of mmap/mmap2/mremap, set by --decode-mmap
+    mmap_prot: ?[]const u8 = null,
+    mmap_flags: ?[]const u8 = null,
+
+    // Permission bits of open/creat/chmod/mkdir-style calls, from the octal mode argument (--decode-mode)
+    mode: ?i32 = null,
+
+    // Socket address of connect/bind/accept/accept4 (--decode-sockaddr)
+    sa_family: ?[]const u8 = null,
+    // IPv4/IPv6 address or AF_UNIX path
+    sock_addr: ?[]const u8 = null,
+    sock_port: ?u16 = null,
+
+    // args (and args_raw) were cut to --max-args-len bytes
+    args_truncated: bool = false,
+
+    // duration in whole nanoseconds, read from the digits rather than the double (--duration-ns)
+    duration_ns: ?i64 = null,
+
+    // Process name looked up from --proc-dir
+    command: ?[]const u8 = null,
+
+    // Hour-of-day bucket of the timestamp, set by --partition-by hour
+    hour: ?u8 = null,
 
     /// Create a syscall with all fields initialized
     pub fn init(
@@ -38,17 +155,173 @@ pub const Syscall = struct {
     }
 };
 
+/// How the time field at the start of each line is read (--time-format)
+pub const TimeFormat = enum {
+    /// Colons mean a clock time (-t/-tt); a bare SECONDS.FRACTION is an epoch time (-ttt)
+    /// from 1e8 seconds up and relative (-r) below that
+    auto,
+    /// HH:MM:SS[.fraction] from -t/-tt, stored in timestamp
+    clock,
+    /// Seconds since the previous syscall from -r, stored in rel_time
+    relative,
+    /// Seconds since the epoch from -ttt, stored in timestamp and as wall_time (UTC)
+    epoch,
+};
+
+/// Which rows are kept, by unfinished/resumed state
+pub const RowFilter = enum {
+    all,
+    /// Only `<unfinished ...>` and `<... resumed>` rows
+    unfinished,
+    /// Only rows that completed on a single line
+    completed,
+
+    pub fn accepts(self: RowFilter, syscall: Syscall) bool {
+        const split = syscall.unfinished or syscall.resumed;
+        return switch (self) {
+            .all => true,
+            .unfinished => split,
+            .completed => !split,
+        };
+    }
+};
+
+/// Which rows are kept, by syscall name (--include-syscall / --exclude-syscall)
+/// Names may use `*` and `?` wildcards; with any includes only matching rows are kept
+/// and the excludes are ignored, otherwise rows matching an exclude are dropped
+pub const SyscallFilter = struct {
+    include: []const []const u8 = &.{},
+    exclude: []const []const u8 = &.{},
+
+    pub fn accepts(self: SyscallFilter, syscall: Syscall) bool {
+        if (self.include.len > 0) return matchesAny(self.include, syscall.syscall);
+        return !matchesAny(self.exclude, syscall.syscall);
+    }
+
+    fn matchesAny(patterns: []const []const u8, name: []const u8) bool {
+        for (patterns) |pattern| {
+            if (inputs.globMatch(pattern, name)) return true;
+        }
+        return false;
+    }
+};
+
+/// Sends the rows of matching files to their own table (--route pattern=table)
+/// The pattern uses `*` and `?` wildcards and is matched against the whole trace_file path
+/// and against its last component, so both `traces/nginx-*` and `nginx-*` work
+pub const Route = struct {
+    pattern: []const u8,
+    /// Checked with database.isValidTableName when the route is parsed
+    table: [:0]const u8,
+
+    pub fn matches(self: Route, trace_file: []const u8) bool {
+        return inputs.globMatch(self.pattern, trace_file) or inputs.globMatch(self.pattern, std.fs.path.basename(trace_file));
+    }
+};
+
+/// Options controlling how each parsed line is decoded before insertion
+pub const ProcessOptions = struct {
+    /// Remove ANSI escape sequences from each line before parsing
+    strip_ansi: bool = false,
+    /// Decode target_pid/signal for signal-sending syscalls
+    extract_signals: bool = false,
+    /// Decode oldfd/newfd for dup/dup2/dup3
+    extract_dup: bool = false,
+    /// Decode peer_addr/peer_port for accept/accept4
+    extract_accept: bool = false,
+    /// Decode clock_value_ns/clock_id for clock_gettime/gettimeofday
+    extract_clock: bool = false,
+    /// Decode bind_addr/bind_port for bind and backlog for listen
+    extract_listen: bool = false,
+    /// Decode fd from a leading integer argument
+    extract_fd: bool = false,
+    /// Store the top-level arguments as a JSON array in args_json
+    args_json: bool = false,
+    /// Decode mmap_prot/mmap_flags for mmap, mmap2 and mremap
+    decode_mmap: bool = false,
+    /// Decode the octal mode of open, openat, creat, chmod, fchmod, fchmodat, mkdir, mkdirat, mknod, mknodat
+    decode_mode: bool = false,
+    /// Decode sa_family/sock_addr/sock_port for connect, bind, accept and accept4
+    decode_sockaddr: bool = false,
+    /// Drop rows not matching the filter before insertion
+    row_filter: RowFilter = .all,
+    /// Drop rows by syscall name before insertion
+    syscall_filter: SyscallFilter = .{},
+    /// Per-file target tables; files matching no route go to the database's own table
+    routes: []const Route = &.{},
+    /// Drop rows faster than this many seconds, and rows without a duration
+    min_duration: ?f64 = null,
+    /// Skip files larger than this many bytes
+    max_file_size: ?u64 = null,
+    /// Fill the command column from a /proc snapshot (shared by all workers)
+    comm_lookup: ?*proc.CommLookup = null,
+    /// Write lines that fail to parse to a sidecar file (shared by all workers)
+    rejects: ?*rejects.RejectsFile = null,
+    /// Collect per-file results for --stats-json (shared by all workers)
+    file_log: ?*report.FileLog = null,
+    /// Append rows to the database; off when only sinks such as aggregates are wanted
+    store_rows: bool = true,
+    /// Fill the hour column from each timestamp (--partition-by hour)
+    partition_by_hour: bool = false,
+    /// Store duration_ns, the exact integer nanoseconds of each duration, alongside duration
+    duration_ns: bool = false,
+    /// Store at most this many bytes of args (and args_raw), cut on a UTF-8 boundary
+    max_args_len: ?usize = null,
+    /// Replace pointer-like hex values in args so calls compare across ASLR runs
+    normalize_hex_addrs: bool = false,
+    /// Store the unmodified source line of each row in raw_line
+    keep_raw: bool = false,
+    /// Midnight of the --date base date, in microseconds since the epoch (UTC)
+    base_date_us: ?i64 = null,
+    /// Store each unfinished/resumed pair as one combined row
+    reassemble: bool = false,
+    /// With reassemble, also store the original unfinished and resumed rows
+    keep_fragments: bool = false,
+    /// Split plain files larger than this many bytes into line-aligned ranges for separate workers
+    split_chunk_size: ?u64 = null,
+    /// Flush each worker's appender after this many rows; null flushes only when the worker finishes
+    flush_every: ?usize = null,
+    /// Skip files whose content hash is already in file_hashes, and record hashes of new files
+    incremental: bool = false,
+    /// How the leading time field of each line is read
+    time_format: TimeFormat = .auto,
+    /// Advanced as lines of plain files are read, for the progress bar (shared by all workers)
+    bytes_read: ?*std.atomic.Value(u64) = null,
+    /// Set (e.g. on Ctrl-C) to stop reading; rows already read are still emitted and flushed
+    stop: ?*const std.atomic.Value(bool) = null,
+    /// Draw the pool's progress bar (off when several pools share the terminal)
+    show_progress: bool = true,
+
+    /// Table of the first route matching trace_file, or null when none does
+    pub fn tableFor(self: ProcessOptions, trace_file: []const u8) ?[:0]const u8 {
+        for (self.routes) |route| {
+            if (route.matches(trace_file)) return route.table;
+        }
+        return null;
+    }
+
+    /// Whether stop has been requested
+    pub fn stopRequested(self: ProcessOptions) bool {
+        const flag = self.stop orelse return false;
+        return flag.load(.seq_cst);
+    }
+};
+
 /// Statistics from processing a single trace file
 pub const FileStats = struct {
     total_lines: usize,
     parsed_lines: usize,
     failed_lines: usize,
+    skipped: bool, // File exceeded max_file_size and was not read
+    lossy_lines: usize = 0, // Lines with invalid UTF-8, stored with U+FFFD in place of the bad bytes
+    filtered_lines: usize = 0, // Parsed rows dropped by a row, syscall or duration filter
 
     pub fn init() FileStats {
         return .{
             .total_lines = 0,
             .parsed_lines = 0,
             .failed_lines = 0,
+            .skipped = false,
         };
     }
 };
@@ -61,6 +334,11 @@ pub const ParallelStats = struct {
     parsed_lines: usize,
     failed_lines: usize,
     files_with_errors: usize,
+    files_skipped: usize,
+    files_unchanged: usize = 0, // Already ingested with the same content (--incremental)
+    lossy_lines: usize = 0, // Lines with invalid UTF-8 bytes replaced (see FileStats)
+    filtered_lines: usize = 0, // Parsed rows dropped by filters (see FileStats)
+    interrupted: bool = false, // Stopped early through ProcessOptions.stop; counts cover what was read
 
     pub fn init() ParallelStats {
         return .{
@@ -70,6 +348,7 @@ pub const ParallelStats = struct {
             .parsed_lines = 0,
             .failed_lines = 0,
             .files_with_errors = 0,
+            .files_skipped = 0,
         };
     }
 };
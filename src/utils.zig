@@ -22,6 +22,220 @@ pub fn extractPidFromFilename(filename: []const u8) ?i32 {
     return null;
 }
 
+/// Drop a compression extension so trace.1234.gz (or .zst) yields the PID of trace.1234
+pub fn stripCompressionSuffix(filename: []const u8) []const u8 {
+    for ([_][]const u8{ ".gz", ".zst" }) |suffix| {
+        if (std.mem.endsWith(u8, filename, suffix)) return filename[0 .. filename.len - suffix.len];
+    }
+    return filename;
+}
+
+/// Remove ANSI escape sequences in place (CSI sequences such as SGR colors, and two-byte ESC sequences)
+/// Returns the shortened line; uses a simple byte scanner rather than a regex
+pub fn stripAnsi(line: []u8) []u8 {
+    var read: usize = 0;
+    var write: usize = 0;
+    while (read < line.len) {
+        if (line[read] == 0x1b) {
+            read += 1;
+            if (read < line.len and line[read] == '[') {
+                read += 1;
+                // Parameter and intermediate bytes, then a single final byte
+                while (read < line.len and line[read] >= 0x20 and line[read] <= 0x3f) : (read += 1) {}
+                if (read < line.len and line[read] >= 0x40 and line[read] <= 0x7e) read += 1;
+            } else if (read < line.len) {
+                read += 1; // two-byte escape such as ESC c
+            }
+            continue;
+        }
+        line[write] = line[read];
+        write += 1;
+        read += 1;
+    }
+    return line[0..write];
+}
+
+/// Copy line into buffer with every byte that is not part of a valid UTF-8 sequence
+/// replaced by U+FFFD; returns the copy, which lives until buffer is next cleared
+/// Callers check std.unicode.utf8ValidateSlice first, so valid lines are never copied
+pub fn replaceInvalidUtf8(allocator: std.mem.Allocator, line: []const u8, buffer: *std.ArrayListUnmanaged(u8)) ![]u8 {
+    const replacement = "\xEF\xBF\xBD";
+    buffer.clearRetainingCapacity();
+    try buffer.ensureTotalCapacity(allocator, line.len);
+
+    var i: usize = 0;
+    while (i < line.len) {
+        const len = std.unicode.utf8ByteSequenceLength(line[i]) catch 0;
+        if (len > 0 and i + len <= line.len) {
+            if (std.unicode.utf8Decode(line[i .. i + len])) |_| {
+                try buffer.appendSlice(allocator, line[i .. i + len]);
+                i += len;
+                continue;
+            } else |_| {}
+        }
+        try buffer.appendSlice(allocator, replacement);
+        i += 1;
+    }
+    return buffer.items;
+}
+
+/// Generate a random (version 4) UUID to tag the rows of one invocation
+/// Writes into the caller's buffer and returns it as a slice
+pub fn generateRunId(buffer: *[36]u8) []const u8 {
+    var bytes: [16]u8 = undefined;
+    std.crypto.random.bytes(&bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40; // version 4
+    bytes[8] = (bytes[8] & 0x3f) | 0x80; // RFC 4122 variant
+
+    const hex = "0123456789abcdef";
+    var out: usize = 0;
+    for (bytes, 0..) |b, i| {
+        if (i == 4 or i == 6 or i == 8 or i == 10) {
+            buffer[out] = '-';
+            out += 1;
+        }
+        buffer[out] = hex[b >> 4];
+        buffer[out + 1] = hex[b & 0x0f];
+        out += 2;
+    }
+    return buffer;
+}
+
+/// Placeholder written over pointer-like hex values by normalizeHexAddrs
+pub const hex_addr_placeholder = "0x<ptr>";
+
+/// Replace pointer-like hex values in args with hex_addr_placeholder, skipping string literals
+/// Only values >= 0x10000 (the default mmap_min_addr) are treated as addresses, so small
+/// constants such as flags or 0x0 survive. Writes into out, which must be at least args.len bytes
+/// Returns null when nothing was replaced
+pub fn normalizeHexAddrs(args: []const u8, out: []u8) ?[]const u8 {
+    var changed = false;
+    var in_string = false;
+    var len: usize = 0;
+    var i: usize = 0;
+    while (i < args.len) {
+        const ch = args[i];
+        if (in_string) {
+            if (ch == '\\' and i + 1 < args.len) {
+                out[len] = ch;
+                out[len + 1] = args[i + 1];
+                len += 2;
+                i += 2;
+                continue;
+            }
+            if (ch == '"') in_string = false;
+        } else if (ch == '"') {
+            in_string = true;
+        } else if (ch == '0' and i + 1 < args.len and args[i + 1] == 'x' and
+            (i == 0 or !isIdentChar(args[i - 1])))
+        {
+            var end = i + 2;
+            while (end < args.len and std.ascii.isHex(args[end])) : (end += 1) {}
+            const is_token = end > i + 2 and (end == args.len or !isIdentChar(args[end]));
+            const value = if (is_token) std.fmt.parseInt(u64, args[i + 2 .. end], 16) catch std.math.maxInt(u64) else 0;
+            if (value >= 0x10000) {
+                @memcpy(out[len..][0..hex_addr_placeholder.len], hex_addr_placeholder);
+                len += hex_addr_placeholder.len;
+                i = end;
+                changed = true;
+                continue;
+            }
+        }
+        out[len] = ch;
+        len += 1;
+        i += 1;
+    }
+    return if (changed) out[0..len] else null;
+}
+
+/// Longest prefix of text that is at most max_len bytes and doesn't end inside a
+/// multi-byte UTF-8 character (--max-args-len)
+pub fn truncateUtf8(text: []const u8, max_len: usize) []const u8 {
+    if (text.len <= max_len) return text;
+    var end = max_len;
+    // Continuation bytes are 10xxxxxx; back up to the start of their character
+    while (end > 0 and text[end] & 0xC0 == 0x80) end -= 1;
+    return text[0..end];
+}
+
+fn isIdentChar(ch: u8) bool {
+    return std.ascii.isAlphanumeric(ch) or ch == '_';
+}
+
+/// Hour-of-day bucket (0-23) of a strace timestamp
+/// Handles wall-clock "HH:MM:SS[.frac]" (-t/-tt) and epoch "SECONDS[.frac]" (-ttt, UTC)
+pub fn timestampHour(timestamp: []const u8) ?u8 {
+    if (std.mem.indexOfScalar(u8, timestamp, ':')) |colon| {
+        const hour = std.fmt.parseInt(u8, timestamp[0..colon], 10) catch return null;
+        return if (hour < 24) hour else null;
+    }
+
+    const seconds_end = std.mem.indexOfScalar(u8, timestamp, '.') orelse timestamp.len;
+    const seconds = std.fmt.parseInt(u64, timestamp[0..seconds_end], 10) catch return null;
+    const hour: u8 = @intCast((seconds / std.time.s_per_hour) % 24);
+    return hour;
+}
+
+/// Parse a YYYY-MM-DD calendar date into days since 1970-01-01
+pub fn parseDate(text: []const u8) !i64 {
+    if (text.len != 10 or text[4] != '-' or text[7] != '-') return error.InvalidDate;
+    const year = std.fmt.parseInt(u16, text[0..4], 10) catch return error.InvalidDate;
+    const month = std.fmt.parseInt(u4, text[5..7], 10) catch return error.InvalidDate;
+    const day = std.fmt.parseInt(u5, text[8..10], 10) catch return error.InvalidDate;
+    if (month < 1 or month > 12 or day < 1) return error.InvalidDate;
+    if (day > std.time.epoch.getDaysInMonth(year, @enumFromInt(month))) return error.InvalidDate;
+
+    // Days-from-civil over 400-year eras (proleptic Gregorian calendar)
+    const y: i64 = @as(i64, year) - @intFromBool(month <= 2);
+    const era = @divFloor(y, 400);
+    const year_of_era = y - era * 400;
+    const shifted_month: i64 = if (month > 2) @as(i64, month) - 3 else @as(i64, month) + 9;
+    const day_of_year = @divFloor(153 * shifted_month + 2, 5) + day - 1;
+    const day_of_era = year_of_era * 365 + @divFloor(year_of_era, 4) - @divFloor(year_of_era, 100) + day_of_year;
+    return era * 146097 + day_of_era - 719468;
+}
+
+/// Parse a byte size with an optional binary unit suffix (case-insensitive)
+/// Accepts plain bytes ("1048576") or B, K/KB, M/MB, G/GB, T/TB ("500MB" = 500 * 1024^2)
+pub fn parseSize(text: []const u8) !u64 {
+    const trimmed = std.mem.trim(u8, text, " ");
+    var digits_end: usize = 0;
+    while (digits_end < trimmed.len and std.ascii.isDigit(trimmed[digits_end])) : (digits_end += 1) {}
+    if (digits_end == 0) return error.InvalidSize;
+
+    const value = std.fmt.parseInt(u64, trimmed[0..digits_end], 10) catch return error.InvalidSize;
+    const suffix = trimmed[digits_end..];
+
+    const units = [_]struct { []const u8, []const u8, u6 }{
+        .{ "", "b", 0 },
+        .{ "k", "kb", 10 },
+        .{ "m", "mb", 20 },
+        .{ "g", "gb", 30 },
+        .{ "t", "tb", 40 },
+    };
+    for (units) |unit| {
+        if (std.ascii.eqlIgnoreCase(suffix, unit[0]) or std.ascii.eqlIgnoreCase(suffix, unit[1])) {
+            return std.math.mul(u64, value, @as(u64, 1) << unit[2]) catch error.InvalidSize;
+        }
+    }
+    return error.InvalidSize;
+}
+
+/// Loose check for a DuckDB memory_limit value: a number followed by a unit ("4GB", "512MiB", "1.5 GB")
+/// DuckDB itself does the final parse, this only rejects obviously malformed values
+pub fn isMemoryLimit(text: []const u8) bool {
+    var i: usize = 0;
+    while (i < text.len and (std.ascii.isDigit(text[i]) or text[i] == '.')) : (i += 1) {}
+    if (i == 0) return false;
+    const unit = std.mem.trim(u8, text[i..], " ");
+
+    const units = [_][]const u8{ "b", "kb", "mb", "gb", "tb", "kib", "mib", "gib", "tib", "k", "m", "g", "t" };
+    for (units) |known| {
+        if (std.ascii.eqlIgnoreCase(unit, known)) return true;
+    }
+    return false;
+}
+
 // ============================================================================
 // TESTS
 // ============================================================================
@@ -37,6 +251,13 @@ test "extractPidFromFilename from filename with multiple dots" {
     try std.testing.expectEqual(@as(?i32, 99), extractPidFromFilename("a.b.c.d.99"));
 }
 
+test "stripCompressionSuffix keeps the PID extension reachable" {
+    try std.testing.expectEqualStrings("trace.1387679", stripCompressionSuffix("trace.1387679.gz"));
+    try std.testing.expectEqualStrings("trace.1387679", stripCompressionSuffix("trace.1387679"));
+    try std.testing.expectEqual(@as(?i32, 1387679), extractPidFromFilename(stripCompressionSuffix("trace.1387679.gz")));
+    try std.testing.expectEqual(@as(?i32, 1387679), extractPidFromFilename(stripCompressionSuffix("trace.1387679.zst")));
+}
+
 test "extractPidFromFilename returns null for no PID" {
     try std.testing.expectEqual(@as(?i32, null), extractPidFromFilename("no-pid-here.txt"));
     try std.testing.expectEqual(@as(?i32, null), extractPidFromFilename("trace.log"));
@@ -52,3 +273,115 @@ test "extractPidFromFilename returns null for no extension" {
     try std.testing.expectEqual(@as(?i32, null), extractPidFromFilename("noextension"));
     try std.testing.expectEqual(@as(?i32, null), extractPidFromFilename("trace"));
 }
+
+test "stripAnsi removes color codes" {
+    var line = "\x1b[1;32m10:23:45.123456\x1b[0m open(\"/tmp/file\", O_RDONLY) = \x1b[33m3\x1b[m".*;
+    try std.testing.expectEqualStrings("10:23:45.123456 open(\"/tmp/file\", O_RDONLY) = 3", stripAnsi(&line));
+}
+
+test "stripAnsi leaves clean lines untouched" {
+    var line = "10:23:45.123456 close(3) = 0 <0.000010>".*;
+    try std.testing.expectEqualStrings("10:23:45.123456 close(3) = 0 <0.000010>", stripAnsi(&line));
+
+    var trailing_escape = "abc\x1b".*;
+    try std.testing.expectEqualStrings("abc", stripAnsi(&trailing_escape));
+}
+
+test "replaceInvalidUtf8 keeps valid sequences and replaces stray bytes" {
+    var buffer = std.ArrayListUnmanaged(u8){};
+    defer buffer.deinit(std.testing.allocator);
+
+    try std.testing.expectEqualStrings("read(3, \"a\xEF\xBF\xBDb\", 3)", try replaceInvalidUtf8(std.testing.allocator, "read(3, \"a\xffb\", 3)", &buffer));
+    // Multi-byte characters survive; a truncated sequence is replaced byte by byte
+    try std.testing.expectEqualStrings("caf\xC3\xA9 \xEF\xBF\xBD\xEF\xBF\xBD", try replaceInvalidUtf8(std.testing.allocator, "caf\xC3\xA9 \xE2\x82", &buffer));
+    try std.testing.expect(std.unicode.utf8ValidateSlice(buffer.items));
+}
+
+test "generateRunId produces distinct version 4 UUIDs" {
+    var first_buffer: [36]u8 = undefined;
+    var second_buffer: [36]u8 = undefined;
+    const first = generateRunId(&first_buffer);
+    const second = generateRunId(&second_buffer);
+
+    try std.testing.expectEqual(@as(usize, 36), first.len);
+    try std.testing.expectEqual(@as(u8, '-'), first[8]);
+    try std.testing.expectEqual(@as(u8, '-'), first[13]);
+    try std.testing.expectEqual(@as(u8, '4'), first[14]);
+    try std.testing.expectEqual(@as(u8, '-'), first[18]);
+    try std.testing.expectEqual(@as(u8, '-'), first[23]);
+    try std.testing.expect(!std.mem.eql(u8, first, second));
+}
+
+test "parseSize accepts plain bytes and unit suffixes" {
+    try std.testing.expectEqual(@as(u64, 1234), try parseSize("1234"));
+    try std.testing.expectEqual(@as(u64, 1234), try parseSize("1234B"));
+    try std.testing.expectEqual(@as(u64, 4 * 1024), try parseSize("4k"));
+    try std.testing.expectEqual(@as(u64, 500 * 1024 * 1024), try parseSize("500MB"));
+    try std.testing.expectEqual(@as(u64, 2 * 1024 * 1024 * 1024), try parseSize("2gb"));
+}
+
+test "parseSize rejects malformed sizes" {
+    try std.testing.expectError(error.InvalidSize, parseSize(""));
+    try std.testing.expectError(error.InvalidSize, parseSize("MB"));
+    try std.testing.expectError(error.InvalidSize, parseSize("12XB"));
+    try std.testing.expectError(error.InvalidSize, parseSize("99999999999999999999"));
+    try std.testing.expectError(error.InvalidSize, parseSize("99999999999TB"));
+}
+
+test "timestampHour buckets wall-clock and epoch timestamps" {
+    try std.testing.expectEqual(@as(?u8, 10), timestampHour("10:23:45.123456"));
+    try std.testing.expectEqual(@as(?u8, 0), timestampHour("00:00:01"));
+    try std.testing.expectEqual(@as(?u8, 23), timestampHour("23:59:59.999999"));
+    // 1700000000 is 2023-11-14 22:13:20 UTC
+    try std.testing.expectEqual(@as(?u8, 22), timestampHour("1700000000.123456"));
+    try std.testing.expectEqual(@as(?u8, null), timestampHour("25:00:00"));
+    try std.testing.expectEqual(@as(?u8, null), timestampHour("garbage"));
+}
+
+test "normalizeHexAddrs makes runs with different addresses identical" {
+    var first_buffer: [128]u8 = undefined;
+    var second_buffer: [128]u8 = undefined;
+
+    const first = normalizeHexAddrs("0x7ffd3a2b1c40, 4096, PROT_READ, 0x7f12aa000000", &first_buffer).?;
+    const second = normalizeHexAddrs("0x7ffe99887760, 4096, PROT_READ, 0x7f8811000000", &second_buffer).?;
+
+    try std.testing.expectEqualStrings("0x<ptr>, 4096, PROT_READ, 0x<ptr>", first);
+    try std.testing.expectEqualStrings(first, second);
+}
+
+test "normalizeHexAddrs keeps strings and small constants" {
+    var buffer: [128]u8 = undefined;
+
+    try std.testing.expectEqual(@as(?[]const u8, null), normalizeHexAddrs("FUTEX_WAKE, 0x1, 0x0", &buffer));
+    try std.testing.expectEqual(@as(?[]const u8, null), normalizeHexAddrs("1, \"0x7ffd3a2b1c40 \\\" 0x7ffd\", 20", &buffer));
+    try std.testing.expectEqualStrings("\"at 0x7ffd3a2b1c40\", 0x<ptr>", normalizeHexAddrs("\"at 0x7ffd3a2b1c40\", 0x55d0c0de0000", &buffer).?);
+}
+
+test "truncateUtf8 cuts on a character boundary" {
+    try std.testing.expectEqualStrings("short", truncateUtf8("short", 10));
+    try std.testing.expectEqualStrings("abc", truncateUtf8("abcdef", 3));
+    // "é" is 2 bytes and "日" 3, so a cut inside either keeps the text before it
+    try std.testing.expectEqualStrings("caf", truncateUtf8("café", 4));
+    try std.testing.expectEqualStrings("café", truncateUtf8("café!", 5));
+    try std.testing.expectEqualStrings("x", truncateUtf8("x日本", 3));
+    try std.testing.expectEqualStrings("x日", truncateUtf8("x日本", 4));
+    try std.testing.expectEqualStrings("", truncateUtf8("日本", 2));
+}
+
+test "parseDate counts days since the epoch" {
+    try std.testing.expectEqual(@as(i64, 0), try parseDate("1970-01-01"));
+    try std.testing.expectEqual(@as(i64, 19723), try parseDate("2024-01-01"));
+    try std.testing.expectEqual(@as(i64, 19782), try parseDate("2024-02-29"));
+    try std.testing.expectError(error.InvalidDate, parseDate("2023-02-29"));
+    try std.testing.expectError(error.InvalidDate, parseDate("2024-13-01"));
+    try std.testing.expectError(error.InvalidDate, parseDate("2024/01/01"));
+}
+
+test "isMemoryLimit accepts sizes with units only" {
+    try std.testing.expect(isMemoryLimit("4GB"));
+    try std.testing.expect(isMemoryLimit("512MiB"));
+    try std.testing.expect(isMemoryLimit("1.5 gb"));
+    try std.testing.expect(!isMemoryLimit("4"));
+    try std.testing.expect(!isMemoryLimit("GB"));
+    try std.testing.expect(!isMemoryLimit("4GB'; DROP TABLE syscalls; --"));
+}
@@ -1,61 +1,185 @@
 const std = @import("std");
 const Database = @import("database.zig").Database;
+const InputError = @import("errors.zig").InputError;
+const isIn = @import("errors.zig").isIn;
 const file_processor = @import("file_processor.zig");
 const progress = @import("progress.zig");
 const AggregateProgress = progress.AggregateProgress;
+const sink = @import("sink.zig");
+const SyscallSink = sink.SyscallSink;
 const types = @import("types.zig");
 const ParallelStats = types.ParallelStats;
+const ProcessOptions = types.ProcessOptions;
+
+/// One unit of work: a whole file, or one range of a split file
+const WorkItem = struct {
+    path: []const u8,
+    /// Position of the file in the input list, shared by all of its ranges
+    file_index: usize,
+    range: ?file_processor.ByteRange = null,
+    /// Bytes of the item on disk, towards the progress total (0 for stdin)
+    size: u64 = 0,
+    /// Read through a decompressor, so its size is counted once the item is done
+    /// rather than line by line (see ProcessOptions.bytes_read)
+    compressed: bool = false,
+};
+
+/// Per input file, shared by the workers handling its ranges
+const FileProgress = struct {
+    /// Items of the file not yet processed successfully; it is complete once this reaches 0
+    items_left: std.atomic.Value(usize) = .init(0),
+    /// Set by the first item that fails, so the file is counted in files_with_errors once
+    failed: std.atomic.Value(bool) = .init(false),
+};
 
 /// Context passed to each worker thread
 const WorkerContext = struct {
     worker_id: usize,
     db_main: *const Database,
-    files: []const []const u8,
+    items: []const WorkItem,
+    /// Indexed by WorkItem.file_index
+    file_progress: []FileProgress,
     num_workers: usize,
     allocator: std.mem.Allocator,
+    options: ProcessOptions,
+    extra_sinks: []const SyscallSink, // Shared sinks fed alongside each worker's database
 
     // Atomic counters for progress tracking
+    items_done: *std.atomic.Value(usize),
     files_complete: *std.atomic.Value(usize),
     total_lines: *std.atomic.Value(usize),
     parsed_lines: *std.atomic.Value(usize),
     failed_lines: *std.atomic.Value(usize),
     files_with_errors: *std.atomic.Value(usize),
+    files_skipped: *std.atomic.Value(usize),
+    files_unchanged: *std.atomic.Value(usize),
+    bytes_read: *std.atomic.Value(u64),
+    lossy_lines: *std.atomic.Value(usize),
+    filtered_lines: *std.atomic.Value(usize),
 
     // Error reporting
     error_slot: *?anyerror,
 
+    /// Count item's file in files_with_errors, unless another of its ranges already did
+    fn fileFailed(self: @This(), item: WorkItem) void {
+        if (!self.file_progress[item.file_index].failed.swap(true, .seq_cst)) {
+            _ = self.files_with_errors.fetchAdd(1, .seq_cst);
+        }
+    }
+
+    /// Count item's file as processed once all of its ranges have succeeded
+    fn itemSucceeded(self: @This(), item: WorkItem) void {
+        if (self.file_progress[item.file_index].items_left.fetchSub(1, .seq_cst) == 1) {
+            _ = self.files_complete.fetchAdd(1, .seq_cst);
+        }
+    }
+
     fn run(self: @This()) !void {
         // Get database instance and create worker connection
         const db_instance = self.db_main.getDbInstance();
         var db = try Database.connectToInstance(db_instance);
         defer db.deinit();
+        db.run_id = self.db_main.run_id;
+        db.table = self.db_main.table;
+        db.flush_every = self.options.flush_every;
 
         // Begin appender ONCE for all files this worker will process
         // This reduces flush overhead from O(files) to O(workers)
-        try db.beginAppend();
+        if (self.options.store_rows) try db.beginAppend();
         defer db.endAppend() catch {}; // Always flush at end, even on error
 
-        // Process assigned files using round-robin distribution
-        // Worker 0 gets files 0, num_workers, 2*num_workers, ...
-        // Worker 1 gets files 1, num_workers+1, 2*num_workers+1, ...
+        // This worker's database connection first (unless rows are not stored), then any shared sinks
+        const db_sinks: usize = if (self.options.store_rows) 1 else 0;
+        const sinks = try self.allocator.alloc(SyscallSink, db_sinks + self.extra_sinks.len);
+        defer self.allocator.free(sinks);
+        if (self.options.store_rows) sinks[0] = sink.databaseSink(&db);
+        @memcpy(sinks[db_sinks..], self.extra_sinks);
+
+        // Process assigned items using round-robin distribution
+        // Worker 0 gets items 0, num_workers, 2*num_workers, ...
+        // Worker 1 gets items 1, num_workers+1, 2*num_workers+1, ...
+        // Ranges of one split file are adjacent, so they land on different workers
         var i = self.worker_id;
-        while (i < self.files.len) : (i += self.num_workers) {
-            const file_path = self.files[i];
+        while (i < self.items.len) : (i += self.num_workers) {
+            const item = self.items[i];
+            defer _ = self.items_done.fetchAdd(1, .seq_cst);
+            // Remaining items still count as done so the progress loop can finish
+            if (self.options.stopRequested()) continue;
+            var timer = try std.time.Timer.start();
+
+            // An unreadable file gets no hash and is left for the processor to report
+            var hash: ?u64 = null;
+            if (self.options.incremental and !std.mem.eql(u8, item.path, file_processor.stdin_path)) {
+                hash = file_processor.hashFile(item.path) catch null;
+                if (hash) |h| {
+                    const seen = db.hasFileHash(item.path, h) catch |err| blk: {
+                        self.error_slot.* = err;
+                        break :blk false;
+                    };
+                    if (seen) {
+                        _ = self.files_unchanged.fetchAdd(1, .seq_cst);
+                        _ = self.bytes_read.fetchAdd(item.size, .seq_cst);
+                        continue;
+                    }
+                }
+            }
+
+            // A --route may send this file to another table; the appender follows it
+            if (self.options.store_rows) {
+                db.useTable(self.options.tableFor(item.path) orelse self.db_main.table) catch |err| {
+                    self.fileFailed(item);
+                    self.error_slot.* = err;
+                    continue;
+                };
+            }
 
             // Process the file (disable per-file progress in parallel mode)
-            const stats = file_processor.processFile(self.allocator, &db, file_path) catch |err| {
-                // File processing failed, increment error counter
-                _ = self.files_with_errors.fetchAdd(1, .seq_cst);
+            const stats = file_processor.processFileRangeToSinks(self.allocator, sinks, item.path, item.file_index, item.range, self.options) catch |err| {
+                // File processing failed; a split file counts once, however many ranges fail
+                self.fileFailed(item);
                 // Store error for debugging (overwrites previous errors)
                 self.error_slot.* = err;
                 continue;
             };
 
+            // Each range of a split file gets its own row; GROUP BY trace_file to combine them
+            // A file skipped by --max-file-size was never read, so it gets no row
+            const duration_sec = @as(f64, @floatFromInt(timer.read())) / std.time.ns_per_s;
+            // A failure here is reported after the run, but must not stop the worker mid-list
+            if (!stats.skipped) {
+                db.recordFileStats(file_processor.traceFileName(item.path), stats, duration_sec) catch |err| {
+                    self.error_slot.* = err;
+                };
+            }
+            if (self.options.file_log) |files| {
+                files.record(item.path, stats, duration_sec) catch |err| {
+                    self.error_slot.* = err;
+                };
+            }
+
+            // Neither a skipped file nor a compressed stream counted its bytes while reading
+            if (stats.skipped or item.compressed) _ = self.bytes_read.fetchAdd(item.size, .seq_cst);
+
+            if (stats.skipped) {
+                _ = self.files_skipped.fetchAdd(1, .seq_cst);
+                continue;
+            }
+
+            // A file cut short must be read again by the next --incremental run
+            if (hash != null and self.options.stopRequested()) hash = null;
+            if (hash) |h| {
+                db.recordFileHash(item.path, h) catch |err| {
+                    self.error_slot.* = err;
+                };
+            }
+
             // Update atomic counters with results
-            _ = self.files_complete.fetchAdd(1, .seq_cst);
+            self.itemSucceeded(item);
             _ = self.total_lines.fetchAdd(stats.total_lines, .seq_cst);
             _ = self.parsed_lines.fetchAdd(stats.parsed_lines, .seq_cst);
             _ = self.failed_lines.fetchAdd(stats.failed_lines, .seq_cst);
+            _ = self.lossy_lines.fetchAdd(stats.lossy_lines, .seq_cst);
+            _ = self.filtered_lines.fetchAdd(stats.filtered_lines, .seq_cst);
         }
     }
 };
@@ -71,6 +195,16 @@ const ThreadWrapper = struct {
     }
 };
 
+/// Process multiple files in parallel using a worker thread pool with default options
+pub fn processFilesParallel(
+    allocator: std.mem.Allocator,
+    db_main: *Database,
+    files: []const []const u8,
+    num_workers: usize,
+) !ParallelStats {
+    return processFilesParallelWithOptions(allocator, db_main, files, num_workers, .{}, &.{});
+}
+
 /// Process multiple files in parallel using a worker thread pool
 ///
 /// Parameters:
@@ -78,14 +212,24 @@ const ThreadWrapper = struct {
 ///   - db_main: Main database connection (must own the database instance)
 ///   - files: Array of file paths to process
 ///   - num_workers: Number of worker threads to spawn
+///   - options: Per-line processing options shared by every worker
+///   - extra_sinks: Thread-safe sinks that receive every row in addition to the database
+///
+/// Concurrency: each worker opens its own connection to db_main's instance and keeps one
+/// appender for all of its files, so no lock is taken between rows. Only the extra sinks
+/// (JSONL, aggregates) serialize workers, through their own mutexes. Opening the database
+/// file a second time per worker instead is not an option: it would be a separate instance
+/// whose writes conflict with db_main's
 ///
 /// Returns:
 ///   Statistics about the parallel processing operation
-pub fn processFilesParallel(
+pub fn processFilesParallelWithOptions(
     allocator: std.mem.Allocator,
     db_main: *Database,
     files: []const []const u8,
     num_workers: usize,
+    options: ProcessOptions,
+    extra_sinks: []const SyscallSink,
 ) !ParallelStats {
     // Handle edge case: no files to process
     if (files.len == 0) {
@@ -94,15 +238,37 @@ pub fn processFilesParallel(
         return stats;
     }
 
-    // Determine actual number of workers (can't have more workers than files)
-    const actual_workers = @min(num_workers, files.len);
+    // Large plain files become several items when splitting is enabled
+    const items = try planWork(allocator, files, options);
+    defer freeWork(allocator, items);
+
+    // A split file is processed once all of its ranges are
+    const file_progress = try allocator.alloc(FileProgress, files.len);
+    defer allocator.free(file_progress);
+    @memset(file_progress, .{});
+    for (items) |item| file_progress[item.file_index].items_left.raw += 1;
+
+    // Determine actual number of workers (can't have more workers than items)
+    const actual_workers = @min(num_workers, items.len);
 
     // Initialize atomic counters
+    var items_done = std.atomic.Value(usize).init(0);
     var files_complete = std.atomic.Value(usize).init(0);
     var total_lines = std.atomic.Value(usize).init(0);
     var parsed_lines = std.atomic.Value(usize).init(0);
     var failed_lines = std.atomic.Value(usize).init(0);
     var files_with_errors = std.atomic.Value(usize).init(0);
+    var files_skipped = std.atomic.Value(usize).init(0);
+    var files_unchanged = std.atomic.Value(usize).init(0);
+    var lossy_lines = std.atomic.Value(usize).init(0);
+    var filtered_lines = std.atomic.Value(usize).init(0);
+    var bytes_read = std.atomic.Value(u64).init(0);
+
+    // Plain files report their progress as they are read
+    var worker_options = options;
+    worker_options.bytes_read = &bytes_read;
+    var total_bytes: u64 = 0;
+    for (items) |item| total_bytes += item.size;
 
     // Allocate thread and error arrays
     const threads = try allocator.alloc(std.Thread, actual_workers);
@@ -122,33 +288,41 @@ pub fn processFilesParallel(
             .ctx = WorkerContext{
                 .worker_id = i,
                 .db_main = db_main,
-                .files = files,
+                .items = items,
+                .file_progress = file_progress,
                 .num_workers = actual_workers,
                 .allocator = allocator,
+                .options = worker_options,
+                .extra_sinks = extra_sinks,
+                .items_done = &items_done,
                 .files_complete = &files_complete,
                 .total_lines = &total_lines,
                 .parsed_lines = &parsed_lines,
                 .failed_lines = &failed_lines,
                 .files_with_errors = &files_with_errors,
+                .files_skipped = &files_skipped,
+                .files_unchanged = &files_unchanged,
+                .lossy_lines = &lossy_lines,
+                .bytes_read = &bytes_read,
+                .filtered_lines = &filtered_lines,
                 .error_slot = &errors[i],
             },
         }});
     }
 
     // Show aggregate progress while workers are running
-    var aggregate_progress = AggregateProgress.init(files.len);
+    var aggregate_progress = AggregateProgress.init(items.len, total_bytes);
+    if (!options.show_progress) aggregate_progress.enabled = false;
     defer aggregate_progress.deinit();
 
-    // Progress loop: continue until all files are processed (success or error)
+    // Progress loop: continue until all items are processed (success, error or skipped)
     while (true) {
-        const complete = files_complete.load(.seq_cst);
-        const error_count = files_with_errors.load(.seq_cst);
+        const done = items_done.load(.seq_cst);
         const lines = total_lines.load(.seq_cst);
 
-        try aggregate_progress.render(complete, lines);
+        try aggregate_progress.render(done, lines, bytes_read.load(.seq_cst));
 
-        // Check if all files have been processed (either completed or errored)
-        if (complete + error_count >= files.len) {
+        if (done >= items.len) {
             break;
         }
 
@@ -162,24 +336,18 @@ pub fn processFilesParallel(
     }
 
     // Final progress update
-    const final_complete = files_complete.load(.seq_cst);
+    const final_done = items_done.load(.seq_cst);
     const final_lines = total_lines.load(.seq_cst);
-    try aggregate_progress.render(final_complete, final_lines);
+    try aggregate_progress.render(final_done, final_lines, bytes_read.load(.seq_cst));
     try aggregate_progress.finish();
 
     // Check for critical errors (return first error found)
     // Note: File-level errors are already tracked in files_with_errors counter
     for (errors) |maybe_err| {
         if (maybe_err) |err| {
-            // Check if this is a critical error (not a file-level error)
-            // File-level errors are expected and tracked separately
-            switch (err) {
-                error.FileNotFound,
-                error.AccessDenied,
-                error.LineTooLong,
-                => continue, // These are file-level errors, already counted
-                else => return err, // Critical error, propagate it
-            }
+            // File-level errors are expected and already counted; anything else is critical
+            if (isIn(InputError, err)) continue;
+            return err;
         }
     }
 
@@ -191,9 +359,68 @@ pub fn processFilesParallel(
         .parsed_lines = parsed_lines.load(.seq_cst),
         .failed_lines = failed_lines.load(.seq_cst),
         .files_with_errors = files_with_errors.load(.seq_cst),
+        .files_skipped = files_skipped.load(.seq_cst),
+        .files_unchanged = files_unchanged.load(.seq_cst),
+        .lossy_lines = lossy_lines.load(.seq_cst),
+        .filtered_lines = filtered_lines.load(.seq_cst),
+        .interrupted = options.stopRequested(),
     };
 }
 
+/// One item per file, or one per range for plain files over options.split_chunk_size
+/// Compressed files and stdin are never split; files the processor will skip or fail on are passed through whole
+/// Caller owns the returned slice and must free it with freeWork
+fn planWork(allocator: std.mem.Allocator, files: []const []const u8, options: ProcessOptions) ![]WorkItem {
+    var items = std.ArrayListUnmanaged(WorkItem){};
+    errdefer items.deinit(allocator);
+    errdefer for (items.items) |item| if (item.range) |range| allocator.free(range.thread_links);
+
+    for (files, 0..) |path, file_index| {
+        if (options.split_chunk_size) |chunk_size| {
+            if (try splittable(path, chunk_size, options)) {
+                const ranges = try file_processor.planRanges(allocator, path, chunk_size);
+                errdefer file_processor.freeRanges(allocator, ranges);
+                try items.ensureUnusedCapacity(allocator, ranges.len);
+                // Each range's thread links move to its item
+                defer allocator.free(ranges);
+                for (ranges) |range| {
+                    items.appendAssumeCapacity(.{
+                        .path = path,
+                        .file_index = file_index,
+                        .range = range,
+                        .size = range.end - range.start,
+                    });
+                }
+                continue;
+            }
+        }
+        // Unreadable files are left for the processor to report
+        const stdin = std.mem.eql(u8, path, file_processor.stdin_path);
+        try items.append(allocator, .{
+            .path = path,
+            .file_index = file_index,
+            .size = if (stdin) 0 else if (std.fs.cwd().statFile(path)) |stat| stat.size else |_| 0,
+            .compressed = !stdin and (file_processor.detectCompression(path) catch .none) != .none,
+        });
+    }
+    return items.toOwnedSlice(allocator);
+}
+
+fn freeWork(allocator: std.mem.Allocator, items: []const WorkItem) void {
+    for (items) |item| if (item.range) |range| allocator.free(range.thread_links);
+    allocator.free(items);
+}
+
+fn splittable(path: []const u8, chunk_size: u64, options: ProcessOptions) !bool {
+    if (std.mem.eql(u8, path, file_processor.stdin_path)) return false;
+    const stat = std.fs.cwd().statFile(path) catch return false;
+    if (stat.size <= chunk_size) return false;
+    if (options.max_file_size) |limit| {
+        if (stat.size > limit) return false;
+    }
+    return try file_processor.detectCompression(path) == .none;
+}
+
 // ============================================================================
 // TESTS
 // ============================================================================
@@ -345,6 +572,30 @@ test "parallel processing with single file" {
     try std.testing.expectEqual(@as(i64, 10), count);
 }
 
+test "parallel processing stops pulling work once stop is set" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-parallel-stop";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    const filename = test_dir ++ "/trace.5001";
+    try std.fs.cwd().writeFile(.{ .sub_path = filename, .data = "10:00:00.000001 close(3) = 0 <0.000001>\n" });
+
+    var db = try Database.init(test_dir ++ "/test.db");
+    defer db.deinit();
+
+    // Already set, as if Ctrl-C arrived before the workers started
+    var stop = std.atomic.Value(bool).init(true);
+    const files = [_][]const u8{ filename, filename };
+    const stats = try processFilesParallelWithOptions(allocator, &db, &files, 2, .{ .stop = &stop }, &.{});
+
+    try std.testing.expect(stats.interrupted);
+    try std.testing.expectEqual(@as(usize, 2), stats.total_files);
+    try std.testing.expectEqual(@as(usize, 0), stats.files_processed);
+    try std.testing.expectEqual(@as(i64, 0), try db.getSyscallCount());
+}
+
 test "parallel processing with empty file list" {
     const allocator = std.testing.allocator;
 
@@ -481,3 +732,350 @@ test "parallel processing with one corrupt file" {
     const count = try db.getSyscallCount();
     try std.testing.expectEqual(@as(i64, 3), count);
 }
+
+test "verify row count passes for a normal ingest" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-parallel-verify";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    var file_list = std.ArrayListUnmanaged([]const u8){};
+    defer file_list.deinit(allocator);
+    defer for (file_list.items) |fname| allocator.free(fname);
+
+    for (0..3) |file_idx| {
+        const filename = try std.fmt.allocPrint(allocator, "{s}/trace.{d}", .{ test_dir, file_idx + 4000 });
+        try file_list.append(allocator, filename);
+
+        const file = try std.fs.cwd().createFile(filename, .{});
+        defer file.close();
+        try file.writeAll("10:00:00.000001 getpid() = 1 <0.000001>\n");
+        try file.writeAll("not a syscall line\n");
+        try file.writeAll("10:00:00.000002 close(3) = 0 <0.000001>\n");
+    }
+
+    const db_path = try std.fmt.allocPrint(allocator, "{s}/test.db", .{test_dir});
+    defer allocator.free(db_path);
+
+    var db = try Database.init(db_path);
+    defer db.deinit();
+
+    const stats = try processFilesParallel(allocator, &db, file_list.items, 2);
+
+    const check = try db.verifyRowCount(stats.parsed_lines);
+    try std.testing.expect(check.passed());
+    try std.testing.expectEqual(@as(i64, 6), check.actual);
+    try std.testing.expectEqual(@as(i64, 0), check.delta());
+
+    // Every file has its own stats row
+    try std.testing.expectEqual(@as(i64, 3), try db.queryInt64("SELECT COUNT(*) FROM trace_files"));
+    try std.testing.expectEqual(@as(i64, 3), try db.queryInt64("SELECT SUM(failed_lines) FROM trace_files"));
+    // The stats rows name files the way the syscall rows do
+    try std.testing.expectEqual(@as(i64, 6), try db.queryInt64("SELECT COUNT(*) FROM syscalls JOIN trace_files USING (trace_file)"));
+}
+
+test "all rows from one invocation share the run id" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-parallel-runid";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    var file_list = std.ArrayListUnmanaged([]const u8){};
+    defer file_list.deinit(allocator);
+    defer for (file_list.items) |fname| allocator.free(fname);
+
+    for (0..4) |file_idx| {
+        const filename = try std.fmt.allocPrint(allocator, "{s}/trace.{d}", .{ test_dir, file_idx + 4100 });
+        try file_list.append(allocator, filename);
+
+        const file = try std.fs.cwd().createFile(filename, .{});
+        defer file.close();
+        try file.writeAll("10:00:00.000001 getpid() = 1 <0.000001>\n");
+        try file.writeAll("10:00:00.000002 close(3) = 0 <0.000001>\n");
+    }
+
+    const db_path = try std.fmt.allocPrint(allocator, "{s}/test.db", .{test_dir});
+    defer allocator.free(db_path);
+
+    var db = try Database.init(db_path);
+    defer db.deinit();
+    db.run_id = "test-run-42";
+
+    _ = try processFilesParallel(allocator, &db, file_list.items, 3);
+
+    try std.testing.expectEqual(@as(i64, 8), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE run_id = 'test-run-42'"));
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT COUNT(DISTINCT run_id) FROM syscalls"));
+}
+
+test "parallel processing fans out to database and jsonl sinks" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-parallel-sinks";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    var file_list = std.ArrayListUnmanaged([]const u8){};
+    defer file_list.deinit(allocator);
+    defer for (file_list.items) |fname| allocator.free(fname);
+
+    for (0..4) |file_idx| {
+        const filename = try std.fmt.allocPrint(allocator, "{s}/trace.{d}", .{ test_dir, file_idx + 4200 });
+        try file_list.append(allocator, filename);
+
+        const file = try std.fs.cwd().createFile(filename, .{});
+        defer file.close();
+        for (0..5) |line_idx| {
+            const line = try std.fmt.allocPrint(allocator, "10:00:00.{d:06} write(1, \"x\", 1) = 1 <0.000001>\n", .{line_idx});
+            defer allocator.free(line);
+            try file.writeAll(line);
+        }
+    }
+
+    const db_path = try std.fmt.allocPrint(allocator, "{s}/test.db", .{test_dir});
+    defer allocator.free(db_path);
+    const jsonl_path = try std.fmt.allocPrint(allocator, "{s}/out.jsonl", .{test_dir});
+    defer allocator.free(jsonl_path);
+
+    var db = try Database.init(db_path);
+    defer db.deinit();
+
+    const jsonl = try sink.JsonlSink.create(allocator, jsonl_path);
+    const stats = try processFilesParallelWithOptions(allocator, &db, file_list.items, 2, .{}, &.{jsonl.sink()});
+    jsonl.destroy();
+
+    const contents = try std.fs.cwd().readFileAlloc(allocator, jsonl_path, 1024 * 1024);
+    defer allocator.free(contents);
+
+    // Both outputs contain the same number of records
+    try std.testing.expectEqual(@as(usize, 20), stats.parsed_lines);
+    try std.testing.expectEqual(@as(i64, 20), try db.getSyscallCount());
+    try std.testing.expectEqual(@as(usize, 20), std.mem.count(u8, contents, "\n"));
+}
+
+test "max file size skips the larger file and ingests the smaller one" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-parallel-maxsize";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    const small_path = test_dir ++ "/trace.4300";
+    const large_path = test_dir ++ "/trace.4301";
+    {
+        const small = try std.fs.cwd().createFile(small_path, .{});
+        defer small.close();
+        try small.writeAll("10:00:00.000001 close(3) = 0 <0.000001>\n");
+
+        const large = try std.fs.cwd().createFile(large_path, .{});
+        defer large.close();
+        for (0..100) |_| try large.writeAll("10:00:00.000001 getpid() = 1 <0.000001>\n");
+    }
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    const files = [_][]const u8{ small_path, large_path };
+    const stats = try processFilesParallelWithOptions(allocator, &db, &files, 2, .{ .max_file_size = 1024 }, &.{});
+
+    try std.testing.expectEqual(@as(usize, 1), stats.files_processed);
+    try std.testing.expectEqual(@as(usize, 1), stats.files_skipped);
+    try std.testing.expectEqual(@as(usize, 0), stats.files_with_errors);
+    try std.testing.expectEqual(@as(i64, 1), try db.getSyscallCount());
+    try std.testing.expectEqual(@as(i64, 0), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE trace_file = 'trace.4301'"));
+}
+
+test "aggregate-only ingest writes aggregates without storing rows" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-parallel-aggregate";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    var file_list = std.ArrayListUnmanaged([]const u8){};
+    defer file_list.deinit(allocator);
+    defer for (file_list.items) |fname| allocator.free(fname);
+
+    for (0..3) |file_idx| {
+        const filename = try std.fmt.allocPrint(allocator, "{s}/trace.{d}", .{ test_dir, file_idx + 4400 });
+        try file_list.append(allocator, filename);
+
+        const file = try std.fs.cwd().createFile(filename, .{});
+        defer file.close();
+        try file.writeAll("10:00:00.000001 open(\"/missing\", O_RDONLY) = -1 ENOENT (No such file) <0.000010>\n");
+        try file.writeAll("10:00:00.000002 close(3) = 0 <0.000001>\n");
+    }
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    var aggregates = sink.AggregatingSink.init(allocator, &db);
+    defer aggregates.deinit();
+
+    const stats = try processFilesParallelWithOptions(allocator, &db, file_list.items, 2, .{ .store_rows = false }, &.{aggregates.sink()});
+    try aggregates.sink().flush();
+
+    try std.testing.expectEqual(@as(usize, 6), stats.parsed_lines);
+    try std.testing.expectEqual(@as(i64, 0), try db.getSyscallCount());
+    try std.testing.expectEqual(@as(i64, 3), try db.queryInt64("SELECT count FROM syscall_aggregates WHERE syscall = 'open'"));
+    try std.testing.expectEqual(@as(i64, 3), try db.queryInt64("SELECT error_count FROM syscall_aggregates WHERE syscall = 'open'"));
+    try std.testing.expectEqual(@as(i64, 0), try db.queryInt64("SELECT error_count FROM syscall_aggregates WHERE syscall = 'close'"));
+}
+
+test "splitting a large file matches processing it whole" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-parallel-split";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    const large_path = test_dir ++ "/trace.4500";
+    {
+        const file = try std.fs.cwd().createFile(large_path, .{});
+        defer file.close();
+        for (0..200) |line_idx| {
+            var line_buffer: [128]u8 = undefined;
+            try file.writeAll(try std.fmt.bufPrint(&line_buffer, "10:00:00.{d:06} write(1, \"x\", 1) = {d} <0.000001>\n", .{ line_idx, line_idx }));
+        }
+    }
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    const files = [_][]const u8{large_path};
+    const stats = try processFilesParallelWithOptions(allocator, &db, &files, 4, .{ .split_chunk_size = 1024 }, &.{});
+
+    try std.testing.expectEqual(@as(usize, 1), stats.files_processed);
+    try std.testing.expectEqual(@as(usize, 200), stats.parsed_lines);
+    try std.testing.expectEqual(@as(i64, 200), try db.getSyscallCount());
+    // Every row keeps the file's PID and its own line number, exactly once
+    try std.testing.expectEqual(@as(i64, 200), try db.queryInt64("SELECT COUNT(DISTINCT line_number) FROM syscalls WHERE pid = 4500"));
+    try std.testing.expectEqual(@as(i64, 0), try db.queryInt64("SELECT COUNT(*) FROM syscalls WHERE line_number <> return_value + 1"));
+}
+
+test "a split file is processed only if every range is, and fails once" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-parallel-split-error";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    // Many small ranges that load, then one holding a line over the 10MB limit
+    const large_path = test_dir ++ "/trace.4550";
+    {
+        const file = try std.fs.cwd().createFile(large_path, .{});
+        defer file.close();
+        for (0..200) |line_idx| {
+            var line_buffer: [128]u8 = undefined;
+            try file.writeAll(try std.fmt.bufPrint(&line_buffer, "10:00:00.{d:06} write(1, \"x\", 1) = {d} <0.000001>\n", .{ line_idx, line_idx }));
+        }
+        try file.writeAll("Z" ** (11 * 1024 * 1024));
+        try file.writeAll("\n");
+    }
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    const files = [_][]const u8{large_path};
+    const stats = try processFilesParallelWithOptions(allocator, &db, &files, 4, .{ .split_chunk_size = 1024 }, &.{});
+
+    try std.testing.expectEqual(@as(usize, 0), stats.files_processed);
+    try std.testing.expectEqual(@as(usize, 1), stats.files_with_errors);
+    try std.testing.expectEqual(@as(usize, 200), stats.parsed_lines);
+}
+
+test "seq orders rows by input file, then line" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-parallel-seq";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    const first_path = test_dir ++ "/trace.4600";
+    const second_path = test_dir ++ "/trace.4601";
+    try std.fs.cwd().writeFile(.{ .sub_path = first_path, .data = "10:00:00.000002 getpid() = 4600\n10:00:00.000003 close(3) = 0\n" });
+    try std.fs.cwd().writeFile(.{ .sub_path = second_path, .data = "10:00:00.000001 getpid() = 4601\n" });
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+
+    const files = [_][]const u8{ first_path, second_path };
+    _ = try processFilesParallelWithOptions(allocator, &db, &files, 2, .{}, &.{});
+
+    try std.testing.expectEqual(@as(i64, 1), try db.queryInt64("SELECT seq FROM syscalls WHERE pid = 4600 AND syscall = 'getpid'"));
+    try std.testing.expectEqual(@as(i64, 2), try db.queryInt64("SELECT seq FROM syscalls WHERE syscall = 'close'"));
+    try std.testing.expectEqual(@as(i64, (1 << 32) | 1), try db.queryInt64("SELECT seq FROM syscalls WHERE pid = 4601"));
+    try std.testing.expectEqual(@as(i64, 4601), try db.queryInt64("SELECT pid FROM syscalls ORDER BY seq DESC LIMIT 1"));
+}
+
+test "incremental runs skip files whose contents are unchanged" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-parallel-incremental";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    const files = [_][]const u8{ test_dir ++ "/trace.4300", test_dir ++ "/trace.4301" };
+    for (files) |path| {
+        try std.fs.cwd().writeFile(.{ .sub_path = path, .data = "10:00:00.000001 close(3) = 0 <0.000001>\n" });
+    }
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+    const options = ProcessOptions{ .incremental = true };
+
+    const first = try processFilesParallelWithOptions(allocator, &db, &files, 2, options, &.{});
+    try std.testing.expectEqual(@as(usize, 2), first.files_processed);
+    try std.testing.expectEqual(@as(usize, 0), first.files_unchanged);
+
+    const second = try processFilesParallelWithOptions(allocator, &db, &files, 2, options, &.{});
+    try std.testing.expectEqual(@as(usize, 0), second.files_processed);
+    try std.testing.expectEqual(@as(usize, 2), second.files_unchanged);
+    try std.testing.expectEqual(@as(i64, 2), try db.getSyscallCount());
+
+    // Only the edited file is loaded again
+    try std.fs.cwd().writeFile(.{ .sub_path = files[1], .data = "10:00:00.000001 close(4) = 0 <0.000001>\n" });
+    const third = try processFilesParallelWithOptions(allocator, &db, &files, 2, options, &.{});
+    try std.testing.expectEqual(@as(usize, 1), third.files_processed);
+    try std.testing.expectEqual(@as(usize, 1), third.files_unchanged);
+    try std.testing.expectEqual(@as(i64, 3), try db.getSyscallCount());
+}
+
+test "routes send each file's rows to the table its pattern names" {
+    const allocator = std.testing.allocator;
+
+    const test_dir = "zig-cache/test-parallel-routes";
+    try std.fs.cwd().makePath(test_dir);
+    defer std.fs.cwd().deleteTree(test_dir) catch {};
+
+    const files = [_][]const u8{
+        test_dir ++ "/nginx.4400",
+        test_dir ++ "/postgres.4401",
+        test_dir ++ "/nginx.4402",
+        test_dir ++ "/other.4403",
+    };
+    for (files) |path| {
+        try std.fs.cwd().writeFile(.{ .sub_path = path, .data = "10:00:00.000001 close(3) = 0 <0.000001>\n10:00:00.000002 getpid() = 1 <0.000001>\n" });
+    }
+
+    var db = try Database.init(":memory:");
+    defer db.deinit();
+    try db.addTable("web");
+    try db.addTable("pg");
+
+    // The basename and the full path both match
+    const routes = [_]types.Route{
+        .{ .pattern = "nginx.*", .table = "web" },
+        .{ .pattern = test_dir ++ "/postgres.*", .table = "pg" },
+    };
+    // One worker has to switch tables between its files
+    const stats = try processFilesParallelWithOptions(allocator, &db, &files, 2, .{ .routes = &routes }, &.{});
+
+    try std.testing.expectEqual(@as(usize, 0), stats.files_with_errors);
+    try std.testing.expectEqual(@as(i64, 4), try db.countRowsIn("web"));
+    try std.testing.expectEqual(@as(i64, 2), try db.countRowsIn("pg"));
+    try std.testing.expectEqual(@as(i64, 2), try db.getSyscallCount());
+    try std.testing.expectEqual(@as(i64, 0), try db.queryInt64("SELECT COUNT(*) FROM web WHERE trace_file NOT LIKE '%nginx%'"));
+    try std.testing.expectEqualStrings("syscalls", db.table);
+}